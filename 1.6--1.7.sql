@@ -0,0 +1,3050 @@
+CREATE TYPE webauthn.lockout_scope AS ENUM (
+  'credential',
+  'user_name'
+);
+
+COMMENT ON TYPE webauthn.lockout_scope IS 'Whether failed assertions are counted per credential_id or per user_name.';
+
+CREATE TYPE webauthn.token_binding_policy AS ENUM (
+  'ignore',
+  'forbid',
+  'require'
+);
+
+COMMENT ON TYPE webauthn.token_binding_policy IS 'Whether the tokenBinding status "present" is accepted, refused or required in the client data, https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-tokenbinding';
+
+CREATE TYPE webauthn.credential_mediation_requirement AS ENUM (
+  'silent',
+  'optional',
+  'conditional',
+  'required'
+);
+
+COMMENT ON TYPE webauthn.credential_mediation_requirement IS 'https://w3c.github.io/webappsec-credential-management/#enumdef-credentialmediationrequirement';
+
+CREATE TYPE webauthn.authenticator_attachment AS ENUM (
+  'platform',
+  'cross-platform'
+);
+
+COMMENT ON TYPE webauthn.authenticator_attachment IS 'https://www.w3.org/TR/webauthn-2/#enum-attachment';
+
+CREATE TYPE webauthn.public_key_credential_hint AS ENUM (
+  'security-key',
+  'client-device',
+  'hybrid'
+);
+
+COMMENT ON TYPE webauthn.public_key_credential_hint IS 'https://www.w3.org/TR/webauthn-3/#enum-hints';
+
+DROP FUNCTION webauthn.init_credential(bytea,text,bytea,text,text,text,boolean,webauthn.user_verification_requirement,webauthn.attestation_conveyance_preference,interval,timestamptz);
+DROP FUNCTION webauthn.store_credential(text,webauthn.credential_type,text,text,timestamptz);
+DROP FUNCTION webauthn.get_credentials(bytea,text,webauthn.user_verification_requirement,interval,text,timestamptz);
+DROP FUNCTION webauthn.verify_assertion(text,webauthn.credential_type,text,text,text,text,timestamptz);
+
+CREATE OR REPLACE FUNCTION webauthn.parse_authenticator_extensions(
+  authenticator_data bytea,
+  attested_credential_data_included boolean,
+  extension_data_included boolean
+)
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE sql
+AS $$
+-- https://www.w3.org/TR/webauthn-2/#sctn-authenticator-data
+-- The extensions CBOR map is the last item of authenticator_data,
+-- following the credential public key if attested credential data is included.
+-- The flags are given by the caller, which has already parsed and validated authenticator_data.
+SELECT CASE WHEN extension_data_included THEN
+  CASE WHEN attested_credential_data_included
+  THEN cbor.to_jsonb_array(
+    cbor := substring(authenticator_data,56+(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54)),
+    encode_binary_format := 'base64'
+  )->1
+  ELSE cbor.to_jsonb(
+    cbor := substring(authenticator_data,38),
+    encode_binary_format := 'base64'
+  )
+  END
+END
+$$;
+
+CREATE TABLE webauthn.lockout_policies (
+scope webauthn.lockout_scope NOT NULL,
+max_failures integer NOT NULL,
+failure_window interval NOT NULL,
+cooldown interval NOT NULL,
+PRIMARY KEY (scope),
+CONSTRAINT positive_max_failures CHECK (max_failures > 0),
+CONSTRAINT positive_failure_window CHECK (failure_window > '0'::interval),
+CONSTRAINT positive_cooldown CHECK (cooldown > '0'::interval)
+);
+
+SELECT pg_catalog.pg_extension_config_dump('lockout_policies', '');
+
+COMMENT ON TABLE webauthn.lockout_policies IS 'Thresholds for failed webauthn.verify_assertion() calls, after which webauthn.lockouts are created. No rows means lockouts are disabled.';
+
+COMMENT ON COLUMN webauthn.lockout_policies.scope IS 'Whether failures are counted per credential_id or per user_name';
+COMMENT ON COLUMN webauthn.lockout_policies.max_failures IS 'Number of failures within failure_window that triggers a lockout';
+COMMENT ON COLUMN webauthn.lockout_policies.failure_window IS 'How far back failures are counted';
+COMMENT ON COLUMN webauthn.lockout_policies.cooldown IS 'How long a triggered lockout lasts';
+
+ALTER TABLE webauthn.credentials ADD COLUMN revoked_at timestamptz;
+ALTER TABLE webauthn.credentials ADD CONSTRAINT revoked_after_credential CHECK (revoked_at >= credential_at);
+COMMENT ON COLUMN webauthn.credentials.revoked_at IS 'Timestamp of when the credential was revoked by webauthn.revoke_credential()';
+
+CREATE OR REPLACE FUNCTION webauthn.credential_challenge_cred_protect(challenge bytea)
+RETURNS integer
+STABLE
+LANGUAGE sql AS $$
+-- https://fidoalliance.org/specs/fido-v2.1-ps-20210615/fido-client-to-authenticator-protocol-v2.1-ps-20210615.html#sctn-credProtect-extension
+-- Only returns the requested level if enforceCredentialProtectionPolicy is true,
+-- since the client may otherwise create the credential with a lower level.
+SELECT CASE extensions->>'credentialProtectionPolicy'
+  WHEN 'userVerificationOptional' THEN 1
+  WHEN 'userVerificationOptionalWithCredentialIDList' THEN 2
+  WHEN 'userVerificationRequired' THEN 3
+END
+FROM webauthn.credential_challenges WHERE challenge = $1
+AND (extensions->'enforceCredentialProtectionPolicy')::boolean
+$$;
+
+ALTER TABLE webauthn.credential_challenges ADD COLUMN extensions jsonb;
+ALTER TABLE webauthn.credential_challenges ADD CONSTRAINT extensions_object CHECK (jsonb_typeof(extensions) = 'object');
+COMMENT ON COLUMN webauthn.credential_challenges.extensions IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialcreationoptions-extensions';
+
+ALTER TABLE webauthn.credentials ADD COLUMN authenticator_extension_outputs jsonb;
+UPDATE webauthn.credentials SET authenticator_extension_outputs = webauthn.parse_authenticator_extensions(decode(cbor.to_jsonb(cbor := attestation_object, encode_binary_format := 'base64')->>'authData','base64'), attested_credential_data_included, extension_data_included);
+ALTER TABLE webauthn.credentials ADD COLUMN client_extension_results jsonb;
+ALTER TABLE webauthn.credentials ADD CONSTRAINT cred_protect_at_least_requested CHECK (COALESCE((authenticator_extension_outputs->>'credProtect')::integer,1) >= webauthn.credential_challenge_cred_protect(challenge));
+ALTER TABLE webauthn.credentials ADD CONSTRAINT client_extension_results_object CHECK (jsonb_typeof(client_extension_results) = 'object');
+COMMENT ON COLUMN webauthn.credentials.authenticator_extension_outputs IS 'https://www.w3.org/TR/webauthn-2/#authenticator-extension-output';
+COMMENT ON COLUMN webauthn.credentials.client_extension_results IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredential-getclientextensionresults';
+
+ALTER TABLE webauthn.assertion_challenges ADD COLUMN extensions jsonb;
+ALTER TABLE webauthn.assertion_challenges ADD CONSTRAINT extensions_object CHECK (jsonb_typeof(extensions) = 'object');
+COMMENT ON COLUMN webauthn.assertion_challenges.extensions IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialrequestoptions-extensions';
+
+ALTER TABLE webauthn.credentials ADD COLUMN prf_enabled boolean GENERATED ALWAYS AS ((client_extension_results->'prf'->>'enabled')::boolean) STORED;
+COMMENT ON COLUMN webauthn.credentials.prf_enabled IS 'https://www.w3.org/TR/webauthn-3/#dom-authenticationextensionsprfoutputs-enabled';
+
+ALTER TABLE webauthn.assertions ADD COLUMN prf_salts jsonb;
+COMMENT ON COLUMN webauthn.assertions.prf_salts IS 'The PRF salts requested for the credential by webauthn.get_credentials(), https://www.w3.org/TR/webauthn-3/#dictdef-authenticationextensionsprfvalues';
+
+CREATE OR REPLACE FUNCTION webauthn.credential_challenge_large_blob_support(challenge bytea)
+RETURNS text
+STABLE
+LANGUAGE sql AS $$
+-- https://www.w3.org/TR/webauthn-2/#dom-authenticationextensionslargeblobinputs-support
+SELECT extensions->'largeBlob'->>'support' FROM webauthn.credential_challenges WHERE challenge = $1
+$$;
+
+ALTER TABLE webauthn.credentials ADD COLUMN large_blob_supported boolean GENERATED ALWAYS AS ((client_extension_results->'largeBlob'->>'supported')::boolean) STORED;
+ALTER TABLE webauthn.credentials ADD CONSTRAINT large_blob_supported_if_required CHECK (large_blob_supported IS TRUE OR webauthn.credential_challenge_large_blob_support(challenge) IS DISTINCT FROM 'required');
+COMMENT ON COLUMN webauthn.credentials.large_blob_supported IS 'https://www.w3.org/TR/webauthn-2/#dom-authenticationextensionslargebloboutputs-supported';
+
+ALTER TABLE webauthn.assertions ADD COLUMN client_extension_results jsonb;
+ALTER TABLE webauthn.assertions ADD CONSTRAINT client_extension_results_object CHECK (jsonb_typeof(client_extension_results) = 'object');
+COMMENT ON COLUMN webauthn.assertions.client_extension_results IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredential-getclientextensionresults';
+
+CREATE TABLE webauthn.large_blobs (
+credential_id bytea NOT NULL REFERENCES webauthn.credentials,
+version integer NOT NULL,
+blob_hash bytea NOT NULL,
+blob_size integer NOT NULL,
+challenge bytea NOT NULL REFERENCES webauthn.assertion_challenges,
+written_at timestamptz NOT NULL,
+PRIMARY KEY (credential_id, version),
+UNIQUE (challenge),
+CONSTRAINT positive_version CHECK (version > 0)
+);
+
+SELECT pg_catalog.pg_extension_config_dump('large_blobs', '');
+
+COMMENT ON TABLE webauthn.large_blobs IS 'Used by webauthn.verify_assertion() to store which blob version each credential holds, written with the largeBlob extension.';
+
+COMMENT ON COLUMN webauthn.large_blobs.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.large_blobs.version IS 'Incremented for every blob written to the credential';
+COMMENT ON COLUMN webauthn.large_blobs.blob_hash IS 'SHA-256 hash of the blob, https://www.w3.org/TR/webauthn-2/#dom-authenticationextensionslargeblobinputs-write';
+COMMENT ON COLUMN webauthn.large_blobs.blob_size IS 'Size of the blob in bytes';
+COMMENT ON COLUMN webauthn.large_blobs.challenge IS 'https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-challenge';
+COMMENT ON COLUMN webauthn.large_blobs.written_at IS 'Timestamp of when the write was confirmed by webauthn.verify_assertion()';
+
+CREATE POLICY relying_party_role ON webauthn.large_blobs USING (credential_id IN (SELECT credentials.credential_id FROM webauthn.credentials));
+
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_relying_party_id(challenge bytea)
+RETURNS text
+STABLE
+LANGUAGE sql AS $$
+SELECT relying_party_id FROM webauthn.assertion_challenges WHERE challenge = $1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.credential_appid(credential_id bytea)
+RETURNS text
+STABLE
+LANGUAGE sql AS $$
+SELECT appid FROM webauthn.credentials WHERE credential_id = $1
+$$;
+
+ALTER TABLE webauthn.credentials ADD COLUMN appid text;
+ALTER TABLE webauthn.credentials ADD CONSTRAINT rp_id_hash_appid CHECK (rp_id_hash = public.digest(appid,'sha256'));
+COMMENT ON COLUMN webauthn.credentials.appid IS 'The AppID of a U2F registration imported by webauthn.import_u2f_credential(), https://www.w3.org/TR/webauthn-2/#sctn-appid-extension';
+
+ALTER TABLE webauthn.assertions ADD CONSTRAINT rp_id_hash_matches CHECK (webauthn.credential_appid(credential_id) IS NULL OR COALESCE(
+  rp_id_hash = public.digest(webauthn.assertion_challenge_relying_party_id(challenge),'sha256')
+  OR (client_extension_results->'appid')::boolean AND rp_id_hash = public.digest(webauthn.credential_appid(credential_id),'sha256'),
+FALSE));
+
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_payment(challenge bytea)
+RETURNS jsonb
+STABLE
+LANGUAGE sql AS $$
+SELECT payment FROM webauthn.assertion_challenges WHERE challenge = $1
+$$;
+
+ALTER TABLE webauthn.assertion_challenges ADD COLUMN payment jsonb;
+ALTER TABLE webauthn.assertion_challenges ADD CONSTRAINT payment_payee CHECK (payment ?| ARRAY['payeeName','payeeOrigin']);
+COMMENT ON COLUMN webauthn.assertion_challenges.payment IS 'The transaction details expected to be confirmed, set by webauthn.get_payment_credentials(), https://www.w3.org/TR/secure-payment-confirmation/#sctn-collectedclientadditionalpaymentdata-dictionary';
+
+ALTER TABLE webauthn.assertions DROP CONSTRAINT client_data_json_type;
+ALTER TABLE webauthn.assertions ADD CONSTRAINT client_data_json_type CHECK (CASE WHEN webauthn.assertion_challenge_payment(challenge) IS NULL THEN 'webauthn.get' ELSE 'payment.get' END = webauthn.from_utf8(client_data_json)::jsonb->>'type');
+ALTER TABLE webauthn.assertions ADD COLUMN payment jsonb GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->'payment') STORED;
+ALTER TABLE webauthn.assertions ADD CONSTRAINT payment_matches CHECK (payment - 'topOrigin' IS NOT DISTINCT FROM webauthn.assertion_challenge_payment(challenge));
+COMMENT ON COLUMN webauthn.assertions.payment IS 'The transaction confirmed with Secure Payment Confirmation, https://www.w3.org/TR/secure-payment-confirmation/#sctn-collectedclientadditionalpaymentdata-dictionary';
+
+CREATE OR REPLACE FUNCTION webauthn.canonical_json_number(number float8)
+RETURNS text
+IMMUTABLE
+SET extra_float_digits = 1
+LANGUAGE sql AS $$
+--
+-- https://www.rfc-editor.org/rfc/rfc8785#section-3.2.2.3
+-- Formats the number like ECMAScript's Number.prototype.toString(),
+-- i.e. from the fewest significant digits that round-trip, in its notation.
+-- float8out doesn't always return the fewest digits, e.g. for large integers,
+-- so its digits are rounded to the fewest that still round-trip.
+-- Rounding up beyond the largest float8 would fail to cast back, so isn't tried.
+--
+WITH float8out AS (
+  SELECT
+    abs(number)::text::numeric AS value,
+    length(parts[1]) + COALESCE(parts[3]::integer, 0)
+      - (length(parts[1] || COALESCE(parts[2], '')) - length(ltrim(parts[1] || COALESCE(parts[2], ''), '0'))) AS n
+  FROM regexp_match(abs(number)::text, '^(\d+)(?:\.(\d+))?(?:e([+-]\d+))?$') AS parts
+), shortest AS (
+  --
+  -- The digits without leading and trailing zeros,
+  -- and the decimal exponent n, i.e. the number is 0.digits * 10^n
+  --
+  SELECT
+    trim(BOTH '0' FROM decimal.integer_part || decimal.fraction_part) AS digits,
+    CASE
+      WHEN decimal.integer_part <> '0' THEN length(decimal.integer_part)
+      ELSE length(ltrim(decimal.fraction_part, '0')) - length(decimal.fraction_part)
+    END AS n
+  FROM float8out
+  CROSS JOIN generate_series(1, 17) AS precision
+  CROSS JOIN LATERAL round(float8out.value, precision - float8out.n) AS rounded
+  CROSS JOIN LATERAL (
+    SELECT split_part(rounded::text, '.', 1) AS integer_part, split_part(rounded::text, '.', 2) AS fraction_part
+  ) AS decimal
+  WHERE CASE WHEN rounded <= float8out.value OR abs(number) < 1e308 THEN rounded::float8 = abs(number) END
+  ORDER BY precision
+  LIMIT 1
+)
+SELECT CASE
+  WHEN number = 0 THEN '0'
+  ELSE CASE WHEN number < 0 THEN '-' ELSE '' END || CASE
+    WHEN length(digits) <= n AND n <= 21 THEN digits || repeat('0', n - length(digits))
+    WHEN 0 < n AND n <= 21 THEN left(digits, n) || '.' || substr(digits, n + 1)
+    WHEN -6 < n AND n <= 0 THEN '0.' || repeat('0', -n) || digits
+    ELSE left(digits, 1) || CASE WHEN length(digits) > 1 THEN '.' || substr(digits, 2) ELSE '' END
+      || 'e' || CASE WHEN n > 0 THEN '+' ELSE '-' END || abs(n - 1)
+  END
+END
+FROM shortest
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.canonical_json(value jsonb)
+RETURNS text
+IMMUTABLE
+LANGUAGE sql AS $$
+--
+-- https://www.rfc-editor.org/rfc/rfc8785
+-- JSON Canonicalization Scheme: no whitespace, object members sorted by the UTF-16 code units of their keys,
+-- strings escaped like JSON.stringify(), which is what to_json() and the jsonb output function do,
+-- and numbers formatted like ECMAScript.
+--
+SELECT CASE jsonb_typeof(value)
+  WHEN 'object' THEN '{' || COALESCE((
+    SELECT string_agg(to_json(member.key)::text || ':' || webauthn.canonical_json(member.value), ',' ORDER BY utf16.code_units COLLATE "C")
+    FROM jsonb_each(value) AS member
+    CROSS JOIN LATERAL (
+      --
+      -- Characters outside the BMP are encoded as surrogate pairs in UTF-16,
+      -- so they sort before U+E000..U+FFFF, unlike in code point order.
+      --
+      SELECT string_agg(CASE
+        WHEN ascii(chr) < 65536 THEN lpad(to_hex(ascii(chr)), 4, '0')
+        ELSE to_hex(55296 + ((ascii(chr) - 65536) >> 10)) || to_hex(56320 + ((ascii(chr) - 65536) & 1023))
+      END, '' ORDER BY position) AS code_units
+      FROM regexp_split_to_table(member.key, '') WITH ORDINALITY AS chars (chr, position)
+    ) AS utf16
+  ), '') || '}'
+  WHEN 'array' THEN '[' || COALESCE((
+    SELECT string_agg(webauthn.canonical_json(element.value), ',' ORDER BY element.position)
+    FROM jsonb_array_elements(value) WITH ORDINALITY AS element (value, position)
+  ), '') || ']'
+  WHEN 'number' THEN webauthn.canonical_json_number(value::text::float8)
+  ELSE value::text
+END
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.payload_challenge(nonce bytea, payload jsonb)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql AS $$
+--
+-- The challenge commits to the payload by hashing the nonce followed by the canonical JSON of the payload,
+-- as defined by RFC 8785, so that a client can compute the same challenge.
+--
+SELECT public.digest(nonce || convert_to(webauthn.canonical_json(payload),'UTF8'),'sha256')
+$$;
+
+ALTER TABLE webauthn.assertion_challenges ADD COLUMN nonce bytea;
+ALTER TABLE webauthn.assertion_challenges ADD COLUMN payload jsonb;
+ALTER TABLE webauthn.assertion_challenges ADD CONSTRAINT payload_challenge CHECK (COALESCE(challenge = webauthn.payload_challenge(nonce, payload), num_nulls(nonce, payload) = 2));
+COMMENT ON COLUMN webauthn.assertion_challenges.nonce IS 'The nonce given to webauthn.get_credentials() for a challenge committing to a payload';
+COMMENT ON COLUMN webauthn.assertion_challenges.payload IS 'The payload the challenge commits to, returned by webauthn.verify_assertion_payload() once verified';
+
+ALTER TABLE webauthn.assertion_challenges ADD COLUMN user_id bytea;
+COMMENT ON COLUMN webauthn.assertion_challenges.user_id IS 'Set by webauthn.get_credentials() for a re-authentication challenge, restricted to credentials of the already known user';
+
+CREATE INDEX ON webauthn.assertions (user_id, verified_at);
+
+CREATE TABLE webauthn.relying_parties (
+relying_party_id text NOT NULL,
+relying_party_name text NOT NULL,
+origins text[],
+require_user_verification boolean NOT NULL DEFAULT FALSE,
+allow_cross_origin boolean NOT NULL DEFAULT FALSE,
+top_origins text[],
+token_binding webauthn.token_binding_policy NOT NULL DEFAULT 'ignore',
+conditional_mediation_timeout interval NOT NULL DEFAULT '1 hour',
+enterprise_attestation boolean NOT NULL DEFAULT FALSE,
+role_name name,
+PRIMARY KEY (relying_party_id),
+CONSTRAINT origins_not_empty CHECK (cardinality(origins) > 0),
+CONSTRAINT top_origins_not_empty CHECK (cardinality(top_origins) > 0),
+CONSTRAINT reasonable_conditional_mediation_timeout CHECK (conditional_mediation_timeout BETWEEN '30000 ms' AND '24 hours')
+);
+
+SELECT pg_catalog.pg_extension_config_dump('relying_parties', '');
+
+COMMENT ON TABLE webauthn.relying_parties IS 'Registry of relying parties, referenced by challenges and credentials. Registered by webauthn.init_credential() on first use, unless inserted beforehand to set its policies.';
+
+COMMENT ON COLUMN webauthn.relying_parties.relying_party_id IS 'https://www.w3.org/TR/webauthn-2/#relying-party-identifier';
+COMMENT ON COLUMN webauthn.relying_parties.relying_party_name IS 'https://www.w3.org/TR/webauthn-2/#dictionary-rp-credential-params';
+COMMENT ON COLUMN webauthn.relying_parties.origins IS 'Origins allowed in the client data of the relying party''s credentials and assertions, or NULL to allow any origin, https://www.w3.org/TR/webauthn-2/#sctn-validating-origin';
+COMMENT ON COLUMN webauthn.relying_parties.require_user_verification IS 'Whether all challenges of the relying party require user verification, regardless of the user_verification requested';
+COMMENT ON COLUMN webauthn.relying_parties.allow_cross_origin IS 'Whether credentials and assertions may be created in a cross-origin iframe, unless overridden by the challenge, https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-crossorigin';
+COMMENT ON COLUMN webauthn.relying_parties.top_origins IS 'Top-level origins allowed in the client data of cross-origin credentials and assertions, or NULL to allow any top-level origin, https://www.w3.org/TR/webauthn-3/#dom-collectedclientdata-toporigin';
+COMMENT ON COLUMN webauthn.relying_parties.token_binding IS 'Whether the relying party''s credentials and assertions may, must not or must have the tokenBinding status "present" in their client data, https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-tokenbinding';
+COMMENT ON COLUMN webauthn.relying_parties.conditional_mediation_timeout IS 'The timeout of challenges created by webauthn.get_credentials() for conditional mediation, i.e. passkey autofill, which stay open while the user fills in the sign-in form, https://w3c.github.io/webappsec-credential-management/#dom-credentialmediationrequirement-conditional';
+COMMENT ON COLUMN webauthn.relying_parties.enterprise_attestation IS 'Whether the relying party is on the allow-list for enterprise attestation, otherwise requested as direct attestation, https://www.w3.org/TR/webauthn-2/#dom-attestationconveyancepreference-enterprise';
+COMMENT ON COLUMN webauthn.relying_parties.role_name IS 'The role allowed to access the rows of the relying party once row level security is enabled by webauthn.revoke_public_access(), including members of the role';
+
+--
+-- Once row level security is enabled by webauthn.revoke_public_access(), a role only sees the relying parties
+-- it's a member of the role_name of, and in the other tables only the rows of those relying parties.
+--
+CREATE POLICY relying_party_role ON webauthn.relying_parties USING (EXISTS (SELECT FROM pg_catalog.pg_roles WHERE pg_roles.rolname = relying_parties.role_name AND pg_catalog.pg_has_role(pg_roles.oid, 'MEMBER')));
+
+CREATE OR REPLACE FUNCTION webauthn.relying_party_origins(relying_party_id text)
+RETURNS text[]
+STABLE
+LANGUAGE sql AS $$
+SELECT origins FROM webauthn.relying_parties WHERE relying_party_id = $1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.relying_party_user_verification(relying_party_id text, user_verification webauthn.user_verification_requirement)
+RETURNS webauthn.user_verification_requirement
+STABLE
+LANGUAGE sql AS $$
+SELECT CASE
+  WHEN (SELECT require_user_verification FROM webauthn.relying_parties WHERE relying_party_id = $1)
+  THEN 'required'::webauthn.user_verification_requirement
+  ELSE $2
+END
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.credential_challenge_relying_party_id(challenge bytea)
+RETURNS text
+STABLE
+LANGUAGE sql AS $$
+SELECT relying_party_id FROM webauthn.credential_challenges WHERE challenge = $1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.relying_party_attestation(relying_party_id text, attestation webauthn.attestation_conveyance_preference)
+RETURNS webauthn.attestation_conveyance_preference
+STABLE
+LANGUAGE sql AS $$
+--
+-- Enterprise attestation is only requested for relying parties on the allow-list,
+-- and replaced by direct attestation for any other relying party.
+--
+SELECT CASE
+  WHEN $2 = 'enterprise' AND (SELECT enterprise_attestation FROM webauthn.relying_parties WHERE relying_party_id = $1) IS NOT TRUE
+  THEN 'direct'::webauthn.attestation_conveyance_preference
+  ELSE $2
+END
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.certificate_serial_number(certificate bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Returns the serialNumber of a DER encoded X.509 certificate,
+-- without the zero byte keeping the sign bit of the INTEGER clear,
+-- i.e. the bytes shown by `openssl x509 -serial`,
+-- or raises an error describing the first violation of DER found on the way.
+--
+-- The rest of the certificate is neither parsed nor verified.
+--
+-- Certificate ::= SEQUENCE { tbsCertificate TBSCertificate, ... }
+-- TBSCertificate ::= SEQUENCE { version [0] EXPLICIT Version DEFAULT v1, serialNumber INTEGER, ... }
+--
+-- https://www.rfc-editor.org/rfc/rfc5280#section-4.1
+-- https://www.itu.int/rec/T-REC-X.690 section 10.1
+--
+DECLARE
+_length integer := length(certificate);
+_offset integer := 0;
+_step integer := 0;
+_tag integer;
+_tag_offset integer;
+_content_length bigint;
+_length_bytes integer;
+_serial_number bytea;
+BEGIN
+LOOP
+  IF _offset + 2 > _length THEN
+    PERFORM webauthn.raise_error('Certificate truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+  END IF;
+  _tag_offset := _offset;
+  _tag := get_byte(certificate,_offset);
+  _content_length := get_byte(certificate,_offset+1);
+  _offset := _offset + 2;
+
+  IF _content_length = 128 THEN
+    PERFORM webauthn.raise_error('Certificate has indefinite length', json_build_object('offset', _offset - 1), NULL::boolean);
+  ELSIF _content_length > 128 THEN
+    _length_bytes := _content_length & 127;
+    IF _offset + _length_bytes > _length THEN
+      PERFORM webauthn.raise_error('Certificate truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+    END IF;
+    _content_length := 0;
+    FOR _j IN 0.._length_bytes-1 LOOP
+      _content_length := (_content_length<<8) + get_byte(certificate,_offset+_j);
+      EXIT WHEN _content_length > _length;
+    END LOOP;
+    IF get_byte(certificate,_offset) = 0 OR _content_length < 128 THEN
+      PERFORM webauthn.raise_error('Certificate length not minimally encoded', json_build_object('offset', _offset - 1), NULL::boolean);
+    END IF;
+    _offset := _offset + _length_bytes;
+  END IF;
+
+  IF _offset + _content_length > _length THEN
+    PERFORM webauthn.raise_error('Certificate truncated', json_build_object('offset', _offset, 'content_length', _content_length, 'length', _length), NULL::boolean);
+  END IF;
+
+  --
+  -- Enter the Certificate and TBSCertificate SEQUENCEs,
+  -- and skip the version, which is absent in v1 certificates.
+  --
+  IF _step < 2 THEN
+    IF _tag <> 48 /* 0x30 SEQUENCE */ THEN
+      PERFORM webauthn.raise_error('Certificate has unexpected tag', json_build_object('offset', _tag_offset, 'tag', _tag, 'expected_tag', 48), NULL::boolean);
+    END IF;
+    IF _step = 0 AND _offset + _content_length < _length THEN
+      PERFORM webauthn.raise_error('Trailing bytes after certificate', json_build_object('offset', _offset + _content_length, 'length', _length), NULL::boolean);
+    END IF;
+    _step := _step + 1;
+    CONTINUE;
+  ELSIF _step = 2 AND _tag = 160 /* 0xa0 [0] EXPLICIT */ THEN
+    _offset := _offset + _content_length::integer;
+    _step := _step + 1;
+    CONTINUE;
+  END IF;
+
+  IF _tag <> 2 /* 0x02 INTEGER */ THEN
+    PERFORM webauthn.raise_error('Certificate has unexpected tag', json_build_object('offset', _tag_offset, 'tag', _tag, 'expected_tag', 2), NULL::boolean);
+  END IF;
+  IF _content_length = 0 THEN
+    PERFORM webauthn.raise_error('Certificate serial number is empty', json_build_object('offset', _offset), NULL::boolean);
+  END IF;
+  _serial_number := substring(certificate from _offset+1 for _content_length::integer);
+  IF get_byte(_serial_number,0) = 0 AND _content_length > 1 THEN
+    _serial_number := substring(_serial_number from 2);
+  END IF;
+  RETURN _serial_number;
+END LOOP;
+END;
+$$;
+
+ALTER TABLE webauthn.credentials ADD COLUMN attestation_certificate_serial bytea;
+
+COMMENT ON COLUMN webauthn.credentials.attestation_certificate_serial IS 'The serial number of the attestation certificate of an enterprise attestation, identifying the authenticator as reported by the client, since neither the attestation statement nor its certificate chain are verified, https://www.w3.org/TR/webauthn-2/#dom-attestationconveyancepreference-enterprise';
+
+INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name)
+SELECT DISTINCT ON (relying_party_id) relying_party_id, relying_party_name
+FROM webauthn.credential_challenges
+WHERE relying_party_id IS NOT NULL
+ORDER BY relying_party_id, challenge_at DESC;
+
+INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name)
+SELECT DISTINCT relying_party_id, relying_party_id
+FROM webauthn.assertion_challenges
+WHERE relying_party_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE webauthn.credential_challenges
+  ADD CONSTRAINT relying_party_user_verification CHECK (user_verification = webauthn.relying_party_user_verification(relying_party_id, user_verification)),
+  ADD FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties;
+
+ALTER TABLE webauthn.assertion_challenges
+  ADD CONSTRAINT relying_party_user_verification CHECK (user_verification = webauthn.relying_party_user_verification(relying_party_id, user_verification)),
+  ADD FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties;
+
+ALTER TABLE webauthn.credentials ADD COLUMN relying_party_id text;
+
+UPDATE webauthn.credentials SET relying_party_id = credential_challenges.relying_party_id
+FROM webauthn.credential_challenges
+WHERE credential_challenges.challenge = credentials.challenge
+AND credential_challenges.relying_party_id IS NOT NULL;
+
+ALTER TABLE webauthn.credentials
+  ADD CONSTRAINT relying_party_id_matches_challenge CHECK (relying_party_id IS NOT DISTINCT FROM webauthn.credential_challenge_relying_party_id(challenge)),
+  ADD FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties;
+
+COMMENT ON COLUMN webauthn.credentials.relying_party_id IS 'https://www.w3.org/TR/webauthn-2/#relying-party-identifier';
+
+CREATE TABLE webauthn.assertion_failures (
+assertion_failure_id bigint NOT NULL GENERATED ALWAYS AS IDENTITY,
+credential_id bytea,
+user_name text,
+relying_party_id text,
+challenge bytea,
+sqlstate text,
+error_message text NOT NULL,
+failed_at timestamptz NOT NULL,
+PRIMARY KEY (assertion_failure_id),
+FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties
+);
+
+CREATE INDEX ON webauthn.assertion_failures (credential_id, failed_at);
+CREATE INDEX ON webauthn.assertion_failures (user_name, failed_at);
+
+SELECT pg_catalog.pg_extension_config_dump('assertion_failures', '');
+
+COMMENT ON TABLE webauthn.assertion_failures IS 'Used by webauthn.verify_assertion() to record failed assertions when webauthn.lockout_policies is non-empty.';
+
+COMMENT ON COLUMN webauthn.assertion_failures.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.assertion_failures.user_name IS 'user_name of the credential, or of the assertion challenge if the credential is unknown';
+COMMENT ON COLUMN webauthn.assertion_failures.relying_party_id IS 'relying_party_id of the credential, or of the assertion challenge if the credential is unknown, since user_name is only unique per relying party';
+COMMENT ON COLUMN webauthn.assertion_failures.challenge IS 'https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-challenge';
+COMMENT ON COLUMN webauthn.assertion_failures.sqlstate IS 'SQLSTATE of the error that caused the failure, or NULL if no matching credential was found';
+COMMENT ON COLUMN webauthn.assertion_failures.error_message IS 'Error message of the error that caused the failure';
+COMMENT ON COLUMN webauthn.assertion_failures.failed_at IS 'Timestamp of when the assertion failed in webauthn.verify_assertion()';
+
+CREATE POLICY relying_party_role ON webauthn.assertion_failures USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
+
+CREATE TABLE webauthn.lockouts (
+lockout_id bigint NOT NULL GENERATED ALWAYS AS IDENTITY,
+scope webauthn.lockout_scope NOT NULL,
+credential_id bytea,
+user_name text,
+relying_party_id text,
+locked_at timestamptz NOT NULL,
+locked_until timestamptz NOT NULL,
+PRIMARY KEY (lockout_id),
+CONSTRAINT scope_subject CHECK (CASE scope
+  WHEN 'credential' THEN credential_id IS NOT NULL AND user_name IS NULL
+  WHEN 'user_name' THEN user_name IS NOT NULL AND credential_id IS NULL
+END),
+CONSTRAINT locked_until_after_locked_at CHECK (locked_until > locked_at),
+FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties
+);
+
+CREATE INDEX ON webauthn.lockouts (credential_id, locked_until);
+CREATE INDEX ON webauthn.lockouts (user_name, locked_until);
+
+SELECT pg_catalog.pg_extension_config_dump('lockouts', '');
+
+COMMENT ON TABLE webauthn.lockouts IS 'Used by webauthn.verify_assertion() to store lockouts triggered by webauthn.lockout_policies.';
+
+COMMENT ON COLUMN webauthn.lockouts.scope IS 'Whether the lockout applies to credential_id or to user_name';
+COMMENT ON COLUMN webauthn.lockouts.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.lockouts.user_name IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialcreationoptions-user';
+COMMENT ON COLUMN webauthn.lockouts.relying_party_id IS 'https://www.w3.org/TR/webauthn-2/#relying-party-identifier, the lockout only applies to this relying party';
+COMMENT ON COLUMN webauthn.lockouts.locked_at IS 'Timestamp of the failure that triggered the lockout';
+COMMENT ON COLUMN webauthn.lockouts.locked_until IS 'Timestamp of when the lockout expires';
+
+CREATE POLICY relying_party_role ON webauthn.lockouts USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
+
+CREATE POLICY relying_party_role ON webauthn.credential_challenges USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
+CREATE POLICY relying_party_role ON webauthn.credentials USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
+CREATE POLICY relying_party_role ON webauthn.assertion_challenges USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
+CREATE POLICY relying_party_role ON webauthn.assertions USING (credential_id IN (SELECT credentials.credential_id FROM webauthn.credentials));
+
+CREATE OR REPLACE FUNCTION webauthn.cbor_item_end(cbor bytea, start_at integer)
+RETURNS integer
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Returns the zero-based offset following the CBOR data item starting at start_at,
+-- without decoding it, or NULL if the item is truncated,
+-- or uses an indefinite length, which CTAP2 canonical CBOR doesn't allow.
+--
+-- https://www.rfc-editor.org/rfc/rfc8949.html#section-3
+-- https://fidoalliance.org/specs/fido-v2.0-ps-20190130/fido-client-to-authenticator-protocol-v2.0-ps-20190130.html#ctap2-canonical-cbor-encoding-form
+--
+DECLARE
+_length integer := length(cbor);
+_pos integer := start_at;
+_pending bigint := 1;
+_major_type integer;
+_additional_info integer;
+_argument bigint;
+BEGIN
+WHILE _pending > 0 LOOP
+  -- Every pending item needs at least one byte
+  IF _pending > _length - _pos THEN
+    RETURN NULL;
+  END IF;
+  _major_type := get_byte(cbor,_pos) >> 5;
+  _additional_info := get_byte(cbor,_pos) & 31;
+  _pos := _pos + 1;
+  IF _additional_info < 24 THEN
+    _argument := _additional_info;
+  ELSIF _additional_info <= 27 THEN
+    IF _pos + (1 << (_additional_info - 24)) > _length THEN
+      RETURN NULL;
+    END IF;
+    _argument := 0;
+    FOR i IN 1..(1 << (_additional_info - 24)) LOOP
+      _argument := (_argument << 8) + get_byte(cbor,_pos);
+      _pos := _pos + 1;
+    END LOOP;
+  ELSE
+    RETURN NULL;
+  END IF;
+  _pending := _pending - 1;
+  IF _major_type IN (2,3) THEN
+    -- Byte and text strings
+    IF _argument < 0 OR _argument > _length - _pos THEN
+      RETURN NULL;
+    END IF;
+    _pos := _pos + _argument;
+  ELSIF _major_type = 4 THEN
+    -- Arrays
+    IF _argument < 0 THEN
+      RETURN NULL;
+    END IF;
+    _pending := _pending + _argument;
+  ELSIF _major_type = 5 THEN
+    -- Maps
+    IF _argument < 0 OR _argument > _length THEN
+      RETURN NULL;
+    END IF;
+    _pending := _pending + 2 * _argument;
+  ELSIF _major_type = 6 THEN
+    -- Tags are followed by the tagged item
+    _pending := _pending + 1;
+  END IF;
+END LOOP;
+RETURN _pos;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.validate_authenticator_data(authenticator_data bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Returns the authenticator data if its structure matches its flags,
+-- i.e. the attested credential data if AT is set, followed by the extensions if ED is set,
+-- without any trailing bytes, or raises an error describing the first violation.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-authenticator-data
+-- https://www.w3.org/TR/webauthn-2/#sctn-attested-credential-data
+--
+DECLARE
+_length integer := length(authenticator_data);
+_flags integer;
+_credential_id_length integer;
+_end integer := 37;
+_item_end integer;
+BEGIN
+IF _length < 37 THEN
+  PERFORM webauthn.raise_error('Authenticator data too short', json_build_object('length', _length, 'minimum_length', 37), NULL::boolean);
+END IF;
+
+_flags := get_byte(authenticator_data,32);
+
+IF _flags>>6&1 = 1 THEN
+  IF _length < 55 THEN
+    PERFORM webauthn.raise_error('Attested credential data too short', json_build_object('length', _length, 'minimum_length', 55), NULL::boolean);
+  END IF;
+  _credential_id_length := (get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54);
+  IF 55 + _credential_id_length >= _length THEN
+    PERFORM webauthn.raise_error('Credential ID length out of range', json_build_object('credential_id_length', _credential_id_length, 'remaining_length', _length - 55), NULL::boolean);
+  END IF;
+  _end := 55 + _credential_id_length;
+  _item_end := webauthn.cbor_item_end(authenticator_data,_end);
+  IF _item_end IS NULL THEN
+    PERFORM webauthn.raise_error('Malformed credential public key', json_build_object('offset', _end), NULL::boolean);
+  ELSIF get_byte(authenticator_data,_end)>>5 <> 5 THEN
+    PERFORM webauthn.raise_error('Credential public key is not a CBOR map', json_build_object('offset', _end), NULL::boolean);
+  END IF;
+  _end := _item_end;
+END IF;
+
+IF _flags>>7&1 = 1 THEN
+  IF _end = _length THEN
+    PERFORM webauthn.raise_error('Extension data flag set without extensions', json_build_object('offset', _end), NULL::boolean);
+  END IF;
+  _item_end := webauthn.cbor_item_end(authenticator_data,_end);
+  IF _item_end IS NULL THEN
+    PERFORM webauthn.raise_error('Malformed extensions', json_build_object('offset', _end), NULL::boolean);
+  ELSIF get_byte(authenticator_data,_end)>>5 <> 5 THEN
+    PERFORM webauthn.raise_error('Extensions are not a CBOR map', json_build_object('offset', _end), NULL::boolean);
+  END IF;
+  _end := _item_end;
+END IF;
+
+IF _end <> _length THEN
+  PERFORM webauthn.raise_error('Trailing bytes after authenticator data', json_build_object('length', _length, 'expected_length', _end), NULL::boolean);
+END IF;
+
+RETURN authenticator_data;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_authenticator_data(
+  OUT rp_id_hash bytea,
+  OUT user_present boolean,
+  OUT user_verified boolean,
+  OUT attested_credential_data_included boolean,
+  OUT extension_data_included boolean,
+  OUT sign_count bigint,
+  authenticator_data bytea
+)
+RETURNS record
+IMMUTABLE
+LANGUAGE sql
+AS $$
+-- https://developer.mozilla.org/en-US/docs/Web/API/AuthenticatorAssertionResponse/authenticatorData
+-- https://github.com/fido-alliance/webauthn-demo/blob/master/utils.js#L304
+SELECT
+  substring(valid_authenticator_data,1,32),
+  (get_byte(valid_authenticator_data,32)&1)::boolean,
+  (get_byte(valid_authenticator_data,32)>>2&1)::boolean,
+  (get_byte(valid_authenticator_data,32)>>6&1)::boolean,
+  (get_byte(valid_authenticator_data,32)>>7&1)::boolean,
+  (get_byte(valid_authenticator_data,33)::bigint<<24) +
+  (get_byte(valid_authenticator_data,34)::bigint<<16) +
+  (get_byte(valid_authenticator_data,35)::bigint<<8) +
+  get_byte(valid_authenticator_data,36)::bigint
+FROM webauthn.validate_authenticator_data(authenticator_data) AS valid_authenticator_data
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_attested_credential_data(
+  OUT aaguid bytea,
+  OUT credential_id bytea,
+  OUT credential_public_key bytea,
+  authenticator_data bytea
+)
+RETURNS record
+IMMUTABLE
+LANGUAGE sql
+AS $$
+-- https://www.w3.org/TR/webauthn-2/#sctn-attested-credential-data
+SELECT
+  substring(authenticator_data,38,16),
+  substring(authenticator_data,56,(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54)),
+  substring(authenticator_data,56+(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54))
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_attestation_object(
+  OUT rp_id_hash bytea,
+  OUT user_present boolean,
+  OUT user_verified boolean,
+  OUT attested_credential_data_included boolean,
+  OUT extension_data_included boolean,
+  OUT sign_count bigint,
+  OUT aaguid bytea,
+  OUT credential_id bytea,
+  OUT credential_public_key bytea,
+  attestation_object bytea
+)
+RETURNS record
+IMMUTABLE
+LANGUAGE sql
+AS $$
+-- https://developer.mozilla.org/en-US/docs/Web/API/AuthenticatorAssertionResponse/authenticatorData
+-- https://github.com/fido-alliance/webauthn-demo/blob/master/utils.js#L183
+SELECT
+  parse_authenticator_data.*,
+  parse_attested_credential_data.*
+FROM decode(cbor.to_jsonb(cbor := attestation_object, encode_binary_format := 'base64')->>'authData','base64') AS authenticator_data
+CROSS JOIN webauthn.parse_authenticator_data(authenticator_data)
+CROSS JOIN webauthn.parse_attested_credential_data(authenticator_data)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_credential_attestation_object()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Sets the columns of webauthn.credentials derived from the attestation object,
+-- decoding its CBOR only once per row, which generated columns can't do,
+-- since each generated column would have to decode it on its own.
+--
+DECLARE
+_attestation_object jsonb := cbor.to_jsonb(cbor := NEW.attestation_object, encode_binary_format := 'base64');
+_authenticator_data bytea := decode(_attestation_object->>'authData','base64');
+_attested_credential_data record;
+BEGIN
+IF _authenticator_data IS NULL THEN
+  PERFORM webauthn.raise_error('Attestation object without authData', json_build_object('credential_id', webauthn.base64url_encode(NEW.credential_id)), NULL::boolean);
+END IF;
+
+SELECT
+  parse_authenticator_data.rp_id_hash,
+  parse_authenticator_data.user_present,
+  parse_authenticator_data.user_verified,
+  parse_authenticator_data.attested_credential_data_included,
+  parse_authenticator_data.extension_data_included,
+  parse_authenticator_data.sign_count
+INTO
+  NEW.rp_id_hash,
+  NEW.user_present,
+  NEW.user_verified,
+  NEW.attested_credential_data_included,
+  NEW.extension_data_included,
+  NEW.sign_count
+FROM webauthn.parse_authenticator_data(_authenticator_data);
+
+IF NOT NEW.attested_credential_data_included THEN
+  PERFORM webauthn.raise_error('Attested credential data missing', json_build_object('credential_id', webauthn.base64url_encode(NEW.credential_id)), NULL::boolean);
+END IF;
+
+_attested_credential_data := webauthn.parse_attested_credential_data(_authenticator_data);
+
+IF NEW.credential_id IS DISTINCT FROM _attested_credential_data.credential_id THEN
+  RAISE check_violation USING
+    MESSAGE = format('new row for relation "%s" violates check constraint "%s"', TG_TABLE_NAME, 'attestation_object_credential_id'),
+    SCHEMA = TG_TABLE_SCHEMA,
+    TABLE = TG_TABLE_NAME,
+    CONSTRAINT = 'attestation_object_credential_id';
+END IF;
+
+NEW.aaguid := _attested_credential_data.aaguid;
+-- \x04 tag byte not prepended since not wanted by pg-ecdsa
+SELECT parse_cose_key.kty, parse_cose_key.alg, parse_cose_key.crv, parse_cose_key.x || parse_cose_key.y
+INTO NEW.kty, NEW.alg, NEW.crv, NEW.public_key
+FROM webauthn.parse_cose_key(_attested_credential_data.credential_public_key);
+NEW.authenticator_extension_outputs := webauthn.parse_authenticator_extensions(_authenticator_data, NEW.attested_credential_data_included, NEW.extension_data_included);
+
+--
+-- An enterprise attestation certificate identifies the individual authenticator,
+-- unlike the batch certificates of other attestations, so only its serial number is kept.
+-- The attestation statement isn't verified, so the serial number is only as trustworthy as the client.
+-- It's only read on insert, since the relying party's allow-list may have changed since.
+-- https://www.w3.org/TR/webauthn-2/#dom-attestationconveyancepreference-enterprise
+--
+IF TG_OP = 'UPDATE' THEN
+  NEW.attestation_certificate_serial := OLD.attestation_certificate_serial;
+ELSIF webauthn.relying_party_attestation(NEW.relying_party_id, (SELECT attestation FROM webauthn.credential_challenges WHERE challenge = NEW.challenge)) = 'enterprise'
+AND _attestation_object->'attStmt'->'x5c'->>0 IS NOT NULL
+THEN
+  NEW.attestation_certificate_serial := webauthn.certificate_serial_number(decode(_attestation_object->'attStmt'->'x5c'->>0,'base64'));
+ELSE
+  NEW.attestation_certificate_serial := NULL;
+END IF;
+
+RETURN NEW;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_assertion_authenticator_data()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Sets the columns of webauthn.assertions derived from the authenticator data,
+-- parsing it only once per row.
+--
+BEGIN
+SELECT
+  parse_authenticator_data.rp_id_hash,
+  parse_authenticator_data.user_present,
+  parse_authenticator_data.user_verified,
+  parse_authenticator_data.attested_credential_data_included,
+  parse_authenticator_data.extension_data_included,
+  parse_authenticator_data.sign_count
+INTO
+  NEW.rp_id_hash,
+  NEW.user_present,
+  NEW.user_verified,
+  NEW.attested_credential_data_included,
+  NEW.extension_data_included,
+  NEW.sign_count
+FROM webauthn.parse_authenticator_data(NEW.authenticator_data);
+
+RETURN NEW;
+END;
+$$;
+
+ALTER TABLE webauthn.credentials
+  ALTER COLUMN rp_id_hash DROP EXPRESSION,
+  ALTER COLUMN user_present DROP EXPRESSION,
+  ALTER COLUMN user_verified DROP EXPRESSION,
+  ALTER COLUMN attested_credential_data_included DROP EXPRESSION,
+  ALTER COLUMN extension_data_included DROP EXPRESSION,
+  ALTER COLUMN sign_count DROP EXPRESSION,
+  ALTER COLUMN aaguid DROP EXPRESSION,
+  ALTER COLUMN public_key DROP EXPRESSION,
+  DROP CONSTRAINT attestation_object_credential_id;
+
+CREATE TRIGGER parse_attestation_object
+BEFORE INSERT OR UPDATE OF attestation_object ON webauthn.credentials
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_credential_attestation_object();
+
+ALTER TABLE webauthn.assertions
+  ALTER COLUMN rp_id_hash DROP EXPRESSION,
+  ALTER COLUMN user_present DROP EXPRESSION,
+  ALTER COLUMN user_verified DROP EXPRESSION,
+  ALTER COLUMN attested_credential_data_included DROP EXPRESSION,
+  ALTER COLUMN extension_data_included DROP EXPRESSION,
+  ALTER COLUMN sign_count DROP EXPRESSION;
+
+CREATE TRIGGER parse_authenticator_data
+BEFORE INSERT OR UPDATE ON webauthn.assertions
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_assertion_authenticator_data();
+
+CREATE OR REPLACE FUNCTION webauthn.decode_asn1_der_signature(asn1der bytea, curve_name text)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Decodes a DER encoded ECDSA-Sig-Value, i.e. SEQUENCE { r INTEGER, s INTEGER },
+-- into r || s, each left-padded with zeros to the size of the curve's field,
+-- or raises an error describing the first violation of DER or of the range of r and s.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-signature-attestation-types
+-- https://www.rfc-editor.org/rfc/rfc3279#section-2.2.3
+-- https://www.itu.int/rec/T-REC-X.690 section 10.1
+--
+DECLARE
+_width integer := CASE decode_asn1_der_signature.curve_name
+  WHEN 'secp256r1' THEN 32
+  WHEN 'secp384r1' THEN 48
+  WHEN 'secp521r1' THEN 66
+END;
+_length integer := length(asn1der);
+_offset integer := 0;
+_expected_tag integer;
+_content_length bigint;
+_length_bytes integer;
+_integer bytea;
+_result bytea := '\x';
+BEGIN
+IF _width IS NULL THEN
+  PERFORM webauthn.raise_error('Unsupported curve', json_build_object('curve_name', curve_name), NULL::boolean);
+END IF;
+
+--
+-- The SEQUENCE header is followed by the two INTEGERs it contains.
+--
+FOR _i IN 0..2 LOOP
+  _expected_tag := CASE WHEN _i = 0 THEN 48 /* 0x30 SEQUENCE */ ELSE 2 /* 0x02 INTEGER */ END;
+  IF _offset + 2 > _length THEN
+    PERFORM webauthn.raise_error('DER signature truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+  END IF;
+  IF get_byte(asn1der,_offset) <> _expected_tag THEN
+    PERFORM webauthn.raise_error('DER signature has unexpected tag', json_build_object('offset', _offset, 'tag', get_byte(asn1der,_offset), 'expected_tag', _expected_tag), NULL::boolean);
+  END IF;
+  _content_length := get_byte(asn1der,_offset+1);
+  _offset := _offset + 2;
+
+  IF _content_length = 128 THEN
+    PERFORM webauthn.raise_error('DER signature has indefinite length', json_build_object('offset', _offset - 1), NULL::boolean);
+  ELSIF _content_length > 128 THEN
+    _length_bytes := _content_length & 127;
+    IF _offset + _length_bytes > _length THEN
+      PERFORM webauthn.raise_error('DER signature truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+    END IF;
+    _content_length := 0;
+    FOR _j IN 0.._length_bytes-1 LOOP
+      _content_length := (_content_length<<8) + get_byte(asn1der,_offset+_j);
+      -- No signature is anywhere near this long, so stop before overflowing.
+      EXIT WHEN _content_length > _length;
+    END LOOP;
+    IF get_byte(asn1der,_offset) = 0 OR _content_length < 128 THEN
+      PERFORM webauthn.raise_error('DER signature length not minimally encoded', json_build_object('offset', _offset - 1), NULL::boolean);
+    END IF;
+    _offset := _offset + _length_bytes;
+  END IF;
+
+  IF _offset + _content_length > _length THEN
+    PERFORM webauthn.raise_error('DER signature truncated', json_build_object('offset', _offset, 'content_length', _content_length, 'length', _length), NULL::boolean);
+  END IF;
+
+  IF _i = 0 THEN
+    IF _offset + _content_length < _length THEN
+      PERFORM webauthn.raise_error('Trailing bytes after DER signature', json_build_object('offset', _offset + _content_length, 'length', _length), NULL::boolean);
+    END IF;
+    CONTINUE;
+  END IF;
+
+  IF _content_length = 0 THEN
+    PERFORM webauthn.raise_error('DER signature INTEGER is empty', json_build_object('offset', _offset), NULL::boolean);
+  END IF;
+  _integer := substring(asn1der from _offset+1 for _content_length::integer);
+  IF get_byte(_integer,0) >= 128 THEN
+    PERFORM webauthn.raise_error('DER signature INTEGER is negative', json_build_object('offset', _offset), NULL::boolean);
+  END IF;
+  IF get_byte(_integer,0) = 0 AND _content_length > 1 THEN
+    IF get_byte(_integer,1) < 128 THEN
+      PERFORM webauthn.raise_error('DER signature INTEGER not minimally encoded', json_build_object('offset', _offset), NULL::boolean);
+    END IF;
+    -- The zero byte only keeps the sign bit clear.
+    _integer := substring(_integer from 2);
+  END IF;
+  IF _integer = '\x00' OR length(_integer) > _width THEN
+    PERFORM webauthn.raise_error('DER signature INTEGER out of range', json_build_object('offset', _offset, 'length', length(_integer), 'maximum_length', _width), NULL::boolean);
+  END IF;
+  _result := _result || decode(repeat('00',_width - length(_integer)),'hex') || _integer;
+  _offset := _offset + _content_length::integer;
+END LOOP;
+
+IF _offset <> _length THEN
+  PERFORM webauthn.raise_error('Trailing bytes after DER signature', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+END IF;
+
+RETURN _result;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.decode_asn1_der_signature(asn1der bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql
+AS $$
+SELECT webauthn.decode_asn1_der_signature(asn1der, 'secp256r1')
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.ec_point_on_curve(crv integer, x bytea, y bytea)
+RETURNS boolean
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Checks that (x,y) is a point on the given NIST curve,
+-- i.e. that x and y are less than p and y^2 = x^3 - 3x + b (mod p),
+-- or returns NULL if the curve is not a NIST curve.
+--
+-- https://www.iana.org/assignments/cose/cose.xhtml#elliptic-curves
+-- https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-186.pdf section 3.2.1
+--
+DECLARE
+_parameters bytea[] := CASE ec_point_on_curve.crv
+  WHEN 1 THEN ARRAY[ -- P-256
+    '\xffffffff00000001000000000000000000000000ffffffffffffffffffffffff',
+    '\x5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b'
+  ]::bytea[]
+  WHEN 2 THEN ARRAY[ -- P-384
+    '\xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000ffffffff',
+    '\xb3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875ac656398d8a2ed19d2a85c8edd3ec2aef'
+  ]::bytea[]
+  WHEN 3 THEN ARRAY[ -- P-521
+    '\x01ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff',
+    '\x0051953eb9618e1c9a1f929a21a0b68540eea2da725b99b315f3b8b489918ef109e156193951ec7e937b1652c0bd3bb1bf073573df883d2c34f1ef451fd46b503f00'
+  ]::bytea[]
+END;
+_integers numeric[] := ARRAY[0,0,0,0];
+_bytes bytea;
+_p numeric;
+_b numeric;
+_x numeric;
+_y numeric;
+BEGIN
+IF _parameters IS NULL THEN
+  RETURN NULL;
+END IF;
+
+FOR _i IN 1..4 LOOP
+  _bytes := (_parameters || ARRAY[x,y])[_i];
+  FOR _j IN 0..length(_bytes)-1 LOOP
+    _integers[_i] := _integers[_i] * 256 + get_byte(_bytes,_j);
+  END LOOP;
+END LOOP;
+_p := _integers[1];
+_b := _integers[2];
+_x := _integers[3];
+_y := _integers[4];
+
+RETURN _x < _p AND _y < _p AND mod(_y * _y - (_x * _x * _x - 3 * _x + _b), _p) = 0;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_cose_key(
+  OUT kty integer,
+  OUT alg integer,
+  OUT crv integer,
+  OUT x bytea,
+  OUT y bytea,
+  cose_key bytea
+)
+RETURNS record
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Parses the credential public key, a COSE_Key, returning its key type, algorithm and curve,
+-- and the coordinates of its point.
+--
+-- Raises an error if a parameter is missing, if the algorithm is unknown,
+-- if the key type or curve are inconsistent with the algorithm,
+-- if a coordinate has the wrong length, or if the point is not on the curve.
+-- Well-formed ES384, ES512 and EdDSA keys are then refused as unsupported,
+-- since signatures are only verified with ES256.
+--
+-- Any data following the COSE_Key, i.e. the extensions, is ignored.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-encoded-credPubKey-examples
+-- https://www.rfc-editor.org/rfc/rfc8152#section-7
+-- https://www.rfc-editor.org/rfc/rfc8152#section-13.1.1
+--
+DECLARE
+_cose_key jsonb := cbor.to_jsonb_array(cbor := cose_key, encode_binary_format := 'base64')->0;
+_label text;
+_expected record;
+BEGIN
+IF jsonb_typeof(_cose_key) IS DISTINCT FROM 'object' THEN
+  PERFORM webauthn.raise_error('COSE key is not a CBOR map', json_build_object('length', length(cose_key)), NULL::boolean);
+END IF;
+
+FOREACH _label IN ARRAY ARRAY['1','3','-1'] LOOP
+  IF jsonb_typeof(_cose_key->_label) IS DISTINCT FROM 'number' THEN
+    PERFORM webauthn.raise_error('COSE key parameter missing', json_build_object('label', _label::integer), NULL::boolean);
+  END IF;
+END LOOP;
+
+kty := (_cose_key->>'1')::integer;
+alg := (_cose_key->>'3')::integer;
+crv := (_cose_key->>'-1')::integer;
+
+--
+-- https://www.iana.org/assignments/cose/cose.xhtml#algorithms
+-- https://www.iana.org/assignments/cose/cose.xhtml#elliptic-curves
+--
+SELECT * INTO _expected
+FROM (VALUES
+  (-7,  2, ARRAY[1],   32), -- ES256, EC2, P-256
+  (-35, 2, ARRAY[2],   48), -- ES384, EC2, P-384
+  (-36, 2, ARRAY[3],   66), -- ES512, EC2, P-521
+  (-8,  1, ARRAY[6,7], NULL) -- EdDSA, OKP, Ed25519 or Ed448
+) AS algorithms (alg, kty, crvs, coordinate_length)
+WHERE algorithms.alg = parse_cose_key.alg;
+
+IF NOT FOUND THEN
+  PERFORM webauthn.raise_error('Unsupported COSE algorithm', json_build_object('alg', alg), NULL::boolean);
+ELSIF kty <> _expected.kty THEN
+  PERFORM webauthn.raise_error('COSE key type inconsistent with algorithm', json_build_object('kty', kty, 'alg', alg), NULL::boolean);
+ELSIF crv <> ALL(_expected.crvs) THEN
+  PERFORM webauthn.raise_error('COSE curve inconsistent with algorithm', json_build_object('crv', crv, 'alg', alg), NULL::boolean);
+END IF;
+
+x := decode(_cose_key->>'-2','base64');
+y := decode(_cose_key->>'-3','base64');
+
+IF kty = 1 THEN
+  IF length(x) IS DISTINCT FROM (CASE crv WHEN 6 THEN 32 WHEN 7 THEN 57 END) THEN
+    PERFORM webauthn.raise_error('COSE key coordinate length invalid', json_build_object('label', -2, 'length', length(x)), NULL::boolean);
+  END IF;
+ELSE
+  FOREACH _label IN ARRAY ARRAY['-2','-3'] LOOP
+    IF length(decode(_cose_key->>_label,'base64')) IS DISTINCT FROM _expected.coordinate_length THEN
+      PERFORM webauthn.raise_error('COSE key coordinate length invalid', json_build_object('label', _label::integer, 'length', length(decode(_cose_key->>_label,'base64')), 'expected_length', _expected.coordinate_length), NULL::boolean);
+    END IF;
+  END LOOP;
+
+  IF NOT webauthn.ec_point_on_curve(crv, x, y) THEN
+    PERFORM webauthn.raise_error('COSE key point not on curve', json_build_object('crv', crv), NULL::boolean);
+  END IF;
+END IF;
+
+--
+-- The public key is stored as the x and y coordinates for pg-ecdsa,
+-- so an OKP key, having no y coordinate, would otherwise violate NOT NULL instead.
+--
+IF alg <> -7 THEN
+  PERFORM webauthn.raise_error('Unsupported COSE algorithm', json_build_object('alg', alg, 'kty', kty, 'crv', crv), NULL::boolean);
+END IF;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_credential_attestation_object()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Sets the columns of webauthn.credentials derived from the attestation object,
+-- decoding its CBOR only once per row, which generated columns can't do,
+-- since each generated column would have to decode it on its own.
+--
+DECLARE
+_attestation_object jsonb := cbor.to_jsonb(cbor := NEW.attestation_object, encode_binary_format := 'base64');
+_authenticator_data bytea := decode(_attestation_object->>'authData','base64');
+_attested_credential_data record;
+BEGIN
+IF _authenticator_data IS NULL THEN
+  PERFORM webauthn.raise_error('Attestation object without authData', json_build_object('credential_id', webauthn.base64url_encode(NEW.credential_id)), NULL::boolean);
+END IF;
+
+SELECT
+  parse_authenticator_data.rp_id_hash,
+  parse_authenticator_data.user_present,
+  parse_authenticator_data.user_verified,
+  parse_authenticator_data.attested_credential_data_included,
+  parse_authenticator_data.extension_data_included,
+  parse_authenticator_data.sign_count
+INTO
+  NEW.rp_id_hash,
+  NEW.user_present,
+  NEW.user_verified,
+  NEW.attested_credential_data_included,
+  NEW.extension_data_included,
+  NEW.sign_count
+FROM webauthn.parse_authenticator_data(_authenticator_data);
+
+IF NOT NEW.attested_credential_data_included THEN
+  PERFORM webauthn.raise_error('Attested credential data missing', json_build_object('credential_id', webauthn.base64url_encode(NEW.credential_id)), NULL::boolean);
+END IF;
+
+_attested_credential_data := webauthn.parse_attested_credential_data(_authenticator_data);
+
+IF NEW.credential_id IS DISTINCT FROM _attested_credential_data.credential_id THEN
+  RAISE check_violation USING
+    MESSAGE = format('new row for relation "%s" violates check constraint "%s"', TG_TABLE_NAME, 'attestation_object_credential_id'),
+    SCHEMA = TG_TABLE_SCHEMA,
+    TABLE = TG_TABLE_NAME,
+    CONSTRAINT = 'attestation_object_credential_id';
+END IF;
+
+NEW.aaguid := _attested_credential_data.aaguid;
+-- \x04 tag byte not prepended since not wanted by pg-ecdsa
+SELECT parse_cose_key.kty, parse_cose_key.alg, parse_cose_key.crv, parse_cose_key.x || parse_cose_key.y
+INTO NEW.kty, NEW.alg, NEW.crv, NEW.public_key
+FROM webauthn.parse_cose_key(_attested_credential_data.credential_public_key);
+NEW.authenticator_extension_outputs := webauthn.parse_authenticator_extensions(_authenticator_data, NEW.attested_credential_data_included, NEW.extension_data_included);
+
+--
+-- An enterprise attestation certificate identifies the individual authenticator,
+-- unlike the batch certificates of other attestations, so only its serial number is kept.
+-- The attestation statement isn't verified, so the serial number is only as trustworthy as the client.
+-- It's only read on insert, since the relying party's allow-list may have changed since.
+-- https://www.w3.org/TR/webauthn-2/#dom-attestationconveyancepreference-enterprise
+--
+IF TG_OP = 'UPDATE' THEN
+  NEW.attestation_certificate_serial := OLD.attestation_certificate_serial;
+ELSIF webauthn.relying_party_attestation(NEW.relying_party_id, (SELECT attestation FROM webauthn.credential_challenges WHERE challenge = NEW.challenge)) = 'enterprise'
+AND _attestation_object->'attStmt'->'x5c'->>0 IS NOT NULL
+THEN
+  NEW.attestation_certificate_serial := webauthn.certificate_serial_number(decode(_attestation_object->'attStmt'->'x5c'->>0,'base64'));
+ELSE
+  NEW.attestation_certificate_serial := NULL;
+END IF;
+
+RETURN NEW;
+END;
+$$;
+
+ALTER TABLE webauthn.credentials
+  ADD COLUMN kty integer,
+  ADD COLUMN alg integer,
+  ADD COLUMN crv integer;
+
+UPDATE webauthn.credentials SET
+  (kty, alg, crv) = (
+    SELECT parse_cose_key.kty, parse_cose_key.alg, parse_cose_key.crv
+    FROM webauthn.parse_attested_credential_data(decode(cbor.to_jsonb(cbor := credentials.attestation_object, encode_binary_format := 'base64')->>'authData','base64')) AS attested_credential_data
+    CROSS JOIN webauthn.parse_cose_key(attested_credential_data.credential_public_key)
+  );
+
+ALTER TABLE webauthn.credentials
+  ALTER COLUMN kty SET NOT NULL,
+  ALTER COLUMN alg SET NOT NULL,
+  ALTER COLUMN crv SET NOT NULL,
+  ADD CONSTRAINT public_key_algorithm_supported CHECK (kty = 2 AND alg = -7 AND crv = 1);
+
+COMMENT ON COLUMN webauthn.credentials.kty IS 'https://www.rfc-editor.org/rfc/rfc8152#section-7.1';
+COMMENT ON COLUMN webauthn.credentials.alg IS 'https://www.w3.org/TR/webauthn-2/#typedefdef-cosealgorithmidentifier';
+COMMENT ON COLUMN webauthn.credentials.crv IS 'https://www.rfc-editor.org/rfc/rfc8152#section-13.1.1';
+
+ALTER TABLE webauthn.credential_challenges ADD COLUMN allow_cross_origin boolean;
+ALTER TABLE webauthn.assertion_challenges ADD COLUMN allow_cross_origin boolean;
+
+--
+-- Cross-origin credentials and assertions were accepted before 1.7,
+-- so their challenges are marked as having allowed it.
+--
+UPDATE webauthn.credential_challenges SET allow_cross_origin = TRUE
+WHERE challenge IN (SELECT challenge FROM webauthn.credentials WHERE cross_origin);
+
+UPDATE webauthn.assertion_challenges SET allow_cross_origin = TRUE
+WHERE challenge IN (SELECT challenge FROM webauthn.assertions WHERE cross_origin);
+
+COMMENT ON COLUMN webauthn.credential_challenges.allow_cross_origin IS 'Whether the challenge may be used in a cross-origin iframe, set by webauthn.init_credential(), or NULL to use the policy of the relying party';
+COMMENT ON COLUMN webauthn.assertion_challenges.allow_cross_origin IS 'Whether the challenge may be used in a cross-origin iframe, set by webauthn.get_credentials(), or NULL to use the policy of the relying party';
+
+CREATE OR REPLACE FUNCTION webauthn.relying_party_top_origins(relying_party_id text)
+RETURNS text[]
+STABLE
+LANGUAGE sql AS $$
+SELECT top_origins FROM webauthn.relying_parties WHERE relying_party_id = $1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.credential_challenge_allow_cross_origin(challenge bytea)
+RETURNS boolean
+STABLE
+LANGUAGE sql AS $$
+SELECT COALESCE(credential_challenges.allow_cross_origin, relying_parties.allow_cross_origin, FALSE)
+FROM webauthn.credential_challenges
+LEFT JOIN webauthn.relying_parties ON relying_parties.relying_party_id = credential_challenges.relying_party_id
+WHERE credential_challenges.challenge = $1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_allow_cross_origin(challenge bytea)
+RETURNS boolean
+STABLE
+LANGUAGE sql AS $$
+SELECT COALESCE(assertion_challenges.allow_cross_origin, relying_parties.allow_cross_origin, FALSE)
+FROM webauthn.assertion_challenges
+LEFT JOIN webauthn.relying_parties ON relying_parties.relying_party_id = assertion_challenges.relying_party_id
+WHERE assertion_challenges.challenge = $1
+$$;
+
+ALTER TABLE webauthn.credentials
+  ADD COLUMN top_origin text GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->>'topOrigin') STORED,
+  ADD CONSTRAINT top_origin_only_if_cross_origin CHECK (top_origin IS NULL OR cross_origin);
+
+ALTER TABLE webauthn.assertions
+  ADD COLUMN top_origin text GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->>'topOrigin') STORED,
+  ADD CONSTRAINT top_origin_only_if_cross_origin CHECK (top_origin IS NULL OR cross_origin);
+
+COMMENT ON COLUMN webauthn.credentials.top_origin IS 'https://www.w3.org/TR/webauthn-3/#dom-collectedclientdata-toporigin';
+COMMENT ON COLUMN webauthn.assertions.top_origin IS 'https://www.w3.org/TR/webauthn-3/#dom-collectedclientdata-toporigin';
+
+CREATE OR REPLACE FUNCTION webauthn.parse_client_data_json(client_data_json bytea)
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Parses the client data, returning it as a jsonb object,
+-- or raises an error if it is not valid UTF-8, not a JSON object,
+-- or if a member is missing or has the wrong type.
+--
+-- Members not defined by the specification are kept, since clients may add new ones.
+--
+-- https://www.w3.org/TR/webauthn-2/#dictionary-client-data
+-- https://www.w3.org/TR/webauthn-2/#sctn-registering-a-new-credential steps 5-6
+--
+DECLARE
+_text text;
+_client_data jsonb;
+_member record;
+_token_binding_status text;
+BEGIN
+BEGIN
+  _text := webauthn.from_utf8(client_data_json);
+EXCEPTION WHEN character_not_in_repertoire THEN
+  PERFORM webauthn.raise_error('clientDataJSON is not valid UTF-8', json_build_object('length', length(client_data_json)), NULL::boolean);
+END;
+
+BEGIN
+  _client_data := _text::jsonb;
+EXCEPTION WHEN invalid_text_representation OR untranslatable_character THEN
+  PERFORM webauthn.raise_error('clientDataJSON is not valid JSON', json_build_object('length', length(client_data_json)), NULL::boolean);
+END;
+
+IF jsonb_typeof(_client_data) IS DISTINCT FROM 'object' THEN
+  PERFORM webauthn.raise_error('clientDataJSON is not a JSON object', json_build_object('type', jsonb_typeof(_client_data)), NULL::boolean);
+END IF;
+
+FOR _member IN
+SELECT * FROM (VALUES
+  ('type',         'string',  TRUE),
+  ('challenge',    'string',  TRUE),
+  ('origin',       'string',  TRUE),
+  ('crossOrigin',  'boolean', FALSE),
+  ('topOrigin',    'string',  FALSE),
+  ('tokenBinding', 'object',  FALSE),
+  ('payment',      'object',  FALSE)
+) AS members (name, expected_type, required)
+LOOP
+  IF NOT _client_data ? _member.name THEN
+    IF _member.required THEN
+      PERFORM webauthn.raise_error('clientDataJSON member missing', json_build_object('member', _member.name), NULL::boolean);
+    END IF;
+  ELSIF jsonb_typeof(_client_data->_member.name) <> _member.expected_type THEN
+    PERFORM webauthn.raise_error('clientDataJSON member has wrong type', json_build_object('member', _member.name, 'type', jsonb_typeof(_client_data->_member.name), 'expected_type', _member.expected_type), NULL::boolean);
+  END IF;
+END LOOP;
+
+--
+-- "not-supported" was removed in Level 2, but is still sent by some clients.
+-- https://www.w3.org/TR/webauthn-2/#dictdef-tokenbinding
+--
+IF _client_data ? 'tokenBinding' THEN
+  _token_binding_status := _client_data->'tokenBinding'->>'status';
+  IF jsonb_typeof(_client_data->'tokenBinding'->'status') IS DISTINCT FROM 'string'
+  OR _token_binding_status NOT IN ('present', 'supported', 'not-supported')
+  THEN
+    PERFORM webauthn.raise_error('clientDataJSON tokenBinding status invalid', json_build_object('status', _client_data->'tokenBinding'->'status'), NULL::boolean);
+  END IF;
+  IF _token_binding_status = 'present' AND jsonb_typeof(_client_data->'tokenBinding'->'id') IS DISTINCT FROM 'string' THEN
+    PERFORM webauthn.raise_error('clientDataJSON tokenBinding id missing', json_build_object('status', _token_binding_status), NULL::boolean);
+  END IF;
+END IF;
+
+RETURN _client_data;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.parse_client_data()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Sets the client_data column of webauthn.credentials and webauthn.assertions,
+-- raising an error describing invalid client data before the generated columns
+-- derived from it would fail to cast it to jsonb.
+--
+BEGIN
+NEW.client_data := webauthn.parse_client_data_json(NEW.client_data_json);
+RETURN NEW;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.relying_party_token_binding(relying_party_id text)
+RETURNS webauthn.token_binding_policy
+STABLE
+LANGUAGE sql AS $$
+SELECT token_binding FROM webauthn.relying_parties WHERE relying_party_id = $1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.check_relying_party_policy()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Checks the client data of a new row in webauthn.credentials or webauthn.assertions
+-- against the policies of its relying party in webauthn.relying_parties.
+--
+-- The policies can change, so unlike check constraints, which would be re-checked
+-- on every update, e.g. when a credential is revoked, they are only checked on insert.
+-- The error is raised as a check_violation like for a check constraint.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-validating-origin
+--
+DECLARE
+_relying_party_id text;
+_allow_cross_origin boolean;
+_payee_origin text;
+_constraint_name text;
+BEGIN
+IF TG_TABLE_NAME = 'credentials' THEN
+  _relying_party_id := NEW.relying_party_id;
+  _allow_cross_origin := webauthn.credential_challenge_allow_cross_origin(NEW.challenge);
+ELSE
+  _relying_party_id := webauthn.assertion_challenge_relying_party_id(NEW.challenge);
+  _allow_cross_origin := webauthn.assertion_challenge_allow_cross_origin(NEW.challenge);
+  _payee_origin := webauthn.assertion_challenge_payment(NEW.challenge)->>'payeeOrigin';
+END IF;
+
+SELECT policy.constraint_name INTO _constraint_name
+FROM (VALUES
+  --
+  -- https://www.w3.org/TR/secure-payment-confirmation/#sctn-verifying-assertion
+  -- A payment.get assertion is made on the merchant's origin, not the relying party's,
+  -- so it is checked against the payeeOrigin of the challenge instead, if given, either as the origin
+  -- or, from a cross-origin iframe, as the top-level origin, and otherwise like any other row.
+  --
+  (1, 'origin_allowed', CASE WHEN NEW.client_data->>'type' = 'payment.get' AND _payee_origin IS NOT NULL
+    THEN COALESCE(_payee_origin IN (NEW.client_data->>'origin', NEW.client_data->'payment'->>'topOrigin'), FALSE)
+    ELSE COALESCE(NEW.client_data->>'origin' = ANY(webauthn.relying_party_origins(_relying_party_id)), TRUE)
+  END),
+  --
+  -- A row created in a cross-origin iframe is only accepted if the challenge or relying party allows it,
+  -- and only from an allowed top-level origin, which the client only reports for cross-origin calls.
+  --
+  (2, 'cross_origin_allowed', (NEW.client_data->'crossOrigin')::boolean IS NOT TRUE OR _allow_cross_origin),
+  (3, 'top_origin_allowed', NEW.client_data->>'type' = 'payment.get' AND _payee_origin IS NOT NULL OR COALESCE(NEW.client_data->>'topOrigin' = ANY(webauthn.relying_party_top_origins(_relying_party_id)), TRUE)),
+  (4, 'token_binding_allowed', CASE webauthn.relying_party_token_binding(_relying_party_id)
+    WHEN 'forbid' THEN NEW.client_data->'tokenBinding'->>'status' IS DISTINCT FROM 'present'
+    WHEN 'require' THEN NEW.client_data->'tokenBinding'->>'status' IS NOT DISTINCT FROM 'present'
+    ELSE TRUE
+  END)
+) AS policy (position, constraint_name, allowed)
+WHERE NOT policy.allowed
+ORDER BY policy.position
+LIMIT 1;
+
+IF _constraint_name IS NOT NULL THEN
+  RAISE check_violation USING
+    MESSAGE = format('new row for relation "%s" violates check constraint "%s"', TG_TABLE_NAME, _constraint_name),
+    SCHEMA = TG_TABLE_SCHEMA,
+    TABLE = TG_TABLE_NAME,
+    CONSTRAINT = _constraint_name;
+END IF;
+
+RETURN NEW;
+END;
+$$;
+
+ALTER TABLE webauthn.credentials ADD COLUMN client_data jsonb;
+ALTER TABLE webauthn.assertions ADD COLUMN client_data jsonb;
+
+--
+-- The client data of existing rows is kept as it is,
+-- since it was only validated by the casts of the generated columns when stored.
+--
+UPDATE webauthn.credentials SET client_data = webauthn.from_utf8(client_data_json)::jsonb;
+UPDATE webauthn.assertions SET client_data = webauthn.from_utf8(client_data_json)::jsonb;
+
+ALTER TABLE webauthn.credentials ALTER COLUMN client_data SET NOT NULL;
+ALTER TABLE webauthn.assertions ALTER COLUMN client_data SET NOT NULL;
+
+CREATE TRIGGER parse_client_data
+BEFORE INSERT OR UPDATE ON webauthn.credentials
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_client_data();
+
+CREATE TRIGGER parse_client_data
+BEFORE INSERT OR UPDATE ON webauthn.assertions
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_client_data();
+
+CREATE TRIGGER relying_party_policy
+BEFORE INSERT ON webauthn.credentials
+FOR EACH ROW EXECUTE FUNCTION webauthn.check_relying_party_policy();
+
+CREATE TRIGGER relying_party_policy
+BEFORE INSERT ON webauthn.assertions
+FOR EACH ROW EXECUTE FUNCTION webauthn.check_relying_party_policy();
+
+COMMENT ON COLUMN webauthn.credentials.client_data IS 'The parsed client data, kept for audit, https://www.w3.org/TR/webauthn-2/#dictdef-collectedclientdata';
+COMMENT ON COLUMN webauthn.assertions.client_data IS 'The parsed client data, kept for audit, https://www.w3.org/TR/webauthn-2/#dictdef-collectedclientdata';
+
+CREATE OR REPLACE FUNCTION webauthn.relying_party_conditional_mediation_timeout(relying_party_id text)
+RETURNS interval
+STABLE
+LANGUAGE sql AS $$
+--
+-- Challenges without a relying party get the default of the conditional_mediation_timeout column.
+--
+SELECT COALESCE(
+  (SELECT conditional_mediation_timeout FROM webauthn.relying_parties WHERE relying_party_id = $1),
+  '1 hour'::interval
+)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_mediation(challenge bytea)
+RETURNS webauthn.credential_mediation_requirement
+STABLE
+LANGUAGE sql AS $$
+SELECT mediation FROM webauthn.assertion_challenges WHERE challenge = $1
+$$;
+
+ALTER TABLE webauthn.assertion_challenges
+  ADD COLUMN mediation webauthn.credential_mediation_requirement,
+  DROP CONSTRAINT reasonable_timeout,
+  ADD CONSTRAINT reasonable_timeout CHECK (timeout BETWEEN '30000 ms' AND CASE WHEN mediation = 'conditional' THEN interval '24 hours' ELSE interval '600000 ms' END),
+  ADD CONSTRAINT conditional_mediation_discoverable CHECK (mediation IS DISTINCT FROM 'conditional' OR num_nulls(user_name, user_id) = 2);
+
+ALTER TABLE webauthn.assertions
+  ADD CONSTRAINT user_handle_if_conditional CHECK (user_handle IS NOT NULL OR webauthn.assertion_challenge_mediation(challenge) IS DISTINCT FROM 'conditional');
+
+COMMENT ON COLUMN webauthn.assertion_challenges.mediation IS 'https://w3c.github.io/webappsec-credential-management/#dom-credentialrequestoptions-mediation';
+
+ALTER TABLE webauthn.credential_challenges
+  ADD COLUMN authenticator_attachment webauthn.authenticator_attachment,
+  ADD COLUMN hints webauthn.public_key_credential_hint[],
+  ADD CONSTRAINT hints_not_empty CHECK (cardinality(hints) > 0);
+
+ALTER TABLE webauthn.assertion_challenges
+  ADD COLUMN hints webauthn.public_key_credential_hint[],
+  ADD CONSTRAINT hints_not_empty CHECK (cardinality(hints) > 0);
+
+ALTER TABLE webauthn.credentials ADD COLUMN authenticator_attachment webauthn.authenticator_attachment;
+
+COMMENT ON COLUMN webauthn.credential_challenges.authenticator_attachment IS 'https://www.w3.org/TR/webauthn-2/#dom-authenticatorselectioncriteria-authenticatorattachment';
+COMMENT ON COLUMN webauthn.credential_challenges.hints IS 'https://www.w3.org/TR/webauthn-3/#dom-publickeycredentialcreationoptions-hints';
+COMMENT ON COLUMN webauthn.assertion_challenges.hints IS 'https://www.w3.org/TR/webauthn-3/#dom-publickeycredentialrequestoptions-hints';
+COMMENT ON COLUMN webauthn.credentials.authenticator_attachment IS 'The attachment reported by the client when the credential was created, https://www.w3.org/TR/webauthn-3/#dom-publickeycredential-authenticatorattachment';
+
+CREATE TABLE webauthn.sessions (
+token_hash bytea NOT NULL,
+signature bytea NOT NULL REFERENCES webauthn.assertions,
+credential_id bytea NOT NULL REFERENCES webauthn.credentials,
+user_id bytea NOT NULL,
+created_at timestamptz NOT NULL,
+expires_at timestamptz NOT NULL,
+idle_timeout interval NOT NULL,
+last_used_at timestamptz NOT NULL,
+revoked_at timestamptz,
+PRIMARY KEY (token_hash),
+UNIQUE (signature),
+CONSTRAINT expires_after_created CHECK (expires_at > created_at),
+CONSTRAINT positive_idle_timeout CHECK (idle_timeout > '0'::interval),
+CONSTRAINT revoked_after_created CHECK (revoked_at >= created_at)
+);
+
+CREATE INDEX ON webauthn.sessions (user_id);
+CREATE INDEX ON webauthn.sessions (credential_id);
+
+SELECT pg_catalog.pg_extension_config_dump('sessions', '');
+
+COMMENT ON TABLE webauthn.sessions IS 'Used by webauthn.create_session() to store sessions issued for verified assertions.';
+
+COMMENT ON COLUMN webauthn.sessions.token_hash IS 'SHA-256 hash of the session token, which is only returned by webauthn.create_session()';
+COMMENT ON COLUMN webauthn.sessions.signature IS 'The signature of the verified assertion the session was issued for, https://www.w3.org/TR/webauthn-2/#assertion-signature';
+COMMENT ON COLUMN webauthn.sessions.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.sessions.user_id IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialuserentity-id';
+COMMENT ON COLUMN webauthn.sessions.created_at IS 'Timestamp of when the session was created by webauthn.create_session()';
+COMMENT ON COLUMN webauthn.sessions.expires_at IS 'Timestamp after which webauthn.validate_session() no longer accepts the session';
+COMMENT ON COLUMN webauthn.sessions.idle_timeout IS 'Maximum time between uses of the session in webauthn.validate_session()';
+COMMENT ON COLUMN webauthn.sessions.last_used_at IS 'Timestamp of when the session was created or last validated by webauthn.validate_session()';
+COMMENT ON COLUMN webauthn.sessions.revoked_at IS 'Timestamp of when the session was revoked by webauthn.revoke_sessions() or webauthn.revoke_credential()';
+
+CREATE POLICY relying_party_role ON webauthn.sessions USING (credential_id IN (SELECT credentials.credential_id FROM webauthn.credentials));
+
+CREATE TABLE webauthn.recovery_codes (
+recovery_code_id bigint NOT NULL GENERATED ALWAYS AS IDENTITY,
+user_id bytea NOT NULL,
+code_hash bytea NOT NULL,
+generated_at timestamptz NOT NULL,
+redeemed_at timestamptz,
+revoked_at timestamptz,
+PRIMARY KEY (recovery_code_id),
+UNIQUE (code_hash),
+CONSTRAINT redeemed_after_generated CHECK (redeemed_at >= generated_at),
+CONSTRAINT revoked_after_generated CHECK (revoked_at >= generated_at)
+);
+
+CREATE INDEX ON webauthn.recovery_codes (user_id);
+
+SELECT pg_catalog.pg_extension_config_dump('recovery_codes', '');
+
+COMMENT ON TABLE webauthn.recovery_codes IS 'Used by webauthn.generate_recovery_codes() to store recovery codes, a fallback for users who have lost all their authenticators.';
+
+COMMENT ON COLUMN webauthn.recovery_codes.user_id IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialuserentity-id';
+COMMENT ON COLUMN webauthn.recovery_codes.code_hash IS 'SHA-256 hash of the normalized recovery code, which is only returned by webauthn.generate_recovery_codes()';
+COMMENT ON COLUMN webauthn.recovery_codes.generated_at IS 'Timestamp of when the code was generated by webauthn.generate_recovery_codes()';
+COMMENT ON COLUMN webauthn.recovery_codes.redeemed_at IS 'Timestamp of when the code was redeemed by webauthn.redeem_recovery_code()';
+COMMENT ON COLUMN webauthn.recovery_codes.revoked_at IS 'Timestamp of when the code was replaced by a new set of codes from webauthn.generate_recovery_codes()';
+
+CREATE POLICY relying_party_role ON webauthn.recovery_codes USING (user_id IN (SELECT credentials.user_id FROM webauthn.credentials));
+
+CREATE OR REPLACE FUNCTION webauthn.check_lockout(credential_id bytea, user_name text, relying_party_id text, at timestamptz)
+RETURNS void
+LANGUAGE sql AS $$
+--
+-- user_name is only unique per relying party,
+-- so a lockout only applies to the relying party of the failed assertions.
+--
+SELECT webauthn.raise_error(
+  'Locked out',
+  json_build_object(
+    'scope', lockouts.scope,
+    'locked_until', lockouts.locked_until
+  ),
+  NULL::boolean
+)
+FROM webauthn.lockouts
+WHERE (lockouts.credential_id = check_lockout.credential_id OR lockouts.user_name = check_lockout.user_name)
+AND lockouts.relying_party_id IS NOT DISTINCT FROM check_lockout.relying_party_id
+AND check_lockout.at >= lockouts.locked_at
+AND check_lockout.at < lockouts.locked_until
+ORDER BY lockouts.locked_until DESC, lockouts.scope
+LIMIT 1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.count_assertion_failures(
+  scope webauthn.lockout_scope,
+  credential_id bytea,
+  user_name text,
+  relying_party_id text,
+  since timestamptz
+)
+RETURNS bigint
+STABLE
+LANGUAGE sql AS $$
+--
+-- Failures older than the last expired lockout or the last successful assertion
+-- for the same subject are not counted, so that a lockout is not immediately
+-- re-triggered after its cooldown, and a legitimate user starts from zero after
+-- signing in.
+--
+SELECT count(*)
+FROM webauthn.assertion_failures
+WHERE CASE count_assertion_failures.scope
+  WHEN 'credential' THEN assertion_failures.credential_id = count_assertion_failures.credential_id
+  WHEN 'user_name' THEN assertion_failures.user_name = count_assertion_failures.user_name
+END
+AND assertion_failures.relying_party_id IS NOT DISTINCT FROM count_assertion_failures.relying_party_id
+AND assertion_failures.failed_at > greatest(
+  count_assertion_failures.since,
+  (
+    SELECT max(lockouts.locked_until)
+    FROM webauthn.lockouts
+    WHERE lockouts.scope = count_assertion_failures.scope
+    AND CASE count_assertion_failures.scope
+      WHEN 'credential' THEN lockouts.credential_id = count_assertion_failures.credential_id
+      WHEN 'user_name' THEN lockouts.user_name = count_assertion_failures.user_name
+    END
+    AND lockouts.relying_party_id IS NOT DISTINCT FROM count_assertion_failures.relying_party_id
+  ),
+  (
+    SELECT max(assertions.verified_at)
+    FROM webauthn.assertions
+    JOIN webauthn.credentials ON credentials.credential_id = assertions.credential_id
+    WHERE CASE count_assertion_failures.scope
+      WHEN 'credential' THEN assertions.credential_id = count_assertion_failures.credential_id
+      WHEN 'user_name' THEN credentials.user_name = count_assertion_failures.user_name
+    END
+    AND credentials.relying_party_id IS NOT DISTINCT FROM count_assertion_failures.relying_party_id
+  )
+)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.record_assertion_failure(
+  credential_id bytea,
+  user_name text,
+  relying_party_id text,
+  challenge bytea,
+  sqlstate text,
+  error_message text,
+  failed_at timestamptz
+)
+RETURNS void
+LANGUAGE sql
+AS $$
+INSERT INTO webauthn.assertion_failures
+       (credential_id, user_name, relying_party_id, challenge, sqlstate, error_message, failed_at)
+VALUES (credential_id, user_name, relying_party_id, challenge, sqlstate, error_message, failed_at);
+
+INSERT INTO webauthn.lockouts (scope, credential_id, user_name, relying_party_id, locked_at, locked_until)
+SELECT
+  lockout_policies.scope,
+  CASE lockout_policies.scope WHEN 'credential' THEN record_assertion_failure.credential_id END,
+  CASE lockout_policies.scope WHEN 'user_name' THEN record_assertion_failure.user_name END,
+  record_assertion_failure.relying_party_id,
+  record_assertion_failure.failed_at,
+  record_assertion_failure.failed_at + lockout_policies.cooldown
+FROM webauthn.lockout_policies
+WHERE lockout_policies.max_failures <= webauthn.count_assertion_failures(
+  scope := lockout_policies.scope,
+  credential_id := record_assertion_failure.credential_id,
+  user_name := record_assertion_failure.user_name,
+  relying_party_id := record_assertion_failure.relying_party_id,
+  since := record_assertion_failure.failed_at - lockout_policies.failure_window
+)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.insert_assertion(
+  OUT user_id bytea,
+  credential_id bytea,
+  credential_type webauthn.credential_type,
+  authenticator_data bytea,
+  client_data_json bytea,
+  challenge bytea,
+  signature bytea,
+  user_handle bytea,
+  verified_at timestamptz,
+  client_extension_results jsonb
+)
+RETURNS bytea
+LANGUAGE sql
+AS $$
+WITH inserted_assertion AS (
+  INSERT INTO webauthn.assertions (signature, credential_id, challenge, authenticator_data, client_data_json, user_id, user_handle, verified_at, prf_salts, client_extension_results)
+  SELECT
+    insert_assertion.signature,
+    credentials.credential_id,
+    assertion_challenges.challenge,
+    insert_assertion.authenticator_data,
+    insert_assertion.client_data_json,
+    credentials.user_id,
+    insert_assertion.user_handle,
+    insert_assertion.verified_at,
+    COALESCE(
+      assertion_challenges.extensions->'prf'->'evalByCredential'->webauthn.base64url_encode(credentials.credential_id),
+      assertion_challenges.extensions->'prf'->'eval'
+    ),
+    insert_assertion.client_extension_results
+  FROM webauthn.assertion_challenges
+  JOIN webauthn.credentials ON  credentials.credential_id   = insert_assertion.credential_id
+                           AND  credentials.credential_type = insert_assertion.credential_type
+                           AND (credentials.user_name      <> assertion_challenges.user_name) IS NOT TRUE
+                           AND (credentials.user_id        <> assertion_challenges.user_id)   IS NOT TRUE
+                           AND  credentials.relying_party_id IS NOT DISTINCT FROM assertion_challenges.relying_party_id
+                           AND  credentials.revoked_at IS NULL
+  WHERE assertion_challenges.challenge = insert_assertion.challenge
+  RETURNING assertions.user_id, assertions.credential_id, assertions.challenge, assertions.verified_at
+), store_large_blob AS (
+  --
+  -- https://www.w3.org/TR/webauthn-2/#sctn-large-blob-extension
+  -- The client reports largeBlob.written if the blob requested to be written was stored on the authenticator.
+  --
+  INSERT INTO webauthn.large_blobs (credential_id, version, blob_hash, blob_size, challenge, written_at)
+  SELECT
+    inserted_assertion.credential_id,
+    COALESCE((SELECT max(large_blobs.version) FROM webauthn.large_blobs WHERE large_blobs.credential_id = inserted_assertion.credential_id),0) + 1,
+    public.digest(webauthn.base64url_decode(assertion_challenges.extensions->'largeBlob'->>'write'),'sha256'),
+    length(webauthn.base64url_decode(assertion_challenges.extensions->'largeBlob'->>'write')),
+    inserted_assertion.challenge,
+    inserted_assertion.verified_at
+  FROM inserted_assertion
+  JOIN webauthn.assertion_challenges ON assertion_challenges.challenge = inserted_assertion.challenge
+  WHERE (insert_assertion.client_extension_results->'largeBlob'->'written')::boolean
+  AND assertion_challenges.extensions->'largeBlob' ? 'write'
+)
+SELECT inserted_assertion.user_id FROM inserted_assertion
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.check_public_key_credential(credential jsonb, response_members text[])
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Validates the shape of a PublicKeyCredential serialized with toJSON(),
+-- i.e. a RegistrationResponseJSON or AuthenticationResponseJSON,
+-- where all binary values are base64url encoded strings.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-registrationresponsejson
+-- https://www.w3.org/TR/webauthn-3/#dictdef-authenticationresponsejson
+--
+SELECT CASE
+  WHEN jsonb_typeof(credential) IS DISTINCT FROM 'object'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', NULL, 'expected', 'object'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'id') IS DISTINCT FROM 'string'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'id', 'expected', 'string'), NULL::jsonb)
+  WHEN credential ? 'rawId' AND credential->'rawId' IS DISTINCT FROM credential->'id'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'rawId', 'expected', 'same value as id'), NULL::jsonb)
+  WHEN credential->>'type' IS DISTINCT FROM 'public-key'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'type', 'expected', 'public-key'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'response') IS DISTINCT FROM 'object'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'response', 'expected', 'object'), NULL::jsonb)
+  WHEN invalid_response_member IS NOT NULL
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'response.' || invalid_response_member, 'expected', 'string'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'response'->'userHandle') NOT IN ('string','null')
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'response.userHandle', 'expected', 'string or null'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'clientExtensionResults') <> 'object'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'clientExtensionResults', 'expected', 'object'), NULL::jsonb)
+  WHEN credential->>'authenticatorAttachment' NOT IN ('platform','cross-platform')
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'authenticatorAttachment', 'expected', 'platform, cross-platform or null'), NULL::jsonb)
+  ELSE credential
+END
+FROM (
+  SELECT (
+    SELECT response_member
+    FROM unnest(response_members) WITH ORDINALITY AS required(response_member, ordinality)
+    WHERE jsonb_typeof(credential->'response'->response_member) IS DISTINCT FROM 'string'
+    ORDER BY ordinality
+    LIMIT 1
+  ) AS invalid_response_member
+) AS check_response_members
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.get_credential_creation_options(challenge bytea)
+RETURNS jsonb
+LANGUAGE sql
+AS $$
+SELECT
+jsonb_build_object(
+  'publicKey', jsonb_build_object(
+    'rp', jsonb_strip_nulls(jsonb_build_object(
+      'name', relying_party_name,
+      'id', relying_party_id
+    )),
+    'user', jsonb_build_object(
+      'name', user_name,
+      'displayName', user_display_name,
+      'id', webauthn.base64url_encode(user_id)
+    ),
+    'challenge', webauthn.base64url_encode(challenge),
+    'pubKeyCredParams', jsonb_build_array(
+      jsonb_build_object(
+        'type', 'public-key',
+        'alg', -7
+      )
+    ),
+    'authenticatorSelection', jsonb_strip_nulls(jsonb_build_object(
+      'authenticatorAttachment', authenticator_attachment,
+      'requireResidentKey', require_resident_key,
+      'userVerification', user_verification
+    )),
+    'timeout', (extract(epoch from timeout)*1000)::bigint,
+    'attestation', attestation
+  ) || CASE WHEN extensions IS NOT NULL THEN jsonb_build_object('extensions', extensions) ELSE '{}' END
+    || CASE WHEN hints IS NOT NULL THEN jsonb_build_object('hints', to_jsonb(hints)) ELSE '{}' END
+)
+FROM webauthn.credential_challenges
+WHERE credential_challenges.challenge = get_credential_creation_options.challenge
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.init_credential(
+  challenge bytea,
+  user_name text,
+  user_id bytea,
+  user_display_name text,
+  relying_party_name text,
+  relying_party_id text DEFAULT NULL,
+  require_resident_key boolean DEFAULT FALSE,
+  user_verification webauthn.user_verification_requirement DEFAULT 'preferred',
+  attestation webauthn.attestation_conveyance_preference DEFAULT 'none',
+  timeout interval DEFAULT '5 minutes'::interval,
+  challenge_at timestamptz DEFAULT now(),
+  extensions jsonb DEFAULT NULL,
+  allow_cross_origin boolean DEFAULT NULL,
+  authenticator_attachment webauthn.authenticator_attachment DEFAULT NULL,
+  hints webauthn.public_key_credential_hint[] DEFAULT NULL
+)
+RETURNS jsonb
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- An unknown relying party is registered on first use,
+-- without any origin restrictions or policies.
+--
+INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name)
+SELECT init_credential.relying_party_id, init_credential.relying_party_name
+WHERE init_credential.relying_party_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+INSERT INTO webauthn.credential_challenges
+       (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, require_resident_key, user_verification, attestation, timeout, challenge_at, extensions, allow_cross_origin, authenticator_attachment, hints)
+VALUES (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, require_resident_key, webauthn.relying_party_user_verification(relying_party_id, user_verification), webauthn.relying_party_attestation(relying_party_id, attestation), timeout, challenge_at, extensions, allow_cross_origin, authenticator_attachment, hints)
+RETURNING webauthn.get_credential_creation_options(challenge)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.store_credential(
+  OUT user_id bytea,
+  credential_id text,
+  credential_type webauthn.credential_type,
+  attestation_object text,
+  client_data_json text,
+  credential_at timestamptz DEFAULT now(),
+  client_extension_results jsonb DEFAULT NULL,
+  authenticator_attachment webauthn.authenticator_attachment DEFAULT NULL
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+INSERT INTO webauthn.credentials (credential_id, credential_type, attestation_object, client_data_json, challenge, user_name, user_id, credential_at, client_extension_results, relying_party_id, authenticator_attachment)
+SELECT
+  webauthn.base64url_decode(store_credential.credential_id),
+  store_credential.credential_type,
+  webauthn.base64url_decode(store_credential.attestation_object),
+  webauthn.base64url_decode(store_credential.client_data_json),
+  credential_challenges.challenge,
+  credential_challenges.user_name,
+  credential_challenges.user_id,
+  store_credential.credential_at,
+  store_credential.client_extension_results,
+  credential_challenges.relying_party_id,
+  store_credential.authenticator_attachment
+FROM webauthn.credential_challenges
+WHERE credential_challenges.challenge = webauthn.base64url_decode(webauthn.parse_client_data_json(webauthn.base64url_decode(store_credential.client_data_json))->>'challenge')
+RETURNING credentials.user_id
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.store_credential(
+  OUT user_id bytea,
+  credential jsonb,
+  credential_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+SELECT webauthn.store_credential(
+  credential_id := public_key_credential->>'id',
+  credential_type := (public_key_credential->>'type')::webauthn.credential_type,
+  attestation_object := public_key_credential->'response'->>'attestationObject',
+  client_data_json := public_key_credential->'response'->>'clientDataJSON',
+  credential_at := store_credential.credential_at,
+  client_extension_results := public_key_credential->'clientExtensionResults',
+  authenticator_attachment := (public_key_credential->>'authenticatorAttachment')::webauthn.authenticator_attachment
+)
+FROM webauthn.check_public_key_credential(
+  credential := store_credential.credential,
+  response_members := ARRAY['clientDataJSON','attestationObject']
+) AS public_key_credential
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.get_credentials(
+  challenge bytea,
+  user_name text DEFAULT NULL,
+  user_verification webauthn.user_verification_requirement DEFAULT 'preferred',
+  timeout interval DEFAULT NULL,
+  relying_party_id text DEFAULT NULL,
+  challenge_at timestamptz DEFAULT now(),
+  extensions jsonb DEFAULT NULL,
+  credential_id text DEFAULT NULL,
+  payload jsonb DEFAULT NULL,
+  user_id bytea DEFAULT NULL,
+  allow_cross_origin boolean DEFAULT NULL,
+  mediation webauthn.credential_mediation_requirement DEFAULT NULL,
+  hints webauthn.public_key_credential_hint[] DEFAULT NULL
+)
+RETURNS jsonb
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Without a user_name, the lockouts of the user_names of the user_id's credentials apply.
+--
+SELECT webauthn.check_lockout(NULL, lockout_user_names.user_name, get_credentials.relying_party_id, get_credentials.challenge_at)
+FROM (
+  SELECT get_credentials.user_name
+  UNION
+  SELECT credentials.user_name
+  FROM webauthn.credentials
+  WHERE get_credentials.user_name IS NULL
+  AND credentials.user_id = get_credentials.user_id
+  AND credentials.relying_party_id IS NOT DISTINCT FROM get_credentials.relying_party_id
+) AS lockout_user_names;
+
+--
+-- If a payload is given, the challenge argument is used as the nonce,
+-- and the challenge sent to the client commits to the payload.
+-- Without a timeout, conditional mediation challenges get the relying party's timeout,
+-- since they stay open while the user fills in the sign-in form.
+--
+WITH store_assertion_challenge AS (
+  INSERT INTO webauthn.assertion_challenges
+         (challenge, user_name, user_verification, timeout, relying_party_id, challenge_at, extensions, nonce, payload, user_id, allow_cross_origin, mediation, hints)
+  VALUES (
+    COALESCE(webauthn.payload_challenge(challenge, payload), challenge),
+    user_name, webauthn.relying_party_user_verification(relying_party_id, user_verification),
+    COALESCE(timeout, CASE WHEN mediation = 'conditional' THEN webauthn.relying_party_conditional_mediation_timeout(relying_party_id) ELSE '5 minutes'::interval END),
+    relying_party_id, challenge_at, extensions,
+    CASE WHEN payload IS NOT NULL THEN challenge END,
+    payload,
+    user_id,
+    allow_cross_origin,
+    mediation,
+    hints
+  )
+  RETURNING assertion_challenges.challenge, assertion_challenges.timeout
+)
+SELECT jsonb_strip_nulls(jsonb_build_object(
+  'publicKey', jsonb_build_object(
+    'userVerification', webauthn.relying_party_user_verification(get_credentials.relying_party_id, get_credentials.user_verification),
+    'allowCredentials', COALESCE(jsonb_agg(
+      jsonb_build_object(
+        'type', credentials.credential_type,
+        'id', webauthn.base64url_encode(credentials.credential_id)
+      )
+    ORDER BY credentials.credential_id),jsonb_build_array()),
+    'timeout', (extract(epoch from (SELECT store_assertion_challenge.timeout FROM store_assertion_challenge))*1000)::bigint,
+    'challenge', webauthn.base64url_encode((SELECT store_assertion_challenge.challenge FROM store_assertion_challenge)),
+    'rpId', get_credentials.relying_party_id,
+    'hints', to_jsonb(get_credentials.hints),
+    'extensions', CASE
+      -- https://www.w3.org/TR/webauthn-2/#sctn-large-blob-extension
+      -- A blob can only be written to a single credential, which insert_assertion() attributes it to.
+      WHEN get_credentials.extensions->'largeBlob' ? 'write' AND count(credentials.credential_id) <> 1
+      THEN webauthn.raise_error('largeBlob write requires exactly one credential', json_build_object('credentials', count(credentials.credential_id)), NULL::jsonb)
+      -- https://www.w3.org/TR/webauthn-2/#sctn-appid-extension
+      WHEN bool_or(credentials.appid IS NOT NULL)
+      THEN COALESCE(get_credentials.extensions,'{}') || jsonb_build_object('appid', max(credentials.appid))
+      ELSE get_credentials.extensions
+    END
+  ),
+  'mediation', get_credentials.mediation
+))
+FROM webauthn.credentials
+WHERE (credentials.user_name = get_credentials.user_name OR get_credentials.user_name IS NULL AND get_credentials.user_id IS NOT NULL)
+AND (credentials.user_id = get_credentials.user_id OR get_credentials.user_id IS NULL)
+AND credentials.relying_party_id IS NOT DISTINCT FROM get_credentials.relying_party_id
+AND credentials.revoked_at IS NULL
+AND (credentials.credential_id = webauthn.base64url_decode(get_credentials.credential_id) OR get_credentials.credential_id IS NULL)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.get_payment_credentials(
+  challenge bytea,
+  user_name text,
+  relying_party_id text,
+  instrument jsonb,
+  total jsonb,
+  payee_origin text DEFAULT NULL,
+  payee_name text DEFAULT NULL,
+  user_verification webauthn.user_verification_requirement DEFAULT 'preferred',
+  timeout interval DEFAULT '5 minutes'::interval,
+  challenge_at timestamptz DEFAULT now()
+)
+RETURNS jsonb
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the arguments for the PaymentRequest constructor of a Secure Payment Confirmation,
+-- and stores the transaction details the client is expected to sign in clientDataJSON.payment.
+--
+-- https://www.w3.org/TR/secure-payment-confirmation/#sctn-securepaymentconfirmationrequest
+-- https://www.w3.org/TR/secure-payment-confirmation/#sctn-collectedclientadditionalpaymentdata-dictionary
+--
+SELECT webauthn.check_lockout(NULL, get_payment_credentials.user_name, get_payment_credentials.relying_party_id, get_payment_credentials.challenge_at);
+
+WITH store_assertion_challenge AS (
+  INSERT INTO webauthn.assertion_challenges
+         (challenge, user_name, user_verification, timeout, relying_party_id, challenge_at, payment)
+  VALUES (challenge, user_name, webauthn.relying_party_user_verification(relying_party_id, user_verification), timeout, relying_party_id, challenge_at, jsonb_strip_nulls(jsonb_build_object(
+    'rpId', relying_party_id,
+    'payeeName', payee_name,
+    'payeeOrigin', payee_origin,
+    'total', total,
+    'instrument', instrument
+  )))
+  RETURNING TRUE
+)
+SELECT jsonb_build_object(
+  'methodData', jsonb_build_array(jsonb_build_object(
+    'supportedMethods', 'secure-payment-confirmation',
+    'data', jsonb_strip_nulls(jsonb_build_object(
+      'challenge', webauthn.base64url_encode(get_payment_credentials.challenge),
+      'rpId', get_payment_credentials.relying_party_id,
+      'credentialIds', COALESCE(jsonb_agg(webauthn.base64url_encode(credentials.credential_id) ORDER BY credentials.credential_id),jsonb_build_array()),
+      'instrument', get_payment_credentials.instrument,
+      'payeeName', get_payment_credentials.payee_name,
+      'payeeOrigin', get_payment_credentials.payee_origin,
+      'timeout', (extract(epoch from get_payment_credentials.timeout)*1000)::bigint
+    ))
+  )),
+  'details', jsonb_build_object(
+    'total', jsonb_build_object(
+      'label', 'Total',
+      'amount', get_payment_credentials.total
+    )
+  )
+)
+FROM webauthn.credentials
+WHERE credentials.user_name = get_payment_credentials.user_name
+AND credentials.relying_party_id IS NOT DISTINCT FROM get_payment_credentials.relying_party_id
+AND credentials.revoked_at IS NULL
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.verify_assertion(
+  OUT user_id bytea,
+  credential_id text,
+  credential_type webauthn.credential_type,
+  authenticator_data text,
+  client_data_json text,
+  signature text,
+  user_handle text,
+  verified_at timestamptz DEFAULT now(),
+  client_extension_results jsonb DEFAULT NULL,
+  relying_party_id text DEFAULT NULL
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE plpgsql
+AS $$
+DECLARE
+_credential_id bytea := webauthn.base64url_decode(verify_assertion.credential_id);
+_challenge bytea := webauthn.base64url_decode(webauthn.parse_client_data_json(webauthn.base64url_decode(verify_assertion.client_data_json))->>'challenge');
+_user_name text;
+_relying_party_id text;
+_sqlstate text;
+_error_message text := 'No matching credential';
+BEGIN
+SELECT credentials.user_name, credentials.relying_party_id INTO _user_name, _relying_party_id
+FROM webauthn.credentials WHERE credentials.credential_id = _credential_id;
+IF NOT FOUND THEN
+  SELECT assertion_challenges.user_name, assertion_challenges.relying_party_id INTO _user_name, _relying_party_id
+  FROM webauthn.assertion_challenges WHERE assertion_challenges.challenge = _challenge;
+END IF;
+
+PERFORM webauthn.check_lockout(_credential_id, _user_name, _relying_party_id, verify_assertion.verified_at);
+
+BEGIN
+  --
+  -- When scoped to a relying party, assertions for challenges
+  -- of any other relying party are treated as not matching.
+  --
+  IF verify_assertion.relying_party_id IS NULL
+  OR verify_assertion.relying_party_id = webauthn.assertion_challenge_relying_party_id(_challenge)
+  THEN
+    user_id := webauthn.insert_assertion(
+      credential_id := _credential_id,
+      credential_type := verify_assertion.credential_type,
+      authenticator_data := webauthn.base64url_decode(verify_assertion.authenticator_data),
+      client_data_json := webauthn.base64url_decode(verify_assertion.client_data_json),
+      challenge := _challenge,
+      signature := webauthn.base64url_decode(verify_assertion.signature),
+      user_handle := webauthn.base64url_decode(NULLIF(verify_assertion.user_handle,'')),
+      verified_at := verify_assertion.verified_at,
+      client_extension_results := verify_assertion.client_extension_results
+    );
+  END IF;
+EXCEPTION WHEN OTHERS THEN
+  --
+  -- Raising the error would roll back the recorded failure,
+  -- so when lockouts are enabled, an assertion for a known credential failing verification
+  -- is recorded and NULL is returned instead, like when no credential matches.
+  -- This includes a malformed signature or authenticator data, raised by the functions parsing them,
+  -- and not only the violated check constraints of webauthn.assertions, such as verified_signature.
+  -- Any other error, such as a replay or an unknown credential, is raised as usual.
+  --
+  GET STACKED DIAGNOSTICS _sqlstate = RETURNED_SQLSTATE, _error_message = MESSAGE_TEXT;
+  IF _sqlstate = 'P0001' OR left(_sqlstate,2) IN ('22','23') AND _sqlstate <> '23505' THEN
+    IF NOT EXISTS (SELECT FROM webauthn.lockout_policies)
+    OR NOT EXISTS (SELECT FROM webauthn.credentials WHERE credentials.credential_id = _credential_id)
+    THEN
+      RAISE;
+    END IF;
+  ELSE
+    RAISE;
+  END IF;
+END;
+
+IF user_id IS NULL AND EXISTS (SELECT FROM webauthn.lockout_policies) THEN
+  PERFORM webauthn.record_assertion_failure(
+    credential_id := _credential_id,
+    user_name := _user_name,
+    relying_party_id := _relying_party_id,
+    challenge := _challenge,
+    sqlstate := _sqlstate,
+    error_message := _error_message,
+    failed_at := verify_assertion.verified_at
+  );
+END IF;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.verify_assertion(
+  OUT user_id bytea,
+  credential jsonb,
+  verified_at timestamptz DEFAULT now(),
+  relying_party_id text DEFAULT NULL
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+SELECT webauthn.verify_assertion(
+  credential_id := public_key_credential->>'id',
+  credential_type := (public_key_credential->>'type')::webauthn.credential_type,
+  authenticator_data := public_key_credential->'response'->>'authenticatorData',
+  client_data_json := public_key_credential->'response'->>'clientDataJSON',
+  signature := public_key_credential->'response'->>'signature',
+  user_handle := public_key_credential->'response'->>'userHandle',
+  verified_at := verify_assertion.verified_at,
+  client_extension_results := public_key_credential->'clientExtensionResults',
+  relying_party_id := verify_assertion.relying_party_id
+)
+FROM webauthn.check_public_key_credential(
+  credential := verify_assertion.credential,
+  response_members := ARRAY['clientDataJSON','authenticatorData','signature']
+) AS public_key_credential
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.verify_assertion_payload(
+  OUT user_id bytea,
+  OUT payload jsonb,
+  credential_id text,
+  credential_type webauthn.credential_type,
+  authenticator_data text,
+  client_data_json text,
+  signature text,
+  user_handle text,
+  verified_at timestamptz DEFAULT now(),
+  client_extension_results jsonb DEFAULT NULL,
+  relying_party_id text DEFAULT NULL
+)
+RETURNS record
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Like webauthn.verify_assertion(), but also returns the payload
+-- the challenge commits to, once the assertion has been verified.
+--
+SELECT
+  verified_assertion.user_id,
+  CASE WHEN verified_assertion.user_id IS NOT NULL THEN assertion_challenges.payload END
+FROM webauthn.verify_assertion(
+  credential_id := verify_assertion_payload.credential_id,
+  credential_type := verify_assertion_payload.credential_type,
+  authenticator_data := verify_assertion_payload.authenticator_data,
+  client_data_json := verify_assertion_payload.client_data_json,
+  signature := verify_assertion_payload.signature,
+  user_handle := verify_assertion_payload.user_handle,
+  verified_at := verify_assertion_payload.verified_at,
+  client_extension_results := verify_assertion_payload.client_extension_results,
+  relying_party_id := verify_assertion_payload.relying_party_id
+) AS verified_assertion
+LEFT JOIN webauthn.assertion_challenges
+  ON assertion_challenges.challenge = webauthn.base64url_decode(webauthn.parse_client_data_json(webauthn.base64url_decode(verify_assertion_payload.client_data_json))->>'challenge')
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.verify_assertion_payload(
+  OUT user_id bytea,
+  OUT payload jsonb,
+  credential jsonb,
+  verified_at timestamptz DEFAULT now(),
+  relying_party_id text DEFAULT NULL
+)
+RETURNS record
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+SELECT verified_assertion.user_id, verified_assertion.payload
+FROM webauthn.check_public_key_credential(
+  credential := verify_assertion_payload.credential,
+  response_members := ARRAY['clientDataJSON','authenticatorData','signature']
+) AS public_key_credential
+CROSS JOIN LATERAL webauthn.verify_assertion_payload(
+  credential_id := public_key_credential->>'id',
+  credential_type := (public_key_credential->>'type')::webauthn.credential_type,
+  authenticator_data := public_key_credential->'response'->>'authenticatorData',
+  client_data_json := public_key_credential->'response'->>'clientDataJSON',
+  signature := public_key_credential->'response'->>'signature',
+  user_handle := public_key_credential->'response'->>'userHandle',
+  verified_at := verify_assertion_payload.verified_at,
+  client_extension_results := public_key_credential->'clientExtensionResults',
+  relying_party_id := verify_assertion_payload.relying_party_id
+) AS verified_assertion
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.recent_verification(
+  user_id bytea,
+  max_age interval,
+  require_uv boolean DEFAULT TRUE,
+  at timestamptz DEFAULT now()
+)
+RETURNS timestamptz
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns when the user most recently proved presence, with user verification if required,
+-- within max_age before the given time, or NULL if there is no such verified assertion.
+--
+SELECT max(assertions.verified_at)
+FROM webauthn.assertions
+WHERE assertions.user_id = recent_verification.user_id
+AND assertions.verified_at >  recent_verification.at - recent_verification.max_age
+AND assertions.verified_at <= recent_verification.at
+AND (assertions.user_verified OR NOT recent_verification.require_uv)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.revoke_credential(
+  OUT user_id bytea,
+  credential_id text,
+  revoked_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+WITH revoked_credential AS (
+  UPDATE webauthn.credentials SET
+    revoked_at = revoke_credential.revoked_at
+  WHERE credentials.credential_id = webauthn.base64url_decode(revoke_credential.credential_id)
+  AND credentials.revoked_at IS NULL
+  RETURNING credentials.credential_id, credentials.user_id
+), revoked_sessions AS (
+  UPDATE webauthn.sessions SET
+    revoked_at = revoke_credential.revoked_at
+  FROM revoked_credential
+  WHERE sessions.credential_id = revoked_credential.credential_id
+  AND sessions.revoked_at IS NULL
+)
+SELECT revoked_credential.user_id FROM revoked_credential
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.create_session(
+  signature text,
+  expires_in interval DEFAULT '12 hours'::interval,
+  idle_timeout interval DEFAULT '30 minutes'::interval,
+  max_age interval DEFAULT '5 minutes'::interval,
+  created_at timestamptz DEFAULT now()
+)
+RETURNS text
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Issues a session for the assertion verified by webauthn.verify_assertion() with the given signature.
+-- Only the hash of the random token is stored, so the returned token cannot be recovered afterwards.
+-- The assertion must have been verified at most max_age before the session is created,
+-- so that a signature obtained later on, e.g. from a log, cannot be turned into a session.
+--
+WITH new_token AS (
+  SELECT public.gen_random_bytes(32) AS token
+), inserted_session AS (
+  INSERT INTO webauthn.sessions (token_hash, signature, credential_id, user_id, created_at, expires_at, idle_timeout, last_used_at)
+  SELECT
+    public.digest(new_token.token,'sha256'),
+    assertions.signature,
+    assertions.credential_id,
+    assertions.user_id,
+    create_session.created_at,
+    create_session.created_at + create_session.expires_in,
+    create_session.idle_timeout,
+    create_session.created_at
+  FROM new_token
+  CROSS JOIN webauthn.assertions
+  JOIN webauthn.credentials ON credentials.credential_id = assertions.credential_id
+                           AND credentials.revoked_at IS NULL
+  WHERE assertions.signature = webauthn.base64url_decode(create_session.signature)
+  AND assertions.verified_at <= create_session.created_at
+  AND assertions.verified_at >= create_session.created_at - create_session.max_age
+  RETURNING TRUE
+)
+SELECT webauthn.base64url_encode(new_token.token)
+FROM new_token
+JOIN inserted_session ON TRUE
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.validate_session(
+  OUT user_id bytea,
+  token text,
+  validated_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+UPDATE webauthn.sessions SET
+  last_used_at = greatest(sessions.last_used_at, validate_session.validated_at)
+WHERE sessions.token_hash = public.digest(webauthn.base64url_decode(validate_session.token),'sha256')
+AND sessions.revoked_at IS NULL
+AND validate_session.validated_at < sessions.expires_at
+AND validate_session.validated_at < sessions.last_used_at + sessions.idle_timeout
+RETURNING sessions.user_id
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.revoke_sessions(
+  user_id bytea,
+  revoked_at timestamptz DEFAULT now()
+)
+RETURNS bigint
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+WITH revoked_sessions AS (
+  UPDATE webauthn.sessions SET
+    revoked_at = revoke_sessions.revoked_at
+  WHERE sessions.user_id = revoke_sessions.user_id
+  AND sessions.revoked_at IS NULL
+  RETURNING TRUE
+)
+SELECT count(*) FROM revoked_sessions
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.recovery_code_hash(code text)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql AS $$
+--
+-- Codes are hashed without separators and case insensitively,
+-- so that "1a2b-3c4d-5e6f-7a8b" and "1A2B3C4D5E6F7A8B" are the same code.
+--
+SELECT public.digest(lower(regexp_replace(code,'[^0-9A-Fa-f]','','g')),'sha256')
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.generate_recovery_codes(
+  user_id bytea,
+  n integer DEFAULT 10,
+  generated_at timestamptz DEFAULT now()
+)
+RETURNS SETOF text
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Replaces the user's unused recovery codes with n new ones.
+-- Only the hashes are stored, so the returned codes cannot be shown again.
+--
+WITH revoked_codes AS (
+  UPDATE webauthn.recovery_codes SET
+    revoked_at = generate_recovery_codes.generated_at
+  WHERE recovery_codes.user_id = generate_recovery_codes.user_id
+  AND recovery_codes.redeemed_at IS NULL
+  AND recovery_codes.revoked_at IS NULL
+), new_codes AS (
+  SELECT
+    ordinality,
+    array_to_string(regexp_match(encode(public.gen_random_bytes(8),'hex'),'^(.{4})(.{4})(.{4})(.{4})$'),'-') AS code
+  FROM generate_series(1, generate_recovery_codes.n) WITH ORDINALITY
+), inserted_codes AS (
+  INSERT INTO webauthn.recovery_codes (user_id, code_hash, generated_at)
+  SELECT generate_recovery_codes.user_id, webauthn.recovery_code_hash(new_codes.code), generate_recovery_codes.generated_at
+  FROM new_codes
+)
+SELECT new_codes.code FROM new_codes ORDER BY new_codes.ordinality
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.redeem_recovery_code(
+  user_id bytea,
+  code text,
+  redeemed_at timestamptz DEFAULT now()
+)
+RETURNS boolean
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+WITH redeemed_code AS (
+  UPDATE webauthn.recovery_codes SET
+    redeemed_at = redeem_recovery_code.redeemed_at
+  WHERE recovery_codes.user_id = redeem_recovery_code.user_id
+  AND recovery_codes.code_hash = webauthn.recovery_code_hash(redeem_recovery_code.code)
+  AND recovery_codes.redeemed_at IS NULL
+  AND recovery_codes.revoked_at IS NULL
+  RETURNING TRUE
+)
+SELECT EXISTS (SELECT FROM redeemed_code)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.list_credentials(user_id bytea)
+RETURNS TABLE (
+  credential_type text,
+  credential_id text,
+  created_at timestamptz,
+  last_used_at timestamptz,
+  revoked_at timestamptz
+)
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Lists the user's credentials, including recovery codes,
+-- which have no credential_id and are used at most once.
+--
+SELECT
+  credentials.credential_type::text,
+  webauthn.base64url_encode(credentials.credential_id),
+  credentials.credential_at,
+  (SELECT max(assertions.verified_at) FROM webauthn.assertions WHERE assertions.credential_id = credentials.credential_id),
+  credentials.revoked_at
+FROM webauthn.credentials
+WHERE credentials.user_id = list_credentials.user_id
+UNION ALL
+SELECT
+  'recovery-code',
+  NULL,
+  recovery_codes.generated_at,
+  recovery_codes.redeemed_at,
+  recovery_codes.revoked_at
+FROM webauthn.recovery_codes
+WHERE recovery_codes.user_id = list_credentials.user_id
+ORDER BY 3, 1, 2, 4, 5
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.der_encode(tag integer, content bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Encodes a DER TLV, with the length in short form if less than 128,
+-- or else in long form with as few length bytes as possible.
+--
+-- https://www.itu.int/rec/T-REC-X.690 section 8.1.3 and 10.1
+--
+SELECT
+  set_byte('\x00'::bytea, 0, der_encode.tag)
+  || CASE
+    WHEN length(der_encode.content) < 128 THEN set_byte('\x00'::bytea, 0, length(der_encode.content))
+    WHEN length(der_encode.content) < 256 THEN '\x81'::bytea || set_byte('\x00'::bytea, 0, length(der_encode.content))
+    ELSE '\x82'::bytea || substring(int4send(length(der_encode.content)) from 3 for 2)
+  END
+  || der_encode.content
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.public_key_spki(kty integer, crv integer, public_key bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Encodes a public key, as stored in webauthn.credentials, as a DER SubjectPublicKeyInfo,
+-- i.e. SEQUENCE { SEQUENCE { algorithm OID, parameters }, BIT STRING key },
+-- with the EC2 point uncompressed, i.e. \x04 || x || y.
+--
+-- https://www.rfc-editor.org/rfc/rfc5480#section-2
+-- https://www.rfc-editor.org/rfc/rfc8410#section-4
+--
+SELECT webauthn.der_encode(48, algorithm_identifier || webauthn.der_encode(3, '\x00'::bytea || subject_public_key))
+FROM (
+  SELECT
+    CASE
+      WHEN public_key_spki.kty = 2 AND public_key_spki.crv IN (1,2,3) THEN webauthn.der_encode(48,
+        '\x06072a8648ce3d0201'::bytea -- id-ecPublicKey
+        || CASE public_key_spki.crv
+          WHEN 1 THEN '\x06082a8648ce3d030107'::bytea -- secp256r1
+          WHEN 2 THEN '\x06052b81040022'::bytea -- secp384r1
+          WHEN 3 THEN '\x06052b81040023'::bytea -- secp521r1
+        END
+      )
+      WHEN public_key_spki.kty = 1 AND public_key_spki.crv = 6 THEN '\x300506032b6570'::bytea -- id-Ed25519
+      WHEN public_key_spki.kty = 1 AND public_key_spki.crv = 7 THEN '\x300506032b6571'::bytea -- id-Ed448
+      ELSE webauthn.raise_error('Unsupported public key', json_build_object('kty', public_key_spki.kty, 'crv', public_key_spki.crv), NULL::bytea)
+    END AS algorithm_identifier,
+    CASE public_key_spki.kty
+      WHEN 2 THEN '\x04'::bytea || public_key_spki.public_key
+      ELSE public_key_spki.public_key
+    END AS subject_public_key
+) AS spki
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.public_key_jwk(kty integer, alg integer, crv integer, public_key bytea)
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Encodes a public key, as stored in webauthn.credentials, as a JSON Web Key,
+-- where an EC2 public key is x || y, with both coordinates of equal length.
+--
+-- https://www.rfc-editor.org/rfc/rfc7518#section-6.2.1
+-- https://www.rfc-editor.org/rfc/rfc8037#section-2
+-- https://www.rfc-editor.org/rfc/rfc8812#section-3
+--
+SELECT CASE
+  WHEN public_key_jwk.kty = 2 AND public_key_jwk.crv IN (1,2,3) THEN jsonb_build_object(
+    'kty', 'EC',
+    'crv', CASE public_key_jwk.crv WHEN 1 THEN 'P-256' WHEN 2 THEN 'P-384' WHEN 3 THEN 'P-521' END,
+    'x', webauthn.base64url_encode(substring(public_key_jwk.public_key from 1 for length(public_key_jwk.public_key)/2)),
+    'y', webauthn.base64url_encode(substring(public_key_jwk.public_key from length(public_key_jwk.public_key)/2+1))
+  )
+  WHEN public_key_jwk.kty = 1 AND public_key_jwk.crv IN (6,7) THEN jsonb_build_object(
+    'kty', 'OKP',
+    'crv', CASE public_key_jwk.crv WHEN 6 THEN 'Ed25519' WHEN 7 THEN 'Ed448' END,
+    'x', webauthn.base64url_encode(public_key_jwk.public_key)
+  )
+  ELSE webauthn.raise_error('Unsupported public key', json_build_object('kty', public_key_jwk.kty, 'crv', public_key_jwk.crv), NULL::jsonb)
+END
+|| jsonb_strip_nulls(jsonb_build_object(
+  'alg', CASE public_key_jwk.alg WHEN -7 THEN 'ES256' WHEN -35 THEN 'ES384' WHEN -36 THEN 'ES512' WHEN -8 THEN 'EdDSA' END,
+  'use', 'sig'
+))
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.credential_public_key_spki(credential_id text)
+RETURNS bytea
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the public key of the credential as a DER SubjectPublicKeyInfo,
+-- e.g. for services verifying assertions on their own.
+--
+SELECT webauthn.public_key_spki(credentials.kty, credentials.crv, credentials.public_key)
+FROM webauthn.credentials
+WHERE credentials.credential_id = webauthn.base64url_decode(credential_public_key_spki.credential_id)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.credential_public_key_pem(credential_id text)
+RETURNS text
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the public key of the credential as a PEM encoded SubjectPublicKeyInfo,
+-- with the base64 wrapped at 64 characters per line.
+--
+-- https://www.rfc-editor.org/rfc/rfc7468#section-13
+--
+SELECT
+  E'-----BEGIN PUBLIC KEY-----\n'
+  || (
+    SELECT string_agg(substr(spki.base64, line_start, 64), E'\n' ORDER BY line_start)
+    FROM generate_series(1, length(spki.base64), 64) AS line_start
+  )
+  || E'\n-----END PUBLIC KEY-----\n'
+FROM (
+  SELECT translate(encode(webauthn.credential_public_key_spki(credential_public_key_pem.credential_id), 'base64'), E'\n', '') AS base64
+) AS spki
+WHERE spki.base64 IS NOT NULL
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.credential_public_key_jwk(credential_id text)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the public key of the credential as a JSON Web Key,
+-- with the credential ID as its key ID.
+--
+-- https://www.rfc-editor.org/rfc/rfc7517#section-4.5
+--
+SELECT webauthn.public_key_jwk(credentials.kty, credentials.alg, credentials.crv, credentials.public_key)
+  || jsonb_build_object('kid', webauthn.base64url_encode(credentials.credential_id))
+FROM webauthn.credentials
+WHERE credentials.credential_id = webauthn.base64url_decode(credential_public_key_jwk.credential_id)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.import_u2f_credential(
+  key_handle text,
+  public_key text,
+  appid text,
+  user_name text,
+  user_id bytea,
+  user_display_name text,
+  relying_party_name text,
+  relying_party_id text DEFAULT NULL,
+  sign_count bigint DEFAULT 0,
+  imported_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Imports a legacy FIDO U2F registration, for use with the FIDO AppID extension.
+--
+-- Since webauthn.credentials only stores credentials created through the WebAuthn ceremony,
+-- the registration is wrapped in a "none" attestation object with a credential challenge
+-- derived from the key handle, with the U2F public key converted to a COSE_Key
+-- and the rpIdHash set to the hash of the AppID, as the authenticator computed it.
+--
+-- An unknown relying party is registered on first use,
+-- without any origin restrictions or policies.
+--
+-- Since the AppID extension only takes a single AppID, which webauthn.get_credentials()
+-- adds to the request options, all U2F credentials of a user and relying party
+-- must share the same AppID, so importing one with another AppID raises an error.
+--
+INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name)
+SELECT import_u2f_credential.relying_party_id, import_u2f_credential.relying_party_name
+WHERE import_u2f_credential.relying_party_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-appid-extension
+-- https://fidoalliance.org/specs/fido-u2f-v1.2-ps-20170411/fido-u2f-raw-message-formats-v1.2-ps-20170411.html#registration-response-message-success
+--
+INSERT INTO webauthn.credential_challenges
+       (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, user_verification, attestation, timeout, challenge_at)
+SELECT
+  public.digest(import_u2f_credential.appid || import_u2f_credential.key_handle,'sha256'),
+  import_u2f_credential.user_name,
+  import_u2f_credential.user_id,
+  import_u2f_credential.user_display_name,
+  import_u2f_credential.relying_party_name,
+  import_u2f_credential.relying_party_id,
+  'discouraged',
+  'none',
+  '5 minutes',
+  import_u2f_credential.imported_at
+WHERE CASE
+  WHEN length(webauthn.base64url_decode(import_u2f_credential.public_key)) <> 65
+    OR get_byte(webauthn.base64url_decode(import_u2f_credential.public_key),0) <> 4
+  THEN webauthn.raise_error('Invalid U2F public key', json_build_object('expected', 'uncompressed P-256 point'), NULL::boolean)
+  WHEN import_u2f_credential.sign_count NOT BETWEEN 0 AND 4294967295
+  THEN webauthn.raise_error('Invalid U2F sign count', json_build_object('sign_count', import_u2f_credential.sign_count), NULL::boolean)
+  WHEN substring(import_u2f_credential.appid from '^https://[^/]+') IS NULL
+  THEN webauthn.raise_error('Invalid AppID', json_build_object('appid', import_u2f_credential.appid), NULL::boolean)
+  WHEN EXISTS (
+    SELECT 1 FROM webauthn.credentials
+    WHERE (credentials.user_name = import_u2f_credential.user_name OR credentials.user_id = import_u2f_credential.user_id)
+    AND credentials.relying_party_id IS NOT DISTINCT FROM import_u2f_credential.relying_party_id
+    AND credentials.appid <> import_u2f_credential.appid
+  )
+  THEN webauthn.raise_error('Conflicting AppID', json_build_object('appid', import_u2f_credential.appid), NULL::boolean)
+  ELSE TRUE
+END;
+
+INSERT INTO webauthn.credentials (credential_id, credential_type, attestation_object, client_data_json, challenge, user_name, user_id, credential_at, appid, relying_party_id)
+SELECT
+  key_handle,
+  'public-key',
+  '\xa3'::bytea
+  || '\x63'::bytea || convert_to('fmt','UTF8') || '\x64'::bytea || convert_to('none','UTF8')
+  || '\x67'::bytea || convert_to('attStmt','UTF8') || '\xa0'::bytea
+  || '\x68'::bytea || convert_to('authData','UTF8')
+  || '\x59'::bytea || int2send(length(authenticator_data)::smallint)
+  || authenticator_data,
+  convert_to(json_build_object(
+    'type', 'webauthn.create',
+    'challenge', webauthn.base64url_encode(challenge),
+    'origin', substring(import_u2f_credential.appid from '^https://[^/]+')
+  )::text,'UTF8'),
+  challenge,
+  import_u2f_credential.user_name,
+  import_u2f_credential.user_id,
+  import_u2f_credential.imported_at,
+  import_u2f_credential.appid,
+  import_u2f_credential.relying_party_id
+FROM webauthn.base64url_decode(import_u2f_credential.key_handle) AS key_handle
+CROSS JOIN webauthn.base64url_decode(import_u2f_credential.public_key) AS public_key
+CROSS JOIN public.digest(import_u2f_credential.appid || import_u2f_credential.key_handle,'sha256') AS challenge
+CROSS JOIN LATERAL (
+  SELECT
+    public.digest(import_u2f_credential.appid,'sha256')
+    || '\x41'::bytea
+    -- The U2F counter is an unsigned 32-bit integer
+    || substring(int8send(import_u2f_credential.sign_count) from 5 for 4)
+    || '\x00000000000000000000000000000000'::bytea
+    || int2send(length(key_handle)::smallint)
+    || key_handle
+    -- COSE_Key: {1: 2 (EC2), 3: -7 (ES256), -1: 1 (P-256), -2: x, -3: y}
+    || '\xa5010203262001215820'::bytea || substring(public_key,2,32)
+    || '\x225820'::bytea || substring(public_key,34,32)
+) AS u2f(authenticator_data)
+RETURNING credentials.credential_id
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.signal_all_accepted_credentials(
+  user_id bytea,
+  relying_party_id text
+)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the options for PublicKeyCredential.signalAllAcceptedCredentials(),
+-- listing the credentials of the user that have not been revoked.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-allacceptedcredentialsoptions
+--
+
+--
+-- Without the relying_party_id, no credential would match,
+-- which would tell the client to remove all the user's credentials.
+--
+SELECT webauthn.raise_error('Relying party ID missing', json_build_object('user_id', webauthn.base64url_encode(signal_all_accepted_credentials.user_id)), NULL::boolean)
+WHERE signal_all_accepted_credentials.relying_party_id IS NULL;
+
+SELECT jsonb_build_object(
+  'rpId', signal_all_accepted_credentials.relying_party_id,
+  'userId', webauthn.base64url_encode(signal_all_accepted_credentials.user_id),
+  'allAcceptedCredentialIds', COALESCE(jsonb_agg(
+    webauthn.base64url_encode(credentials.credential_id)
+  ORDER BY credentials.credential_id),jsonb_build_array())
+)
+FROM webauthn.credentials
+WHERE credentials.user_id = signal_all_accepted_credentials.user_id
+AND credentials.relying_party_id = signal_all_accepted_credentials.relying_party_id
+AND credentials.revoked_at IS NULL
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.signal_unknown_credential(
+  credential_id text,
+  relying_party_id text
+)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the options for PublicKeyCredential.signalUnknownCredential(),
+-- or NULL if the credential exists and has not been revoked.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-unknowncredentialoptions
+--
+
+--
+-- Without the relying_party_id, no credential would match,
+-- which would tell the client to remove a credential that exists.
+--
+SELECT webauthn.raise_error('Relying party ID missing', json_build_object('credential_id', signal_unknown_credential.credential_id), NULL::boolean)
+WHERE signal_unknown_credential.relying_party_id IS NULL;
+
+SELECT jsonb_build_object(
+  'rpId', signal_unknown_credential.relying_party_id,
+  'credentialId', signal_unknown_credential.credential_id
+)
+WHERE NOT EXISTS (
+  SELECT 1 FROM webauthn.credentials
+  WHERE credentials.credential_id = webauthn.base64url_decode(signal_unknown_credential.credential_id)
+  AND credentials.relying_party_id = signal_unknown_credential.relying_party_id
+  AND credentials.revoked_at IS NULL
+)
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.signal_current_user_details(
+  user_id bytea,
+  relying_party_id text
+)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the options for PublicKeyCredential.signalCurrentUserDetails(),
+-- using the user_name and user_display_name of the most recently stored credential,
+-- or NULL if the user has no credentials that have not been revoked.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-currentuserdetailsoptions
+--
+
+--
+-- Without the relying_party_id, no credential would match.
+--
+SELECT webauthn.raise_error('Relying party ID missing', json_build_object('user_id', webauthn.base64url_encode(signal_current_user_details.user_id)), NULL::boolean)
+WHERE signal_current_user_details.relying_party_id IS NULL;
+
+SELECT jsonb_build_object(
+  'rpId', signal_current_user_details.relying_party_id,
+  'userId', webauthn.base64url_encode(signal_current_user_details.user_id),
+  'name', credential_challenges.user_name,
+  'displayName', credential_challenges.user_display_name
+)
+FROM webauthn.credentials
+JOIN webauthn.credential_challenges ON credential_challenges.challenge = credentials.challenge
+WHERE credentials.user_id = signal_current_user_details.user_id
+AND credentials.relying_party_id = signal_current_user_details.relying_party_id
+AND credentials.revoked_at IS NULL
+ORDER BY credentials.credential_at DESC
+LIMIT 1
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.grant_api_access(role_name name)
+RETURNS void
+LANGUAGE plpgsql
+AS $$
+DECLARE
+_api_functions text[] := ARRAY[
+  'init_credential',
+  'store_credential',
+  'get_credentials',
+  'get_payment_credentials',
+  'verify_assertion',
+  'verify_assertion_payload',
+  'recent_verification',
+  'revoke_credential',
+  'create_session',
+  'validate_session',
+  'revoke_sessions',
+  'generate_recovery_codes',
+  'redeem_recovery_code',
+  'list_credentials',
+  'credential_public_key_spki',
+  'credential_public_key_pem',
+  'credential_public_key_jwk',
+  'import_u2f_credential',
+  'signal_all_accepted_credentials',
+  'signal_unknown_credential',
+  'signal_current_user_details'
+];
+_function regprocedure;
+BEGIN
+--
+-- The role is only granted EXECUTE on the API functions,
+-- which are SECURITY DEFINER and run as the extension owner,
+-- so the role needs no privileges on the tables,
+-- and can't e.g. SELECT public_key from webauthn.credentials
+-- or INSERT into webauthn.assertions directly.
+--
+EXECUTE format('REVOKE ALL ON ALL TABLES IN SCHEMA webauthn FROM %I', grant_api_access.role_name);
+EXECUTE format('REVOKE ALL ON ALL SEQUENCES IN SCHEMA webauthn FROM %I', grant_api_access.role_name);
+EXECUTE format('REVOKE ALL ON ALL FUNCTIONS IN SCHEMA webauthn FROM %I', grant_api_access.role_name);
+EXECUTE format('GRANT USAGE ON SCHEMA webauthn TO %I', grant_api_access.role_name);
+
+FOR _function IN
+  SELECT pg_proc.oid FROM pg_catalog.pg_proc
+  WHERE pg_proc.pronamespace = 'webauthn'::regnamespace
+  AND pg_proc.proname = ANY(_api_functions)
+LOOP
+  EXECUTE format('GRANT EXECUTE ON FUNCTION %s TO %I', _function, grant_api_access.role_name);
+END LOOP;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.revoke_public_access()
+RETURNS void
+LANGUAGE plpgsql
+AS $$
+DECLARE
+_table regclass;
+BEGIN
+--
+-- The functions are never executable by PUBLIC, but privileges on the tables
+-- might have been granted to PUBLIC after the extension was created.
+--
+REVOKE ALL ON ALL TABLES IN SCHEMA webauthn FROM PUBLIC;
+REVOKE ALL ON ALL SEQUENCES IN SCHEMA webauthn FROM PUBLIC;
+
+--
+-- Row level security limits roles other than the owner to the rows of the relying parties
+-- whose role_name they're a member of, by the relying_party_role policies of the tables,
+-- even if privileges on the tables are granted, e.g. for reporting per relying party.
+-- The lockout_policies table is global and has no policy, so no rows are visible to other roles.
+--
+FOR _table IN
+  SELECT pg_class.oid FROM pg_catalog.pg_class
+  WHERE pg_class.relnamespace = 'webauthn'::regnamespace
+  AND pg_class.relkind = 'r'
+LOOP
+  EXECUTE format('ALTER TABLE %s ENABLE ROW LEVEL SECURITY', _table);
+END LOOP;
+END;
+$$;
+
+--
+-- The API functions are SECURITY DEFINER, so none of the functions are executable by PUBLIC,
+-- only by the owner and the roles granted access by webauthn.grant_api_access().
+--
+REVOKE EXECUTE ON ALL FUNCTIONS IN SCHEMA webauthn FROM PUBLIC;
@@ -0,0 +1,6 @@
+CREATE TYPE webauthn.authenticator_attachment AS ENUM (
+  'platform',
+  'cross-platform'
+);
+
+COMMENT ON TYPE webauthn.authenticator_attachment IS 'https://www.w3.org/TR/webauthn-2/#enum-attachment';
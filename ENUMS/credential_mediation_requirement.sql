@@ -0,0 +1,8 @@
+CREATE TYPE webauthn.credential_mediation_requirement AS ENUM (
+  'silent',
+  'optional',
+  'conditional',
+  'required'
+);
+
+COMMENT ON TYPE webauthn.credential_mediation_requirement IS 'https://w3c.github.io/webappsec-credential-management/#enumdef-credentialmediationrequirement';
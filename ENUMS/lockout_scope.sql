@@ -0,0 +1,6 @@
+CREATE TYPE webauthn.lockout_scope AS ENUM (
+  'credential',
+  'user_name'
+);
+
+COMMENT ON TYPE webauthn.lockout_scope IS 'Whether failed assertions are counted per credential_id or per user_name.';
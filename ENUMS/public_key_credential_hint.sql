@@ -0,0 +1,7 @@
+CREATE TYPE webauthn.public_key_credential_hint AS ENUM (
+  'security-key',
+  'client-device',
+  'hybrid'
+);
+
+COMMENT ON TYPE webauthn.public_key_credential_hint IS 'https://www.w3.org/TR/webauthn-3/#enum-hints';
@@ -0,0 +1,7 @@
+CREATE TYPE webauthn.token_binding_policy AS ENUM (
+  'ignore',
+  'forbid',
+  'require'
+);
+
+COMMENT ON TYPE webauthn.token_binding_policy IS 'Whether the tokenBinding status "present" is accepted, refused or required in the client data, https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-tokenbinding';
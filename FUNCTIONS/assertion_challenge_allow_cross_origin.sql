@@ -0,0 +1,9 @@
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_allow_cross_origin(challenge bytea)
+RETURNS boolean
+STABLE
+LANGUAGE sql AS $$
+SELECT COALESCE(assertion_challenges.allow_cross_origin, relying_parties.allow_cross_origin, FALSE)
+FROM webauthn.assertion_challenges
+LEFT JOIN webauthn.relying_parties ON relying_parties.relying_party_id = assertion_challenges.relying_party_id
+WHERE assertion_challenges.challenge = $1
+$$;
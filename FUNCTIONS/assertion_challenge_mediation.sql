@@ -0,0 +1,6 @@
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_mediation(challenge bytea)
+RETURNS webauthn.credential_mediation_requirement
+STABLE
+LANGUAGE sql AS $$
+SELECT mediation FROM webauthn.assertion_challenges WHERE challenge = $1
+$$;
@@ -0,0 +1,6 @@
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_payment(challenge bytea)
+RETURNS jsonb
+STABLE
+LANGUAGE sql AS $$
+SELECT payment FROM webauthn.assertion_challenges WHERE challenge = $1
+$$;
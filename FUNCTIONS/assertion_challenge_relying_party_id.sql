@@ -0,0 +1,6 @@
+CREATE OR REPLACE FUNCTION webauthn.assertion_challenge_relying_party_id(challenge bytea)
+RETURNS text
+STABLE
+LANGUAGE sql AS $$
+SELECT relying_party_id FROM webauthn.assertion_challenges WHERE challenge = $1
+$$;
@@ -0,0 +1,34 @@
+CREATE OR REPLACE FUNCTION webauthn.canonical_json(value jsonb)
+RETURNS text
+IMMUTABLE
+LANGUAGE sql AS $$
+--
+-- https://www.rfc-editor.org/rfc/rfc8785
+-- JSON Canonicalization Scheme: no whitespace, object members sorted by the UTF-16 code units of their keys,
+-- strings escaped like JSON.stringify(), which is what to_json() and the jsonb output function do,
+-- and numbers formatted like ECMAScript.
+--
+SELECT CASE jsonb_typeof(value)
+  WHEN 'object' THEN '{' || COALESCE((
+    SELECT string_agg(to_json(member.key)::text || ':' || webauthn.canonical_json(member.value), ',' ORDER BY utf16.code_units COLLATE "C")
+    FROM jsonb_each(value) AS member
+    CROSS JOIN LATERAL (
+      --
+      -- Characters outside the BMP are encoded as surrogate pairs in UTF-16,
+      -- so they sort before U+E000..U+FFFF, unlike in code point order.
+      --
+      SELECT string_agg(CASE
+        WHEN ascii(chr) < 65536 THEN lpad(to_hex(ascii(chr)), 4, '0')
+        ELSE to_hex(55296 + ((ascii(chr) - 65536) >> 10)) || to_hex(56320 + ((ascii(chr) - 65536) & 1023))
+      END, '' ORDER BY position) AS code_units
+      FROM regexp_split_to_table(member.key, '') WITH ORDINALITY AS chars (chr, position)
+    ) AS utf16
+  ), '') || '}'
+  WHEN 'array' THEN '[' || COALESCE((
+    SELECT string_agg(webauthn.canonical_json(element.value), ',' ORDER BY element.position)
+    FROM jsonb_array_elements(value) WITH ORDINALITY AS element (value, position)
+  ), '') || ']'
+  WHEN 'number' THEN webauthn.canonical_json_number(value::text::float8)
+  ELSE value::text
+END
+$$;
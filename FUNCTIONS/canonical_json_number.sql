@@ -0,0 +1,52 @@
+CREATE OR REPLACE FUNCTION webauthn.canonical_json_number(number float8)
+RETURNS text
+IMMUTABLE
+SET extra_float_digits = 1
+LANGUAGE sql AS $$
+--
+-- https://www.rfc-editor.org/rfc/rfc8785#section-3.2.2.3
+-- Formats the number like ECMAScript's Number.prototype.toString(),
+-- i.e. from the fewest significant digits that round-trip, in its notation.
+-- float8out doesn't always return the fewest digits, e.g. for large integers,
+-- so its digits are rounded to the fewest that still round-trip.
+-- Rounding up beyond the largest float8 would fail to cast back, so isn't tried.
+--
+WITH float8out AS (
+  SELECT
+    abs(number)::text::numeric AS value,
+    length(parts[1]) + COALESCE(parts[3]::integer, 0)
+      - (length(parts[1] || COALESCE(parts[2], '')) - length(ltrim(parts[1] || COALESCE(parts[2], ''), '0'))) AS n
+  FROM regexp_match(abs(number)::text, '^(\d+)(?:\.(\d+))?(?:e([+-]\d+))?$') AS parts
+), shortest AS (
+  --
+  -- The digits without leading and trailing zeros,
+  -- and the decimal exponent n, i.e. the number is 0.digits * 10^n
+  --
+  SELECT
+    trim(BOTH '0' FROM decimal.integer_part || decimal.fraction_part) AS digits,
+    CASE
+      WHEN decimal.integer_part <> '0' THEN length(decimal.integer_part)
+      ELSE length(ltrim(decimal.fraction_part, '0')) - length(decimal.fraction_part)
+    END AS n
+  FROM float8out
+  CROSS JOIN generate_series(1, 17) AS precision
+  CROSS JOIN LATERAL round(float8out.value, precision - float8out.n) AS rounded
+  CROSS JOIN LATERAL (
+    SELECT split_part(rounded::text, '.', 1) AS integer_part, split_part(rounded::text, '.', 2) AS fraction_part
+  ) AS decimal
+  WHERE CASE WHEN rounded <= float8out.value OR abs(number) < 1e308 THEN rounded::float8 = abs(number) END
+  ORDER BY precision
+  LIMIT 1
+)
+SELECT CASE
+  WHEN number = 0 THEN '0'
+  ELSE CASE WHEN number < 0 THEN '-' ELSE '' END || CASE
+    WHEN length(digits) <= n AND n <= 21 THEN digits || repeat('0', n - length(digits))
+    WHEN 0 < n AND n <= 21 THEN left(digits, n) || '.' || substr(digits, n + 1)
+    WHEN -6 < n AND n <= 0 THEN '0.' || repeat('0', -n) || digits
+    ELSE left(digits, 1) || CASE WHEN length(digits) > 1 THEN '.' || substr(digits, 2) ELSE '' END
+      || 'e' || CASE WHEN n > 0 THEN '+' ELSE '-' END || abs(n - 1)
+  END
+END
+FROM shortest
+$$;
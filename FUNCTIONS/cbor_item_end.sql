@@ -0,0 +1,70 @@
+CREATE OR REPLACE FUNCTION webauthn.cbor_item_end(cbor bytea, start_at integer)
+RETURNS integer
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Returns the zero-based offset following the CBOR data item starting at start_at,
+-- without decoding it, or NULL if the item is truncated,
+-- or uses an indefinite length, which CTAP2 canonical CBOR doesn't allow.
+--
+-- https://www.rfc-editor.org/rfc/rfc8949.html#section-3
+-- https://fidoalliance.org/specs/fido-v2.0-ps-20190130/fido-client-to-authenticator-protocol-v2.0-ps-20190130.html#ctap2-canonical-cbor-encoding-form
+--
+DECLARE
+_length integer := length(cbor);
+_pos integer := start_at;
+_pending bigint := 1;
+_major_type integer;
+_additional_info integer;
+_argument bigint;
+BEGIN
+WHILE _pending > 0 LOOP
+  -- Every pending item needs at least one byte
+  IF _pending > _length - _pos THEN
+    RETURN NULL;
+  END IF;
+  _major_type := get_byte(cbor,_pos) >> 5;
+  _additional_info := get_byte(cbor,_pos) & 31;
+  _pos := _pos + 1;
+  IF _additional_info < 24 THEN
+    _argument := _additional_info;
+  ELSIF _additional_info <= 27 THEN
+    IF _pos + (1 << (_additional_info - 24)) > _length THEN
+      RETURN NULL;
+    END IF;
+    _argument := 0;
+    FOR i IN 1..(1 << (_additional_info - 24)) LOOP
+      _argument := (_argument << 8) + get_byte(cbor,_pos);
+      _pos := _pos + 1;
+    END LOOP;
+  ELSE
+    RETURN NULL;
+  END IF;
+  _pending := _pending - 1;
+  IF _major_type IN (2,3) THEN
+    -- Byte and text strings
+    IF _argument < 0 OR _argument > _length - _pos THEN
+      RETURN NULL;
+    END IF;
+    _pos := _pos + _argument;
+  ELSIF _major_type = 4 THEN
+    -- Arrays
+    IF _argument < 0 THEN
+      RETURN NULL;
+    END IF;
+    _pending := _pending + _argument;
+  ELSIF _major_type = 5 THEN
+    -- Maps
+    IF _argument < 0 OR _argument > _length THEN
+      RETURN NULL;
+    END IF;
+    _pending := _pending + 2 * _argument;
+  ELSIF _major_type = 6 THEN
+    -- Tags are followed by the tagged item
+    _pending := _pending + 1;
+  END IF;
+END LOOP;
+RETURN _pos;
+END;
+$$;
@@ -0,0 +1,93 @@
+CREATE OR REPLACE FUNCTION webauthn.certificate_serial_number(certificate bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Returns the serialNumber of a DER encoded X.509 certificate,
+-- without the zero byte keeping the sign bit of the INTEGER clear,
+-- i.e. the bytes shown by `openssl x509 -serial`,
+-- or raises an error describing the first violation of DER found on the way.
+--
+-- The rest of the certificate is neither parsed nor verified.
+--
+-- Certificate ::= SEQUENCE { tbsCertificate TBSCertificate, ... }
+-- TBSCertificate ::= SEQUENCE { version [0] EXPLICIT Version DEFAULT v1, serialNumber INTEGER, ... }
+--
+-- https://www.rfc-editor.org/rfc/rfc5280#section-4.1
+-- https://www.itu.int/rec/T-REC-X.690 section 10.1
+--
+DECLARE
+_length integer := length(certificate);
+_offset integer := 0;
+_step integer := 0;
+_tag integer;
+_tag_offset integer;
+_content_length bigint;
+_length_bytes integer;
+_serial_number bytea;
+BEGIN
+LOOP
+  IF _offset + 2 > _length THEN
+    PERFORM webauthn.raise_error('Certificate truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+  END IF;
+  _tag_offset := _offset;
+  _tag := get_byte(certificate,_offset);
+  _content_length := get_byte(certificate,_offset+1);
+  _offset := _offset + 2;
+
+  IF _content_length = 128 THEN
+    PERFORM webauthn.raise_error('Certificate has indefinite length', json_build_object('offset', _offset - 1), NULL::boolean);
+  ELSIF _content_length > 128 THEN
+    _length_bytes := _content_length & 127;
+    IF _offset + _length_bytes > _length THEN
+      PERFORM webauthn.raise_error('Certificate truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+    END IF;
+    _content_length := 0;
+    FOR _j IN 0.._length_bytes-1 LOOP
+      _content_length := (_content_length<<8) + get_byte(certificate,_offset+_j);
+      EXIT WHEN _content_length > _length;
+    END LOOP;
+    IF get_byte(certificate,_offset) = 0 OR _content_length < 128 THEN
+      PERFORM webauthn.raise_error('Certificate length not minimally encoded', json_build_object('offset', _offset - 1), NULL::boolean);
+    END IF;
+    _offset := _offset + _length_bytes;
+  END IF;
+
+  IF _offset + _content_length > _length THEN
+    PERFORM webauthn.raise_error('Certificate truncated', json_build_object('offset', _offset, 'content_length', _content_length, 'length', _length), NULL::boolean);
+  END IF;
+
+  --
+  -- Enter the Certificate and TBSCertificate SEQUENCEs,
+  -- and skip the version, which is absent in v1 certificates.
+  --
+  IF _step < 2 THEN
+    IF _tag <> 48 /* 0x30 SEQUENCE */ THEN
+      PERFORM webauthn.raise_error('Certificate has unexpected tag', json_build_object('offset', _tag_offset, 'tag', _tag, 'expected_tag', 48), NULL::boolean);
+    END IF;
+    IF _step = 0 AND _offset + _content_length < _length THEN
+      PERFORM webauthn.raise_error('Trailing bytes after certificate', json_build_object('offset', _offset + _content_length, 'length', _length), NULL::boolean);
+    END IF;
+    _step := _step + 1;
+    CONTINUE;
+  ELSIF _step = 2 AND _tag = 160 /* 0xa0 [0] EXPLICIT */ THEN
+    _offset := _offset + _content_length::integer;
+    _step := _step + 1;
+    CONTINUE;
+  END IF;
+
+  IF _tag <> 2 /* 0x02 INTEGER */ THEN
+    PERFORM webauthn.raise_error('Certificate has unexpected tag', json_build_object('offset', _tag_offset, 'tag', _tag, 'expected_tag', 2), NULL::boolean);
+  END IF;
+  IF _content_length = 0 THEN
+    PERFORM webauthn.raise_error('Certificate serial number is empty', json_build_object('offset', _offset), NULL::boolean);
+  END IF;
+  _serial_number := substring(certificate from _offset+1 for _content_length::integer);
+  IF get_byte(_serial_number,0) = 0 AND _content_length > 1 THEN
+    _serial_number := substring(_serial_number from 2);
+  END IF;
+  RETURN _serial_number;
+END LOOP;
+END;
+$$;
@@ -0,0 +1,23 @@
+CREATE OR REPLACE FUNCTION webauthn.check_lockout(credential_id bytea, user_name text, relying_party_id text, at timestamptz)
+RETURNS void
+LANGUAGE sql AS $$
+--
+-- user_name is only unique per relying party,
+-- so a lockout only applies to the relying party of the failed assertions.
+--
+SELECT webauthn.raise_error(
+  'Locked out',
+  json_build_object(
+    'scope', lockouts.scope,
+    'locked_until', lockouts.locked_until
+  ),
+  NULL::boolean
+)
+FROM webauthn.lockouts
+WHERE (lockouts.credential_id = check_lockout.credential_id OR lockouts.user_name = check_lockout.user_name)
+AND lockouts.relying_party_id IS NOT DISTINCT FROM check_lockout.relying_party_id
+AND check_lockout.at >= lockouts.locked_at
+AND check_lockout.at < lockouts.locked_until
+ORDER BY lockouts.locked_until DESC, lockouts.scope
+LIMIT 1
+$$;
@@ -0,0 +1,44 @@
+CREATE OR REPLACE FUNCTION webauthn.check_public_key_credential(credential jsonb, response_members text[])
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Validates the shape of a PublicKeyCredential serialized with toJSON(),
+-- i.e. a RegistrationResponseJSON or AuthenticationResponseJSON,
+-- where all binary values are base64url encoded strings.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-registrationresponsejson
+-- https://www.w3.org/TR/webauthn-3/#dictdef-authenticationresponsejson
+--
+SELECT CASE
+  WHEN jsonb_typeof(credential) IS DISTINCT FROM 'object'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', NULL, 'expected', 'object'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'id') IS DISTINCT FROM 'string'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'id', 'expected', 'string'), NULL::jsonb)
+  WHEN credential ? 'rawId' AND credential->'rawId' IS DISTINCT FROM credential->'id'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'rawId', 'expected', 'same value as id'), NULL::jsonb)
+  WHEN credential->>'type' IS DISTINCT FROM 'public-key'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'type', 'expected', 'public-key'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'response') IS DISTINCT FROM 'object'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'response', 'expected', 'object'), NULL::jsonb)
+  WHEN invalid_response_member IS NOT NULL
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'response.' || invalid_response_member, 'expected', 'string'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'response'->'userHandle') NOT IN ('string','null')
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'response.userHandle', 'expected', 'string or null'), NULL::jsonb)
+  WHEN jsonb_typeof(credential->'clientExtensionResults') <> 'object'
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'clientExtensionResults', 'expected', 'object'), NULL::jsonb)
+  WHEN credential->>'authenticatorAttachment' NOT IN ('platform','cross-platform')
+  THEN webauthn.raise_error('Invalid PublicKeyCredential', json_build_object('member', 'authenticatorAttachment', 'expected', 'platform, cross-platform or null'), NULL::jsonb)
+  ELSE credential
+END
+FROM (
+  SELECT (
+    SELECT response_member
+    FROM unnest(response_members) WITH ORDINALITY AS required(response_member, ordinality)
+    WHERE jsonb_typeof(credential->'response'->response_member) IS DISTINCT FROM 'string'
+    ORDER BY ordinality
+    LIMIT 1
+  ) AS invalid_response_member
+) AS check_response_members
+$$;
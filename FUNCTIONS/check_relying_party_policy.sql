@@ -0,0 +1,68 @@
+CREATE OR REPLACE FUNCTION webauthn.check_relying_party_policy()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Checks the client data of a new row in webauthn.credentials or webauthn.assertions
+-- against the policies of its relying party in webauthn.relying_parties.
+--
+-- The policies can change, so unlike check constraints, which would be re-checked
+-- on every update, e.g. when a credential is revoked, they are only checked on insert.
+-- The error is raised as a check_violation like for a check constraint.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-validating-origin
+--
+DECLARE
+_relying_party_id text;
+_allow_cross_origin boolean;
+_payee_origin text;
+_constraint_name text;
+BEGIN
+IF TG_TABLE_NAME = 'credentials' THEN
+  _relying_party_id := NEW.relying_party_id;
+  _allow_cross_origin := webauthn.credential_challenge_allow_cross_origin(NEW.challenge);
+ELSE
+  _relying_party_id := webauthn.assertion_challenge_relying_party_id(NEW.challenge);
+  _allow_cross_origin := webauthn.assertion_challenge_allow_cross_origin(NEW.challenge);
+  _payee_origin := webauthn.assertion_challenge_payment(NEW.challenge)->>'payeeOrigin';
+END IF;
+
+SELECT policy.constraint_name INTO _constraint_name
+FROM (VALUES
+  --
+  -- https://www.w3.org/TR/secure-payment-confirmation/#sctn-verifying-assertion
+  -- A payment.get assertion is made on the merchant's origin, not the relying party's,
+  -- so it is checked against the payeeOrigin of the challenge instead, if given, either as the origin
+  -- or, from a cross-origin iframe, as the top-level origin, and otherwise like any other row.
+  --
+  (1, 'origin_allowed', CASE WHEN NEW.client_data->>'type' = 'payment.get' AND _payee_origin IS NOT NULL
+    THEN COALESCE(_payee_origin IN (NEW.client_data->>'origin', NEW.client_data->'payment'->>'topOrigin'), FALSE)
+    ELSE COALESCE(NEW.client_data->>'origin' = ANY(webauthn.relying_party_origins(_relying_party_id)), TRUE)
+  END),
+  --
+  -- A row created in a cross-origin iframe is only accepted if the challenge or relying party allows it,
+  -- and only from an allowed top-level origin, which the client only reports for cross-origin calls.
+  --
+  (2, 'cross_origin_allowed', (NEW.client_data->'crossOrigin')::boolean IS NOT TRUE OR _allow_cross_origin),
+  (3, 'top_origin_allowed', NEW.client_data->>'type' = 'payment.get' AND _payee_origin IS NOT NULL OR COALESCE(NEW.client_data->>'topOrigin' = ANY(webauthn.relying_party_top_origins(_relying_party_id)), TRUE)),
+  (4, 'token_binding_allowed', CASE webauthn.relying_party_token_binding(_relying_party_id)
+    WHEN 'forbid' THEN NEW.client_data->'tokenBinding'->>'status' IS DISTINCT FROM 'present'
+    WHEN 'require' THEN NEW.client_data->'tokenBinding'->>'status' IS NOT DISTINCT FROM 'present'
+    ELSE TRUE
+  END)
+) AS policy (position, constraint_name, allowed)
+WHERE NOT policy.allowed
+ORDER BY policy.position
+LIMIT 1;
+
+IF _constraint_name IS NOT NULL THEN
+  RAISE check_violation USING
+    MESSAGE = format('new row for relation "%s" violates check constraint "%s"', TG_TABLE_NAME, _constraint_name),
+    SCHEMA = TG_TABLE_SCHEMA,
+    TABLE = TG_TABLE_NAME,
+    CONSTRAINT = _constraint_name;
+END IF;
+
+RETURN NEW;
+END;
+$$;
@@ -0,0 +1,47 @@
+CREATE OR REPLACE FUNCTION webauthn.count_assertion_failures(
+  scope webauthn.lockout_scope,
+  credential_id bytea,
+  user_name text,
+  relying_party_id text,
+  since timestamptz
+)
+RETURNS bigint
+STABLE
+LANGUAGE sql AS $$
+--
+-- Failures older than the last expired lockout or the last successful assertion
+-- for the same subject are not counted, so that a lockout is not immediately
+-- re-triggered after its cooldown, and a legitimate user starts from zero after
+-- signing in.
+--
+SELECT count(*)
+FROM webauthn.assertion_failures
+WHERE CASE count_assertion_failures.scope
+  WHEN 'credential' THEN assertion_failures.credential_id = count_assertion_failures.credential_id
+  WHEN 'user_name' THEN assertion_failures.user_name = count_assertion_failures.user_name
+END
+AND assertion_failures.relying_party_id IS NOT DISTINCT FROM count_assertion_failures.relying_party_id
+AND assertion_failures.failed_at > greatest(
+  count_assertion_failures.since,
+  (
+    SELECT max(lockouts.locked_until)
+    FROM webauthn.lockouts
+    WHERE lockouts.scope = count_assertion_failures.scope
+    AND CASE count_assertion_failures.scope
+      WHEN 'credential' THEN lockouts.credential_id = count_assertion_failures.credential_id
+      WHEN 'user_name' THEN lockouts.user_name = count_assertion_failures.user_name
+    END
+    AND lockouts.relying_party_id IS NOT DISTINCT FROM count_assertion_failures.relying_party_id
+  ),
+  (
+    SELECT max(assertions.verified_at)
+    FROM webauthn.assertions
+    JOIN webauthn.credentials ON credentials.credential_id = assertions.credential_id
+    WHERE CASE count_assertion_failures.scope
+      WHEN 'credential' THEN assertions.credential_id = count_assertion_failures.credential_id
+      WHEN 'user_name' THEN credentials.user_name = count_assertion_failures.user_name
+    END
+    AND credentials.relying_party_id IS NOT DISTINCT FROM count_assertion_failures.relying_party_id
+  )
+)
+$$;
@@ -0,0 +1,44 @@
+CREATE OR REPLACE FUNCTION webauthn.create_session(
+  signature text,
+  expires_in interval DEFAULT '12 hours'::interval,
+  idle_timeout interval DEFAULT '30 minutes'::interval,
+  max_age interval DEFAULT '5 minutes'::interval,
+  created_at timestamptz DEFAULT now()
+)
+RETURNS text
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Issues a session for the assertion verified by webauthn.verify_assertion() with the given signature.
+-- Only the hash of the random token is stored, so the returned token cannot be recovered afterwards.
+-- The assertion must have been verified at most max_age before the session is created,
+-- so that a signature obtained later on, e.g. from a log, cannot be turned into a session.
+--
+WITH new_token AS (
+  SELECT public.gen_random_bytes(32) AS token
+), inserted_session AS (
+  INSERT INTO webauthn.sessions (token_hash, signature, credential_id, user_id, created_at, expires_at, idle_timeout, last_used_at)
+  SELECT
+    public.digest(new_token.token,'sha256'),
+    assertions.signature,
+    assertions.credential_id,
+    assertions.user_id,
+    create_session.created_at,
+    create_session.created_at + create_session.expires_in,
+    create_session.idle_timeout,
+    create_session.created_at
+  FROM new_token
+  CROSS JOIN webauthn.assertions
+  JOIN webauthn.credentials ON credentials.credential_id = assertions.credential_id
+                           AND credentials.revoked_at IS NULL
+  WHERE assertions.signature = webauthn.base64url_decode(create_session.signature)
+  AND assertions.verified_at <= create_session.created_at
+  AND assertions.verified_at >= create_session.created_at - create_session.max_age
+  RETURNING TRUE
+)
+SELECT webauthn.base64url_encode(new_token.token)
+FROM new_token
+JOIN inserted_session ON TRUE
+$$;
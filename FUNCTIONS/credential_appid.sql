@@ -0,0 +1,6 @@
+CREATE OR REPLACE FUNCTION webauthn.credential_appid(credential_id bytea)
+RETURNS text
+STABLE
+LANGUAGE sql AS $$
+SELECT appid FROM webauthn.credentials WHERE credential_id = $1
+$$;
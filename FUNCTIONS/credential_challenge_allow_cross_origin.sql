@@ -0,0 +1,9 @@
+CREATE OR REPLACE FUNCTION webauthn.credential_challenge_allow_cross_origin(challenge bytea)
+RETURNS boolean
+STABLE
+LANGUAGE sql AS $$
+SELECT COALESCE(credential_challenges.allow_cross_origin, relying_parties.allow_cross_origin, FALSE)
+FROM webauthn.credential_challenges
+LEFT JOIN webauthn.relying_parties ON relying_parties.relying_party_id = credential_challenges.relying_party_id
+WHERE credential_challenges.challenge = $1
+$$;
@@ -0,0 +1,15 @@
+CREATE OR REPLACE FUNCTION webauthn.credential_challenge_cred_protect(challenge bytea)
+RETURNS integer
+STABLE
+LANGUAGE sql AS $$
+-- https://fidoalliance.org/specs/fido-v2.1-ps-20210615/fido-client-to-authenticator-protocol-v2.1-ps-20210615.html#sctn-credProtect-extension
+-- Only returns the requested level if enforceCredentialProtectionPolicy is true,
+-- since the client may otherwise create the credential with a lower level.
+SELECT CASE extensions->>'credentialProtectionPolicy'
+  WHEN 'userVerificationOptional' THEN 1
+  WHEN 'userVerificationOptionalWithCredentialIDList' THEN 2
+  WHEN 'userVerificationRequired' THEN 3
+END
+FROM webauthn.credential_challenges WHERE challenge = $1
+AND (extensions->'enforceCredentialProtectionPolicy')::boolean
+$$;
@@ -0,0 +1,7 @@
+CREATE OR REPLACE FUNCTION webauthn.credential_challenge_large_blob_support(challenge bytea)
+RETURNS text
+STABLE
+LANGUAGE sql AS $$
+-- https://www.w3.org/TR/webauthn-2/#dom-authenticationextensionslargeblobinputs-support
+SELECT extensions->'largeBlob'->>'support' FROM webauthn.credential_challenges WHERE challenge = $1
+$$;
@@ -0,0 +1,18 @@
+CREATE OR REPLACE FUNCTION webauthn.credential_public_key_jwk(credential_id text)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the public key of the credential as a JSON Web Key,
+-- with the credential ID as its key ID.
+--
+-- https://www.rfc-editor.org/rfc/rfc7517#section-4.5
+--
+SELECT webauthn.public_key_jwk(credentials.kty, credentials.alg, credentials.crv, credentials.public_key)
+  || jsonb_build_object('kid', webauthn.base64url_encode(credentials.credential_id))
+FROM webauthn.credentials
+WHERE credentials.credential_id = webauthn.base64url_decode(credential_public_key_jwk.credential_id)
+$$;
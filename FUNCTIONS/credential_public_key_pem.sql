@@ -0,0 +1,25 @@
+CREATE OR REPLACE FUNCTION webauthn.credential_public_key_pem(credential_id text)
+RETURNS text
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the public key of the credential as a PEM encoded SubjectPublicKeyInfo,
+-- with the base64 wrapped at 64 characters per line.
+--
+-- https://www.rfc-editor.org/rfc/rfc7468#section-13
+--
+SELECT
+  E'-----BEGIN PUBLIC KEY-----\n'
+  || (
+    SELECT string_agg(substr(spki.base64, line_start, 64), E'\n' ORDER BY line_start)
+    FROM generate_series(1, length(spki.base64), 64) AS line_start
+  )
+  || E'\n-----END PUBLIC KEY-----\n'
+FROM (
+  SELECT translate(encode(webauthn.credential_public_key_spki(credential_public_key_pem.credential_id), 'base64'), E'\n', '') AS base64
+) AS spki
+WHERE spki.base64 IS NOT NULL
+$$;
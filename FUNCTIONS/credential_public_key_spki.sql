@@ -0,0 +1,15 @@
+CREATE OR REPLACE FUNCTION webauthn.credential_public_key_spki(credential_id text)
+RETURNS bytea
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the public key of the credential as a DER SubjectPublicKeyInfo,
+-- e.g. for services verifying assertions on their own.
+--
+SELECT webauthn.public_key_spki(credentials.kty, credentials.crv, credentials.public_key)
+FROM webauthn.credentials
+WHERE credentials.credential_id = webauthn.base64url_decode(credential_public_key_spki.credential_id)
+$$;
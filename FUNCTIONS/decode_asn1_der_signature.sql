@@ -1,38 +1,112 @@
-CREATE OR REPLACE FUNCTION webauthn.decode_asn1_der_signature(asn1der bytea)
+CREATE OR REPLACE FUNCTION webauthn.decode_asn1_der_signature(asn1der bytea, curve_name text)
 RETURNS bytea
 IMMUTABLE
 LANGUAGE plpgsql
 AS $$
+--
+-- Decodes a DER encoded ECDSA-Sig-Value, i.e. SEQUENCE { r INTEGER, s INTEGER },
+-- into r || s, each left-padded with zeros to the size of the curve's field,
+-- or raises an error describing the first violation of DER or of the range of r and s.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-signature-attestation-types
+-- https://www.rfc-editor.org/rfc/rfc3279#section-2.2.3
+-- https://www.itu.int/rec/T-REC-X.690 section 10.1
+--
 DECLARE
-len1 integer;
-len2 integer;
-integer1_zeropadded bytea;
-integer1 bytea;
-integer2_zeropadded bytea;
-integer2 bytea;
-result bytea;
+_width integer := CASE decode_asn1_der_signature.curve_name
+  WHEN 'secp256r1' THEN 32
+  WHEN 'secp384r1' THEN 48
+  WHEN 'secp521r1' THEN 66
+END;
+_length integer := length(asn1der);
+_offset integer := 0;
+_expected_tag integer;
+_content_length bigint;
+_length_bytes integer;
+_integer bytea;
+_result bytea := '\x';
 BEGIN
-IF length(asn1der) <= 3 THEN
-    RETURN NULL;
+IF _width IS NULL THEN
+  PERFORM webauthn.raise_error('Unsupported curve', json_build_object('curve_name', curve_name), NULL::boolean);
 END IF;
-len1 := get_byte(asn1der,3);
-IF length(asn1der) <= 5+len1 THEN
-    RETURN NULL;
-END IF;
-len2 := get_byte(asn1der,5+len1);
-integer1_zeropadded := substring(asn1der from 5 for len1);
-integer1 := substring(decode(repeat('00',32),'hex')||integer1_zeropadded from len1+1 for 32);
-integer2_zeropadded := substring(asn1der from 5+len1+2 for len2);
-integer2 := substring(decode(repeat('00',32),'hex')||integer2_zeropadded from len2+1 for 32);
-result := integer1||integer2;
-
-IF get_byte(asn1der,0) = 48
-AND get_byte(asn1der,1) = length(asn1der)-2
-AND get_byte(asn1der,2) = 2 /* 0x02 INTEGER */
-AND get_byte(asn1der,5+len1-1) = 2 /* 0x02 INTEGER */
-AND length(integer1||integer2) = 64
-THEN
-    RETURN result;
+
+--
+-- The SEQUENCE header is followed by the two INTEGERs it contains.
+--
+FOR _i IN 0..2 LOOP
+  _expected_tag := CASE WHEN _i = 0 THEN 48 /* 0x30 SEQUENCE */ ELSE 2 /* 0x02 INTEGER */ END;
+  IF _offset + 2 > _length THEN
+    PERFORM webauthn.raise_error('DER signature truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+  END IF;
+  IF get_byte(asn1der,_offset) <> _expected_tag THEN
+    PERFORM webauthn.raise_error('DER signature has unexpected tag', json_build_object('offset', _offset, 'tag', get_byte(asn1der,_offset), 'expected_tag', _expected_tag), NULL::boolean);
+  END IF;
+  _content_length := get_byte(asn1der,_offset+1);
+  _offset := _offset + 2;
+
+  IF _content_length = 128 THEN
+    PERFORM webauthn.raise_error('DER signature has indefinite length', json_build_object('offset', _offset - 1), NULL::boolean);
+  ELSIF _content_length > 128 THEN
+    _length_bytes := _content_length & 127;
+    IF _offset + _length_bytes > _length THEN
+      PERFORM webauthn.raise_error('DER signature truncated', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
+    END IF;
+    _content_length := 0;
+    FOR _j IN 0.._length_bytes-1 LOOP
+      _content_length := (_content_length<<8) + get_byte(asn1der,_offset+_j);
+      -- No signature is anywhere near this long, so stop before overflowing.
+      EXIT WHEN _content_length > _length;
+    END LOOP;
+    IF get_byte(asn1der,_offset) = 0 OR _content_length < 128 THEN
+      PERFORM webauthn.raise_error('DER signature length not minimally encoded', json_build_object('offset', _offset - 1), NULL::boolean);
+    END IF;
+    _offset := _offset + _length_bytes;
+  END IF;
+
+  IF _offset + _content_length > _length THEN
+    PERFORM webauthn.raise_error('DER signature truncated', json_build_object('offset', _offset, 'content_length', _content_length, 'length', _length), NULL::boolean);
+  END IF;
+
+  IF _i = 0 THEN
+    IF _offset + _content_length < _length THEN
+      PERFORM webauthn.raise_error('Trailing bytes after DER signature', json_build_object('offset', _offset + _content_length, 'length', _length), NULL::boolean);
+    END IF;
+    CONTINUE;
+  END IF;
+
+  IF _content_length = 0 THEN
+    PERFORM webauthn.raise_error('DER signature INTEGER is empty', json_build_object('offset', _offset), NULL::boolean);
+  END IF;
+  _integer := substring(asn1der from _offset+1 for _content_length::integer);
+  IF get_byte(_integer,0) >= 128 THEN
+    PERFORM webauthn.raise_error('DER signature INTEGER is negative', json_build_object('offset', _offset), NULL::boolean);
+  END IF;
+  IF get_byte(_integer,0) = 0 AND _content_length > 1 THEN
+    IF get_byte(_integer,1) < 128 THEN
+      PERFORM webauthn.raise_error('DER signature INTEGER not minimally encoded', json_build_object('offset', _offset), NULL::boolean);
+    END IF;
+    -- The zero byte only keeps the sign bit clear.
+    _integer := substring(_integer from 2);
+  END IF;
+  IF _integer = '\x00' OR length(_integer) > _width THEN
+    PERFORM webauthn.raise_error('DER signature INTEGER out of range', json_build_object('offset', _offset, 'length', length(_integer), 'maximum_length', _width), NULL::boolean);
+  END IF;
+  _result := _result || decode(repeat('00',_width - length(_integer)),'hex') || _integer;
+  _offset := _offset + _content_length::integer;
+END LOOP;
+
+IF _offset <> _length THEN
+  PERFORM webauthn.raise_error('Trailing bytes after DER signature', json_build_object('offset', _offset, 'length', _length), NULL::boolean);
 END IF;
+
+RETURN _result;
 END;
 $$;
+
+CREATE OR REPLACE FUNCTION webauthn.decode_asn1_der_signature(asn1der bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql
+AS $$
+SELECT webauthn.decode_asn1_der_signature(asn1der, 'secp256r1')
+$$;
@@ -0,0 +1,20 @@
+CREATE OR REPLACE FUNCTION webauthn.der_encode(tag integer, content bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Encodes a DER TLV, with the length in short form if less than 128,
+-- or else in long form with as few length bytes as possible.
+--
+-- https://www.itu.int/rec/T-REC-X.690 section 8.1.3 and 10.1
+--
+SELECT
+  set_byte('\x00'::bytea, 0, der_encode.tag)
+  || CASE
+    WHEN length(der_encode.content) < 128 THEN set_byte('\x00'::bytea, 0, length(der_encode.content))
+    WHEN length(der_encode.content) < 256 THEN '\x81'::bytea || set_byte('\x00'::bytea, 0, length(der_encode.content))
+    ELSE '\x82'::bytea || substring(int4send(length(der_encode.content)) from 3 for 2)
+  END
+  || der_encode.content
+$$;
@@ -0,0 +1,53 @@
+CREATE OR REPLACE FUNCTION webauthn.ec_point_on_curve(crv integer, x bytea, y bytea)
+RETURNS boolean
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Checks that (x,y) is a point on the given NIST curve,
+-- i.e. that x and y are less than p and y^2 = x^3 - 3x + b (mod p),
+-- or returns NULL if the curve is not a NIST curve.
+--
+-- https://www.iana.org/assignments/cose/cose.xhtml#elliptic-curves
+-- https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-186.pdf section 3.2.1
+--
+DECLARE
+_parameters bytea[] := CASE ec_point_on_curve.crv
+  WHEN 1 THEN ARRAY[ -- P-256
+    '\xffffffff00000001000000000000000000000000ffffffffffffffffffffffff',
+    '\x5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b'
+  ]::bytea[]
+  WHEN 2 THEN ARRAY[ -- P-384
+    '\xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000ffffffff',
+    '\xb3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875ac656398d8a2ed19d2a85c8edd3ec2aef'
+  ]::bytea[]
+  WHEN 3 THEN ARRAY[ -- P-521
+    '\x01ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff',
+    '\x0051953eb9618e1c9a1f929a21a0b68540eea2da725b99b315f3b8b489918ef109e156193951ec7e937b1652c0bd3bb1bf073573df883d2c34f1ef451fd46b503f00'
+  ]::bytea[]
+END;
+_integers numeric[] := ARRAY[0,0,0,0];
+_bytes bytea;
+_p numeric;
+_b numeric;
+_x numeric;
+_y numeric;
+BEGIN
+IF _parameters IS NULL THEN
+  RETURN NULL;
+END IF;
+
+FOR _i IN 1..4 LOOP
+  _bytes := (_parameters || ARRAY[x,y])[_i];
+  FOR _j IN 0..length(_bytes)-1 LOOP
+    _integers[_i] := _integers[_i] * 256 + get_byte(_bytes,_j);
+  END LOOP;
+END LOOP;
+_p := _integers[1];
+_b := _integers[2];
+_x := _integers[3];
+_y := _integers[4];
+
+RETURN _x < _p AND _y < _p AND mod(_y * _y - (_x * _x * _x - 3 * _x + _b), _p) = 0;
+END;
+$$;
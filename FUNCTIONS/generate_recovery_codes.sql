@@ -0,0 +1,32 @@
+CREATE OR REPLACE FUNCTION webauthn.generate_recovery_codes(
+  user_id bytea,
+  n integer DEFAULT 10,
+  generated_at timestamptz DEFAULT now()
+)
+RETURNS SETOF text
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Replaces the user's unused recovery codes with n new ones.
+-- Only the hashes are stored, so the returned codes cannot be shown again.
+--
+WITH revoked_codes AS (
+  UPDATE webauthn.recovery_codes SET
+    revoked_at = generate_recovery_codes.generated_at
+  WHERE recovery_codes.user_id = generate_recovery_codes.user_id
+  AND recovery_codes.redeemed_at IS NULL
+  AND recovery_codes.revoked_at IS NULL
+), new_codes AS (
+  SELECT
+    ordinality,
+    array_to_string(regexp_match(encode(public.gen_random_bytes(8),'hex'),'^(.{4})(.{4})(.{4})(.{4})$'),'-') AS code
+  FROM generate_series(1, generate_recovery_codes.n) WITH ORDINALITY
+), inserted_codes AS (
+  INSERT INTO webauthn.recovery_codes (user_id, code_hash, generated_at)
+  SELECT generate_recovery_codes.user_id, webauthn.recovery_code_hash(new_codes.code), generate_recovery_codes.generated_at
+  FROM new_codes
+)
+SELECT new_codes.code FROM new_codes ORDER BY new_codes.ordinality
+$$;
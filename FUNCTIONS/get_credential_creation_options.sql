@@ -21,13 +21,15 @@ jsonb_build_object(
         'alg', -7
       )
     ),
-    'authenticatorSelection', jsonb_build_object(
+    'authenticatorSelection', jsonb_strip_nulls(jsonb_build_object(
+      'authenticatorAttachment', authenticator_attachment,
       'requireResidentKey', require_resident_key,
       'userVerification', user_verification
-    ),
+    )),
     'timeout', (extract(epoch from timeout)*1000)::bigint,
     'attestation', attestation
-  )
+  ) || CASE WHEN extensions IS NOT NULL THEN jsonb_build_object('extensions', extensions) ELSE '{}' END
+    || CASE WHEN hints IS NOT NULL THEN jsonb_build_object('hints', to_jsonb(hints)) ELSE '{}' END
 )
 FROM webauthn.credential_challenges
 WHERE credential_challenges.challenge = get_credential_creation_options.challenge
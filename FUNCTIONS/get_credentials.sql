@@ -2,33 +2,89 @@ CREATE OR REPLACE FUNCTION webauthn.get_credentials(
   challenge bytea,
   user_name text DEFAULT NULL,
   user_verification webauthn.user_verification_requirement DEFAULT 'preferred',
-  timeout interval DEFAULT '5 minutes'::interval,
+  timeout interval DEFAULT NULL,
   relying_party_id text DEFAULT NULL,
-  challenge_at timestamptz DEFAULT now()
+  challenge_at timestamptz DEFAULT now(),
+  extensions jsonb DEFAULT NULL,
+  credential_id text DEFAULT NULL,
+  payload jsonb DEFAULT NULL,
+  user_id bytea DEFAULT NULL,
+  allow_cross_origin boolean DEFAULT NULL,
+  mediation webauthn.credential_mediation_requirement DEFAULT NULL,
+  hints webauthn.public_key_credential_hint[] DEFAULT NULL
 )
 RETURNS jsonb
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
 LANGUAGE sql
 AS $$
+--
+-- Without a user_name, the lockouts of the user_names of the user_id's credentials apply.
+--
+SELECT webauthn.check_lockout(NULL, lockout_user_names.user_name, get_credentials.relying_party_id, get_credentials.challenge_at)
+FROM (
+  SELECT get_credentials.user_name
+  UNION
+  SELECT credentials.user_name
+  FROM webauthn.credentials
+  WHERE get_credentials.user_name IS NULL
+  AND credentials.user_id = get_credentials.user_id
+  AND credentials.relying_party_id IS NOT DISTINCT FROM get_credentials.relying_party_id
+) AS lockout_user_names;
+
+--
+-- If a payload is given, the challenge argument is used as the nonce,
+-- and the challenge sent to the client commits to the payload.
+-- Without a timeout, conditional mediation challenges get the relying party's timeout,
+-- since they stay open while the user fills in the sign-in form.
+--
 WITH store_assertion_challenge AS (
   INSERT INTO webauthn.assertion_challenges
-         (challenge, user_name, user_verification, timeout, relying_party_id, challenge_at)
-  VALUES (challenge, user_name, user_verification, timeout, relying_party_id, challenge_at)
-  RETURNING TRUE
+         (challenge, user_name, user_verification, timeout, relying_party_id, challenge_at, extensions, nonce, payload, user_id, allow_cross_origin, mediation, hints)
+  VALUES (
+    COALESCE(webauthn.payload_challenge(challenge, payload), challenge),
+    user_name, webauthn.relying_party_user_verification(relying_party_id, user_verification),
+    COALESCE(timeout, CASE WHEN mediation = 'conditional' THEN webauthn.relying_party_conditional_mediation_timeout(relying_party_id) ELSE '5 minutes'::interval END),
+    relying_party_id, challenge_at, extensions,
+    CASE WHEN payload IS NOT NULL THEN challenge END,
+    payload,
+    user_id,
+    allow_cross_origin,
+    mediation,
+    hints
+  )
+  RETURNING assertion_challenges.challenge, assertion_challenges.timeout
 )
 SELECT jsonb_strip_nulls(jsonb_build_object(
   'publicKey', jsonb_build_object(
-    'userVerification', get_credentials.user_verification,
+    'userVerification', webauthn.relying_party_user_verification(get_credentials.relying_party_id, get_credentials.user_verification),
     'allowCredentials', COALESCE(jsonb_agg(
       jsonb_build_object(
         'type', credentials.credential_type,
         'id', webauthn.base64url_encode(credentials.credential_id)
       )
     ORDER BY credentials.credential_id),jsonb_build_array()),
-    'timeout', (extract(epoch from get_credentials.timeout)*1000)::bigint,
-    'challenge', webauthn.base64url_encode(get_credentials.challenge),
-    'rpId', get_credentials.relying_party_id
-  )
+    'timeout', (extract(epoch from (SELECT store_assertion_challenge.timeout FROM store_assertion_challenge))*1000)::bigint,
+    'challenge', webauthn.base64url_encode((SELECT store_assertion_challenge.challenge FROM store_assertion_challenge)),
+    'rpId', get_credentials.relying_party_id,
+    'hints', to_jsonb(get_credentials.hints),
+    'extensions', CASE
+      -- https://www.w3.org/TR/webauthn-2/#sctn-large-blob-extension
+      -- A blob can only be written to a single credential, which insert_assertion() attributes it to.
+      WHEN get_credentials.extensions->'largeBlob' ? 'write' AND count(credentials.credential_id) <> 1
+      THEN webauthn.raise_error('largeBlob write requires exactly one credential', json_build_object('credentials', count(credentials.credential_id)), NULL::jsonb)
+      -- https://www.w3.org/TR/webauthn-2/#sctn-appid-extension
+      WHEN bool_or(credentials.appid IS NOT NULL)
+      THEN COALESCE(get_credentials.extensions,'{}') || jsonb_build_object('appid', max(credentials.appid))
+      ELSE get_credentials.extensions
+    END
+  ),
+  'mediation', get_credentials.mediation
 ))
 FROM webauthn.credentials
-WHERE credentials.user_name = get_credentials.user_name
+WHERE (credentials.user_name = get_credentials.user_name OR get_credentials.user_name IS NULL AND get_credentials.user_id IS NOT NULL)
+AND (credentials.user_id = get_credentials.user_id OR get_credentials.user_id IS NULL)
+AND credentials.relying_party_id IS NOT DISTINCT FROM get_credentials.relying_party_id
+AND credentials.revoked_at IS NULL
+AND (credentials.credential_id = webauthn.base64url_decode(get_credentials.credential_id) OR get_credentials.credential_id IS NULL)
 $$;
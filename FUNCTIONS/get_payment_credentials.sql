@@ -0,0 +1,63 @@
+CREATE OR REPLACE FUNCTION webauthn.get_payment_credentials(
+  challenge bytea,
+  user_name text,
+  relying_party_id text,
+  instrument jsonb,
+  total jsonb,
+  payee_origin text DEFAULT NULL,
+  payee_name text DEFAULT NULL,
+  user_verification webauthn.user_verification_requirement DEFAULT 'preferred',
+  timeout interval DEFAULT '5 minutes'::interval,
+  challenge_at timestamptz DEFAULT now()
+)
+RETURNS jsonb
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the arguments for the PaymentRequest constructor of a Secure Payment Confirmation,
+-- and stores the transaction details the client is expected to sign in clientDataJSON.payment.
+--
+-- https://www.w3.org/TR/secure-payment-confirmation/#sctn-securepaymentconfirmationrequest
+-- https://www.w3.org/TR/secure-payment-confirmation/#sctn-collectedclientadditionalpaymentdata-dictionary
+--
+SELECT webauthn.check_lockout(NULL, get_payment_credentials.user_name, get_payment_credentials.relying_party_id, get_payment_credentials.challenge_at);
+
+WITH store_assertion_challenge AS (
+  INSERT INTO webauthn.assertion_challenges
+         (challenge, user_name, user_verification, timeout, relying_party_id, challenge_at, payment)
+  VALUES (challenge, user_name, webauthn.relying_party_user_verification(relying_party_id, user_verification), timeout, relying_party_id, challenge_at, jsonb_strip_nulls(jsonb_build_object(
+    'rpId', relying_party_id,
+    'payeeName', payee_name,
+    'payeeOrigin', payee_origin,
+    'total', total,
+    'instrument', instrument
+  )))
+  RETURNING TRUE
+)
+SELECT jsonb_build_object(
+  'methodData', jsonb_build_array(jsonb_build_object(
+    'supportedMethods', 'secure-payment-confirmation',
+    'data', jsonb_strip_nulls(jsonb_build_object(
+      'challenge', webauthn.base64url_encode(get_payment_credentials.challenge),
+      'rpId', get_payment_credentials.relying_party_id,
+      'credentialIds', COALESCE(jsonb_agg(webauthn.base64url_encode(credentials.credential_id) ORDER BY credentials.credential_id),jsonb_build_array()),
+      'instrument', get_payment_credentials.instrument,
+      'payeeName', get_payment_credentials.payee_name,
+      'payeeOrigin', get_payment_credentials.payee_origin,
+      'timeout', (extract(epoch from get_payment_credentials.timeout)*1000)::bigint
+    ))
+  )),
+  'details', jsonb_build_object(
+    'total', jsonb_build_object(
+      'label', 'Total',
+      'amount', get_payment_credentials.total
+    )
+  )
+)
+FROM webauthn.credentials
+WHERE credentials.user_name = get_payment_credentials.user_name
+AND credentials.relying_party_id IS NOT DISTINCT FROM get_payment_credentials.relying_party_id
+AND credentials.revoked_at IS NULL
+$$;
@@ -0,0 +1,51 @@
+CREATE OR REPLACE FUNCTION webauthn.grant_api_access(role_name name)
+RETURNS void
+LANGUAGE plpgsql
+AS $$
+DECLARE
+_api_functions text[] := ARRAY[
+  'init_credential',
+  'store_credential',
+  'get_credentials',
+  'get_payment_credentials',
+  'verify_assertion',
+  'verify_assertion_payload',
+  'recent_verification',
+  'revoke_credential',
+  'create_session',
+  'validate_session',
+  'revoke_sessions',
+  'generate_recovery_codes',
+  'redeem_recovery_code',
+  'list_credentials',
+  'credential_public_key_spki',
+  'credential_public_key_pem',
+  'credential_public_key_jwk',
+  'import_u2f_credential',
+  'signal_all_accepted_credentials',
+  'signal_unknown_credential',
+  'signal_current_user_details'
+];
+_function regprocedure;
+BEGIN
+--
+-- The role is only granted EXECUTE on the API functions,
+-- which are SECURITY DEFINER and run as the extension owner,
+-- so the role needs no privileges on the tables,
+-- and can't e.g. SELECT public_key from webauthn.credentials
+-- or INSERT into webauthn.assertions directly.
+--
+EXECUTE format('REVOKE ALL ON ALL TABLES IN SCHEMA webauthn FROM %I', grant_api_access.role_name);
+EXECUTE format('REVOKE ALL ON ALL SEQUENCES IN SCHEMA webauthn FROM %I', grant_api_access.role_name);
+EXECUTE format('REVOKE ALL ON ALL FUNCTIONS IN SCHEMA webauthn FROM %I', grant_api_access.role_name);
+EXECUTE format('GRANT USAGE ON SCHEMA webauthn TO %I', grant_api_access.role_name);
+
+FOR _function IN
+  SELECT pg_proc.oid FROM pg_catalog.pg_proc
+  WHERE pg_proc.pronamespace = 'webauthn'::regnamespace
+  AND pg_proc.proname = ANY(_api_functions)
+LOOP
+  EXECUTE format('GRANT EXECUTE ON FUNCTION %s TO %I', _function, grant_api_access.role_name);
+END LOOP;
+END;
+$$;
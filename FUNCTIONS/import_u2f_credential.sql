@@ -0,0 +1,111 @@
+CREATE OR REPLACE FUNCTION webauthn.import_u2f_credential(
+  key_handle text,
+  public_key text,
+  appid text,
+  user_name text,
+  user_id bytea,
+  user_display_name text,
+  relying_party_name text,
+  relying_party_id text DEFAULT NULL,
+  sign_count bigint DEFAULT 0,
+  imported_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Imports a legacy FIDO U2F registration, for use with the FIDO AppID extension.
+--
+-- Since webauthn.credentials only stores credentials created through the WebAuthn ceremony,
+-- the registration is wrapped in a "none" attestation object with a credential challenge
+-- derived from the key handle, with the U2F public key converted to a COSE_Key
+-- and the rpIdHash set to the hash of the AppID, as the authenticator computed it.
+--
+-- An unknown relying party is registered on first use,
+-- without any origin restrictions or policies.
+--
+-- Since the AppID extension only takes a single AppID, which webauthn.get_credentials()
+-- adds to the request options, all U2F credentials of a user and relying party
+-- must share the same AppID, so importing one with another AppID raises an error.
+--
+INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name)
+SELECT import_u2f_credential.relying_party_id, import_u2f_credential.relying_party_name
+WHERE import_u2f_credential.relying_party_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-appid-extension
+-- https://fidoalliance.org/specs/fido-u2f-v1.2-ps-20170411/fido-u2f-raw-message-formats-v1.2-ps-20170411.html#registration-response-message-success
+--
+INSERT INTO webauthn.credential_challenges
+       (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, user_verification, attestation, timeout, challenge_at)
+SELECT
+  public.digest(import_u2f_credential.appid || import_u2f_credential.key_handle,'sha256'),
+  import_u2f_credential.user_name,
+  import_u2f_credential.user_id,
+  import_u2f_credential.user_display_name,
+  import_u2f_credential.relying_party_name,
+  import_u2f_credential.relying_party_id,
+  'discouraged',
+  'none',
+  '5 minutes',
+  import_u2f_credential.imported_at
+WHERE CASE
+  WHEN length(webauthn.base64url_decode(import_u2f_credential.public_key)) <> 65
+    OR get_byte(webauthn.base64url_decode(import_u2f_credential.public_key),0) <> 4
+  THEN webauthn.raise_error('Invalid U2F public key', json_build_object('expected', 'uncompressed P-256 point'), NULL::boolean)
+  WHEN import_u2f_credential.sign_count NOT BETWEEN 0 AND 4294967295
+  THEN webauthn.raise_error('Invalid U2F sign count', json_build_object('sign_count', import_u2f_credential.sign_count), NULL::boolean)
+  WHEN substring(import_u2f_credential.appid from '^https://[^/]+') IS NULL
+  THEN webauthn.raise_error('Invalid AppID', json_build_object('appid', import_u2f_credential.appid), NULL::boolean)
+  WHEN EXISTS (
+    SELECT 1 FROM webauthn.credentials
+    WHERE (credentials.user_name = import_u2f_credential.user_name OR credentials.user_id = import_u2f_credential.user_id)
+    AND credentials.relying_party_id IS NOT DISTINCT FROM import_u2f_credential.relying_party_id
+    AND credentials.appid <> import_u2f_credential.appid
+  )
+  THEN webauthn.raise_error('Conflicting AppID', json_build_object('appid', import_u2f_credential.appid), NULL::boolean)
+  ELSE TRUE
+END;
+
+INSERT INTO webauthn.credentials (credential_id, credential_type, attestation_object, client_data_json, challenge, user_name, user_id, credential_at, appid, relying_party_id)
+SELECT
+  key_handle,
+  'public-key',
+  '\xa3'::bytea
+  || '\x63'::bytea || convert_to('fmt','UTF8') || '\x64'::bytea || convert_to('none','UTF8')
+  || '\x67'::bytea || convert_to('attStmt','UTF8') || '\xa0'::bytea
+  || '\x68'::bytea || convert_to('authData','UTF8')
+  || '\x59'::bytea || int2send(length(authenticator_data)::smallint)
+  || authenticator_data,
+  convert_to(json_build_object(
+    'type', 'webauthn.create',
+    'challenge', webauthn.base64url_encode(challenge),
+    'origin', substring(import_u2f_credential.appid from '^https://[^/]+')
+  )::text,'UTF8'),
+  challenge,
+  import_u2f_credential.user_name,
+  import_u2f_credential.user_id,
+  import_u2f_credential.imported_at,
+  import_u2f_credential.appid,
+  import_u2f_credential.relying_party_id
+FROM webauthn.base64url_decode(import_u2f_credential.key_handle) AS key_handle
+CROSS JOIN webauthn.base64url_decode(import_u2f_credential.public_key) AS public_key
+CROSS JOIN public.digest(import_u2f_credential.appid || import_u2f_credential.key_handle,'sha256') AS challenge
+CROSS JOIN LATERAL (
+  SELECT
+    public.digest(import_u2f_credential.appid,'sha256')
+    || '\x41'::bytea
+    -- The U2F counter is an unsigned 32-bit integer
+    || substring(int8send(import_u2f_credential.sign_count) from 5 for 4)
+    || '\x00000000000000000000000000000000'::bytea
+    || int2send(length(key_handle)::smallint)
+    || key_handle
+    -- COSE_Key: {1: 2 (EC2), 3: -7 (ES256), -1: 1 (P-256), -2: x, -3: y}
+    || '\xa5010203262001215820'::bytea || substring(public_key,2,32)
+    || '\x225820'::bytea || substring(public_key,34,32)
+) AS u2f(authenticator_data)
+RETURNING credentials.credential_id
+$$;
@@ -9,13 +9,28 @@ CREATE OR REPLACE FUNCTION webauthn.init_credential(
   user_verification webauthn.user_verification_requirement DEFAULT 'preferred',
   attestation webauthn.attestation_conveyance_preference DEFAULT 'none',
   timeout interval DEFAULT '5 minutes'::interval,
-  challenge_at timestamptz DEFAULT now()
+  challenge_at timestamptz DEFAULT now(),
+  extensions jsonb DEFAULT NULL,
+  allow_cross_origin boolean DEFAULT NULL,
+  authenticator_attachment webauthn.authenticator_attachment DEFAULT NULL,
+  hints webauthn.public_key_credential_hint[] DEFAULT NULL
 )
 RETURNS jsonb
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
 LANGUAGE sql
 AS $$
+--
+-- An unknown relying party is registered on first use,
+-- without any origin restrictions or policies.
+--
+INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name)
+SELECT init_credential.relying_party_id, init_credential.relying_party_name
+WHERE init_credential.relying_party_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
 INSERT INTO webauthn.credential_challenges
-       (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, require_resident_key, user_verification, attestation, timeout, challenge_at)
-VALUES (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, require_resident_key, user_verification, attestation, timeout, challenge_at)
+       (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, require_resident_key, user_verification, attestation, timeout, challenge_at, extensions, allow_cross_origin, authenticator_attachment, hints)
+VALUES (challenge, user_name, user_id, user_display_name, relying_party_name, relying_party_id, require_resident_key, webauthn.relying_party_user_verification(relying_party_id, user_verification), webauthn.relying_party_attestation(relying_party_id, attestation), timeout, challenge_at, extensions, allow_cross_origin, authenticator_attachment, hints)
 RETURNING webauthn.get_credential_creation_options(challenge)
 $$;
@@ -0,0 +1,60 @@
+CREATE OR REPLACE FUNCTION webauthn.insert_assertion(
+  OUT user_id bytea,
+  credential_id bytea,
+  credential_type webauthn.credential_type,
+  authenticator_data bytea,
+  client_data_json bytea,
+  challenge bytea,
+  signature bytea,
+  user_handle bytea,
+  verified_at timestamptz,
+  client_extension_results jsonb
+)
+RETURNS bytea
+LANGUAGE sql
+AS $$
+WITH inserted_assertion AS (
+  INSERT INTO webauthn.assertions (signature, credential_id, challenge, authenticator_data, client_data_json, user_id, user_handle, verified_at, prf_salts, client_extension_results)
+  SELECT
+    insert_assertion.signature,
+    credentials.credential_id,
+    assertion_challenges.challenge,
+    insert_assertion.authenticator_data,
+    insert_assertion.client_data_json,
+    credentials.user_id,
+    insert_assertion.user_handle,
+    insert_assertion.verified_at,
+    COALESCE(
+      assertion_challenges.extensions->'prf'->'evalByCredential'->webauthn.base64url_encode(credentials.credential_id),
+      assertion_challenges.extensions->'prf'->'eval'
+    ),
+    insert_assertion.client_extension_results
+  FROM webauthn.assertion_challenges
+  JOIN webauthn.credentials ON  credentials.credential_id   = insert_assertion.credential_id
+                           AND  credentials.credential_type = insert_assertion.credential_type
+                           AND (credentials.user_name      <> assertion_challenges.user_name) IS NOT TRUE
+                           AND (credentials.user_id        <> assertion_challenges.user_id)   IS NOT TRUE
+                           AND  credentials.relying_party_id IS NOT DISTINCT FROM assertion_challenges.relying_party_id
+                           AND  credentials.revoked_at IS NULL
+  WHERE assertion_challenges.challenge = insert_assertion.challenge
+  RETURNING assertions.user_id, assertions.credential_id, assertions.challenge, assertions.verified_at
+), store_large_blob AS (
+  --
+  -- https://www.w3.org/TR/webauthn-2/#sctn-large-blob-extension
+  -- The client reports largeBlob.written if the blob requested to be written was stored on the authenticator.
+  --
+  INSERT INTO webauthn.large_blobs (credential_id, version, blob_hash, blob_size, challenge, written_at)
+  SELECT
+    inserted_assertion.credential_id,
+    COALESCE((SELECT max(large_blobs.version) FROM webauthn.large_blobs WHERE large_blobs.credential_id = inserted_assertion.credential_id),0) + 1,
+    public.digest(webauthn.base64url_decode(assertion_challenges.extensions->'largeBlob'->>'write'),'sha256'),
+    length(webauthn.base64url_decode(assertion_challenges.extensions->'largeBlob'->>'write')),
+    inserted_assertion.challenge,
+    inserted_assertion.verified_at
+  FROM inserted_assertion
+  JOIN webauthn.assertion_challenges ON assertion_challenges.challenge = inserted_assertion.challenge
+  WHERE (insert_assertion.client_extension_results->'largeBlob'->'written')::boolean
+  AND assertion_challenges.extensions->'largeBlob' ? 'write'
+)
+SELECT inserted_assertion.user_id FROM inserted_assertion
+$$;
@@ -0,0 +1,36 @@
+CREATE OR REPLACE FUNCTION webauthn.list_credentials(user_id bytea)
+RETURNS TABLE (
+  credential_type text,
+  credential_id text,
+  created_at timestamptz,
+  last_used_at timestamptz,
+  revoked_at timestamptz
+)
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Lists the user's credentials, including recovery codes,
+-- which have no credential_id and are used at most once.
+--
+SELECT
+  credentials.credential_type::text,
+  webauthn.base64url_encode(credentials.credential_id),
+  credentials.credential_at,
+  (SELECT max(assertions.verified_at) FROM webauthn.assertions WHERE assertions.credential_id = credentials.credential_id),
+  credentials.revoked_at
+FROM webauthn.credentials
+WHERE credentials.user_id = list_credentials.user_id
+UNION ALL
+SELECT
+  'recovery-code',
+  NULL,
+  recovery_codes.generated_at,
+  recovery_codes.redeemed_at,
+  recovery_codes.revoked_at
+FROM webauthn.recovery_codes
+WHERE recovery_codes.user_id = list_credentials.user_id
+ORDER BY 3, 1, 2, 4, 5
+$$;
@@ -0,0 +1,28 @@
+CREATE OR REPLACE FUNCTION webauthn.parse_assertion_authenticator_data()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Sets the columns of webauthn.assertions derived from the authenticator data,
+-- parsing it only once per row.
+--
+BEGIN
+SELECT
+  parse_authenticator_data.rp_id_hash,
+  parse_authenticator_data.user_present,
+  parse_authenticator_data.user_verified,
+  parse_authenticator_data.attested_credential_data_included,
+  parse_authenticator_data.extension_data_included,
+  parse_authenticator_data.sign_count
+INTO
+  NEW.rp_id_hash,
+  NEW.user_present,
+  NEW.user_verified,
+  NEW.attested_credential_data_included,
+  NEW.extension_data_included,
+  NEW.sign_count
+FROM webauthn.parse_authenticator_data(NEW.authenticator_data);
+
+RETURN NEW;
+END;
+$$;
@@ -18,9 +18,8 @@ AS $$
 -- https://github.com/fido-alliance/webauthn-demo/blob/master/utils.js#L183
 SELECT
   parse_authenticator_data.*,
-  substring(authenticator_data,38,16),
-  substring(authenticator_data,56,(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54)),
-  substring(authenticator_data,56+(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54))
+  parse_attested_credential_data.*
 FROM decode(cbor.to_jsonb(cbor := attestation_object, encode_binary_format := 'base64')->>'authData','base64') AS authenticator_data
 CROSS JOIN webauthn.parse_authenticator_data(authenticator_data)
+CROSS JOIN webauthn.parse_attested_credential_data(authenticator_data)
 $$;
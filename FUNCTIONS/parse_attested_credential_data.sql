@@ -0,0 +1,16 @@
+CREATE OR REPLACE FUNCTION webauthn.parse_attested_credential_data(
+  OUT aaguid bytea,
+  OUT credential_id bytea,
+  OUT credential_public_key bytea,
+  authenticator_data bytea
+)
+RETURNS record
+IMMUTABLE
+LANGUAGE sql
+AS $$
+-- https://www.w3.org/TR/webauthn-2/#sctn-attested-credential-data
+SELECT
+  substring(authenticator_data,38,16),
+  substring(authenticator_data,56,(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54)),
+  substring(authenticator_data,56+(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54))
+$$;
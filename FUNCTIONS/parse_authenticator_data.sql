@@ -14,13 +14,14 @@ AS $$
 -- https://developer.mozilla.org/en-US/docs/Web/API/AuthenticatorAssertionResponse/authenticatorData
 -- https://github.com/fido-alliance/webauthn-demo/blob/master/utils.js#L304
 SELECT
-  substring(authenticator_data,1,32),
-  (get_byte(authenticator_data,32)&1)::boolean,
-  (get_byte(authenticator_data,32)>>2&1)::boolean,
-  (get_byte(authenticator_data,32)>>6&1)::boolean,
-  (get_byte(authenticator_data,32)>>7&1)::boolean,
-  (get_byte(authenticator_data,33)<<24)::bigint +
-  (get_byte(authenticator_data,34)<<16)::bigint +
-  (get_byte(authenticator_data,35)<<8)::bigint +
-  get_byte(authenticator_data,36)::bigint
+  substring(valid_authenticator_data,1,32),
+  (get_byte(valid_authenticator_data,32)&1)::boolean,
+  (get_byte(valid_authenticator_data,32)>>2&1)::boolean,
+  (get_byte(valid_authenticator_data,32)>>6&1)::boolean,
+  (get_byte(valid_authenticator_data,32)>>7&1)::boolean,
+  (get_byte(valid_authenticator_data,33)::bigint<<24) +
+  (get_byte(valid_authenticator_data,34)::bigint<<16) +
+  (get_byte(valid_authenticator_data,35)::bigint<<8) +
+  get_byte(valid_authenticator_data,36)::bigint
+FROM webauthn.validate_authenticator_data(authenticator_data) AS valid_authenticator_data
 $$;
@@ -0,0 +1,26 @@
+CREATE OR REPLACE FUNCTION webauthn.parse_authenticator_extensions(
+  authenticator_data bytea,
+  attested_credential_data_included boolean,
+  extension_data_included boolean
+)
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE sql
+AS $$
+-- https://www.w3.org/TR/webauthn-2/#sctn-authenticator-data
+-- The extensions CBOR map is the last item of authenticator_data,
+-- following the credential public key if attested credential data is included.
+-- The flags are given by the caller, which has already parsed and validated authenticator_data.
+SELECT CASE WHEN extension_data_included THEN
+  CASE WHEN attested_credential_data_included
+  THEN cbor.to_jsonb_array(
+    cbor := substring(authenticator_data,56+(get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54)),
+    encode_binary_format := 'base64'
+  )->1
+  ELSE cbor.to_jsonb(
+    cbor := substring(authenticator_data,38),
+    encode_binary_format := 'base64'
+  )
+  END
+END
+$$;
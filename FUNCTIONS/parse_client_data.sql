@@ -0,0 +1,14 @@
+CREATE OR REPLACE FUNCTION webauthn.parse_client_data()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Sets the client_data column of webauthn.credentials and webauthn.assertions,
+-- raising an error describing invalid client data before the generated columns
+-- derived from it would fail to cast it to jsonb.
+--
+BEGIN
+NEW.client_data := webauthn.parse_client_data_json(NEW.client_data_json);
+RETURN NEW;
+END;
+$$;
@@ -0,0 +1,76 @@
+CREATE OR REPLACE FUNCTION webauthn.parse_client_data_json(client_data_json bytea)
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Parses the client data, returning it as a jsonb object,
+-- or raises an error if it is not valid UTF-8, not a JSON object,
+-- or if a member is missing or has the wrong type.
+--
+-- Members not defined by the specification are kept, since clients may add new ones.
+--
+-- https://www.w3.org/TR/webauthn-2/#dictionary-client-data
+-- https://www.w3.org/TR/webauthn-2/#sctn-registering-a-new-credential steps 5-6
+--
+DECLARE
+_text text;
+_client_data jsonb;
+_member record;
+_token_binding_status text;
+BEGIN
+BEGIN
+  _text := webauthn.from_utf8(client_data_json);
+EXCEPTION WHEN character_not_in_repertoire THEN
+  PERFORM webauthn.raise_error('clientDataJSON is not valid UTF-8', json_build_object('length', length(client_data_json)), NULL::boolean);
+END;
+
+BEGIN
+  _client_data := _text::jsonb;
+EXCEPTION WHEN invalid_text_representation OR untranslatable_character THEN
+  PERFORM webauthn.raise_error('clientDataJSON is not valid JSON', json_build_object('length', length(client_data_json)), NULL::boolean);
+END;
+
+IF jsonb_typeof(_client_data) IS DISTINCT FROM 'object' THEN
+  PERFORM webauthn.raise_error('clientDataJSON is not a JSON object', json_build_object('type', jsonb_typeof(_client_data)), NULL::boolean);
+END IF;
+
+FOR _member IN
+SELECT * FROM (VALUES
+  ('type',         'string',  TRUE),
+  ('challenge',    'string',  TRUE),
+  ('origin',       'string',  TRUE),
+  ('crossOrigin',  'boolean', FALSE),
+  ('topOrigin',    'string',  FALSE),
+  ('tokenBinding', 'object',  FALSE),
+  ('payment',      'object',  FALSE)
+) AS members (name, expected_type, required)
+LOOP
+  IF NOT _client_data ? _member.name THEN
+    IF _member.required THEN
+      PERFORM webauthn.raise_error('clientDataJSON member missing', json_build_object('member', _member.name), NULL::boolean);
+    END IF;
+  ELSIF jsonb_typeof(_client_data->_member.name) <> _member.expected_type THEN
+    PERFORM webauthn.raise_error('clientDataJSON member has wrong type', json_build_object('member', _member.name, 'type', jsonb_typeof(_client_data->_member.name), 'expected_type', _member.expected_type), NULL::boolean);
+  END IF;
+END LOOP;
+
+--
+-- "not-supported" was removed in Level 2, but is still sent by some clients.
+-- https://www.w3.org/TR/webauthn-2/#dictdef-tokenbinding
+--
+IF _client_data ? 'tokenBinding' THEN
+  _token_binding_status := _client_data->'tokenBinding'->>'status';
+  IF jsonb_typeof(_client_data->'tokenBinding'->'status') IS DISTINCT FROM 'string'
+  OR _token_binding_status NOT IN ('present', 'supported', 'not-supported')
+  THEN
+    PERFORM webauthn.raise_error('clientDataJSON tokenBinding status invalid', json_build_object('status', _client_data->'tokenBinding'->'status'), NULL::boolean);
+  END IF;
+  IF _token_binding_status = 'present' AND jsonb_typeof(_client_data->'tokenBinding'->'id') IS DISTINCT FROM 'string' THEN
+    PERFORM webauthn.raise_error('clientDataJSON tokenBinding id missing', json_build_object('status', _token_binding_status), NULL::boolean);
+  END IF;
+END IF;
+
+RETURN _client_data;
+END;
+$$;
@@ -0,0 +1,96 @@
+CREATE OR REPLACE FUNCTION webauthn.parse_cose_key(
+  OUT kty integer,
+  OUT alg integer,
+  OUT crv integer,
+  OUT x bytea,
+  OUT y bytea,
+  cose_key bytea
+)
+RETURNS record
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Parses the credential public key, a COSE_Key, returning its key type, algorithm and curve,
+-- and the coordinates of its point.
+--
+-- Raises an error if a parameter is missing, if the algorithm is unknown,
+-- if the key type or curve are inconsistent with the algorithm,
+-- if a coordinate has the wrong length, or if the point is not on the curve.
+-- Well-formed ES384, ES512 and EdDSA keys are then refused as unsupported,
+-- since signatures are only verified with ES256.
+--
+-- Any data following the COSE_Key, i.e. the extensions, is ignored.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-encoded-credPubKey-examples
+-- https://www.rfc-editor.org/rfc/rfc8152#section-7
+-- https://www.rfc-editor.org/rfc/rfc8152#section-13.1.1
+--
+DECLARE
+_cose_key jsonb := cbor.to_jsonb_array(cbor := cose_key, encode_binary_format := 'base64')->0;
+_label text;
+_expected record;
+BEGIN
+IF jsonb_typeof(_cose_key) IS DISTINCT FROM 'object' THEN
+  PERFORM webauthn.raise_error('COSE key is not a CBOR map', json_build_object('length', length(cose_key)), NULL::boolean);
+END IF;
+
+FOREACH _label IN ARRAY ARRAY['1','3','-1'] LOOP
+  IF jsonb_typeof(_cose_key->_label) IS DISTINCT FROM 'number' THEN
+    PERFORM webauthn.raise_error('COSE key parameter missing', json_build_object('label', _label::integer), NULL::boolean);
+  END IF;
+END LOOP;
+
+kty := (_cose_key->>'1')::integer;
+alg := (_cose_key->>'3')::integer;
+crv := (_cose_key->>'-1')::integer;
+
+--
+-- https://www.iana.org/assignments/cose/cose.xhtml#algorithms
+-- https://www.iana.org/assignments/cose/cose.xhtml#elliptic-curves
+--
+SELECT * INTO _expected
+FROM (VALUES
+  (-7,  2, ARRAY[1],   32), -- ES256, EC2, P-256
+  (-35, 2, ARRAY[2],   48), -- ES384, EC2, P-384
+  (-36, 2, ARRAY[3],   66), -- ES512, EC2, P-521
+  (-8,  1, ARRAY[6,7], NULL) -- EdDSA, OKP, Ed25519 or Ed448
+) AS algorithms (alg, kty, crvs, coordinate_length)
+WHERE algorithms.alg = parse_cose_key.alg;
+
+IF NOT FOUND THEN
+  PERFORM webauthn.raise_error('Unsupported COSE algorithm', json_build_object('alg', alg), NULL::boolean);
+ELSIF kty <> _expected.kty THEN
+  PERFORM webauthn.raise_error('COSE key type inconsistent with algorithm', json_build_object('kty', kty, 'alg', alg), NULL::boolean);
+ELSIF crv <> ALL(_expected.crvs) THEN
+  PERFORM webauthn.raise_error('COSE curve inconsistent with algorithm', json_build_object('crv', crv, 'alg', alg), NULL::boolean);
+END IF;
+
+x := decode(_cose_key->>'-2','base64');
+y := decode(_cose_key->>'-3','base64');
+
+IF kty = 1 THEN
+  IF length(x) IS DISTINCT FROM (CASE crv WHEN 6 THEN 32 WHEN 7 THEN 57 END) THEN
+    PERFORM webauthn.raise_error('COSE key coordinate length invalid', json_build_object('label', -2, 'length', length(x)), NULL::boolean);
+  END IF;
+ELSE
+  FOREACH _label IN ARRAY ARRAY['-2','-3'] LOOP
+    IF length(decode(_cose_key->>_label,'base64')) IS DISTINCT FROM _expected.coordinate_length THEN
+      PERFORM webauthn.raise_error('COSE key coordinate length invalid', json_build_object('label', _label::integer, 'length', length(decode(_cose_key->>_label,'base64')), 'expected_length', _expected.coordinate_length), NULL::boolean);
+    END IF;
+  END LOOP;
+
+  IF NOT webauthn.ec_point_on_curve(crv, x, y) THEN
+    PERFORM webauthn.raise_error('COSE key point not on curve', json_build_object('crv', crv), NULL::boolean);
+  END IF;
+END IF;
+
+--
+-- The public key is stored as the x and y coordinates for pg-ecdsa,
+-- so an OKP key, having no y coordinate, would otherwise violate NOT NULL instead.
+--
+IF alg <> -7 THEN
+  PERFORM webauthn.raise_error('Unsupported COSE algorithm', json_build_object('alg', alg, 'kty', kty, 'crv', crv), NULL::boolean);
+END IF;
+END;
+$$;
@@ -0,0 +1,75 @@
+CREATE OR REPLACE FUNCTION webauthn.parse_credential_attestation_object()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+--
+-- Sets the columns of webauthn.credentials derived from the attestation object,
+-- decoding its CBOR only once per row, which generated columns can't do,
+-- since each generated column would have to decode it on its own.
+--
+DECLARE
+_attestation_object jsonb := cbor.to_jsonb(cbor := NEW.attestation_object, encode_binary_format := 'base64');
+_authenticator_data bytea := decode(_attestation_object->>'authData','base64');
+_attested_credential_data record;
+BEGIN
+IF _authenticator_data IS NULL THEN
+  PERFORM webauthn.raise_error('Attestation object without authData', json_build_object('credential_id', webauthn.base64url_encode(NEW.credential_id)), NULL::boolean);
+END IF;
+
+SELECT
+  parse_authenticator_data.rp_id_hash,
+  parse_authenticator_data.user_present,
+  parse_authenticator_data.user_verified,
+  parse_authenticator_data.attested_credential_data_included,
+  parse_authenticator_data.extension_data_included,
+  parse_authenticator_data.sign_count
+INTO
+  NEW.rp_id_hash,
+  NEW.user_present,
+  NEW.user_verified,
+  NEW.attested_credential_data_included,
+  NEW.extension_data_included,
+  NEW.sign_count
+FROM webauthn.parse_authenticator_data(_authenticator_data);
+
+IF NOT NEW.attested_credential_data_included THEN
+  PERFORM webauthn.raise_error('Attested credential data missing', json_build_object('credential_id', webauthn.base64url_encode(NEW.credential_id)), NULL::boolean);
+END IF;
+
+_attested_credential_data := webauthn.parse_attested_credential_data(_authenticator_data);
+
+IF NEW.credential_id IS DISTINCT FROM _attested_credential_data.credential_id THEN
+  RAISE check_violation USING
+    MESSAGE = format('new row for relation "%s" violates check constraint "%s"', TG_TABLE_NAME, 'attestation_object_credential_id'),
+    SCHEMA = TG_TABLE_SCHEMA,
+    TABLE = TG_TABLE_NAME,
+    CONSTRAINT = 'attestation_object_credential_id';
+END IF;
+
+NEW.aaguid := _attested_credential_data.aaguid;
+-- \x04 tag byte not prepended since not wanted by pg-ecdsa
+SELECT parse_cose_key.kty, parse_cose_key.alg, parse_cose_key.crv, parse_cose_key.x || parse_cose_key.y
+INTO NEW.kty, NEW.alg, NEW.crv, NEW.public_key
+FROM webauthn.parse_cose_key(_attested_credential_data.credential_public_key);
+NEW.authenticator_extension_outputs := webauthn.parse_authenticator_extensions(_authenticator_data, NEW.attested_credential_data_included, NEW.extension_data_included);
+
+--
+-- An enterprise attestation certificate identifies the individual authenticator,
+-- unlike the batch certificates of other attestations, so only its serial number is kept.
+-- The attestation statement isn't verified, so the serial number is only as trustworthy as the client.
+-- It's only read on insert, since the relying party's allow-list may have changed since.
+-- https://www.w3.org/TR/webauthn-2/#dom-attestationconveyancepreference-enterprise
+--
+IF TG_OP = 'UPDATE' THEN
+  NEW.attestation_certificate_serial := OLD.attestation_certificate_serial;
+ELSIF webauthn.relying_party_attestation(NEW.relying_party_id, (SELECT attestation FROM webauthn.credential_challenges WHERE challenge = NEW.challenge)) = 'enterprise'
+AND _attestation_object->'attStmt'->'x5c'->>0 IS NOT NULL
+THEN
+  NEW.attestation_certificate_serial := webauthn.certificate_serial_number(decode(_attestation_object->'attStmt'->'x5c'->>0,'base64'));
+ELSE
+  NEW.attestation_certificate_serial := NULL;
+END IF;
+
+RETURN NEW;
+END;
+$$;
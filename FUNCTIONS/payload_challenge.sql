@@ -0,0 +1,10 @@
+CREATE OR REPLACE FUNCTION webauthn.payload_challenge(nonce bytea, payload jsonb)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql AS $$
+--
+-- The challenge commits to the payload by hashing the nonce followed by the canonical JSON of the payload,
+-- as defined by RFC 8785, so that a client can compute the same challenge.
+--
+SELECT public.digest(nonce || convert_to(webauthn.canonical_json(payload),'UTF8'),'sha256')
+$$;
@@ -0,0 +1,32 @@
+CREATE OR REPLACE FUNCTION webauthn.public_key_jwk(kty integer, alg integer, crv integer, public_key bytea)
+RETURNS jsonb
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Encodes a public key, as stored in webauthn.credentials, as a JSON Web Key,
+-- where an EC2 public key is x || y, with both coordinates of equal length.
+--
+-- https://www.rfc-editor.org/rfc/rfc7518#section-6.2.1
+-- https://www.rfc-editor.org/rfc/rfc8037#section-2
+-- https://www.rfc-editor.org/rfc/rfc8812#section-3
+--
+SELECT CASE
+  WHEN public_key_jwk.kty = 2 AND public_key_jwk.crv IN (1,2,3) THEN jsonb_build_object(
+    'kty', 'EC',
+    'crv', CASE public_key_jwk.crv WHEN 1 THEN 'P-256' WHEN 2 THEN 'P-384' WHEN 3 THEN 'P-521' END,
+    'x', webauthn.base64url_encode(substring(public_key_jwk.public_key from 1 for length(public_key_jwk.public_key)/2)),
+    'y', webauthn.base64url_encode(substring(public_key_jwk.public_key from length(public_key_jwk.public_key)/2+1))
+  )
+  WHEN public_key_jwk.kty = 1 AND public_key_jwk.crv IN (6,7) THEN jsonb_build_object(
+    'kty', 'OKP',
+    'crv', CASE public_key_jwk.crv WHEN 6 THEN 'Ed25519' WHEN 7 THEN 'Ed448' END,
+    'x', webauthn.base64url_encode(public_key_jwk.public_key)
+  )
+  ELSE webauthn.raise_error('Unsupported public key', json_build_object('kty', public_key_jwk.kty, 'crv', public_key_jwk.crv), NULL::jsonb)
+END
+|| jsonb_strip_nulls(jsonb_build_object(
+  'alg', CASE public_key_jwk.alg WHEN -7 THEN 'ES256' WHEN -35 THEN 'ES384' WHEN -36 THEN 'ES512' WHEN -8 THEN 'EdDSA' END,
+  'use', 'sig'
+))
+$$;
@@ -0,0 +1,35 @@
+CREATE OR REPLACE FUNCTION webauthn.public_key_spki(kty integer, crv integer, public_key bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql
+AS $$
+--
+-- Encodes a public key, as stored in webauthn.credentials, as a DER SubjectPublicKeyInfo,
+-- i.e. SEQUENCE { SEQUENCE { algorithm OID, parameters }, BIT STRING key },
+-- with the EC2 point uncompressed, i.e. \x04 || x || y.
+--
+-- https://www.rfc-editor.org/rfc/rfc5480#section-2
+-- https://www.rfc-editor.org/rfc/rfc8410#section-4
+--
+SELECT webauthn.der_encode(48, algorithm_identifier || webauthn.der_encode(3, '\x00'::bytea || subject_public_key))
+FROM (
+  SELECT
+    CASE
+      WHEN public_key_spki.kty = 2 AND public_key_spki.crv IN (1,2,3) THEN webauthn.der_encode(48,
+        '\x06072a8648ce3d0201'::bytea -- id-ecPublicKey
+        || CASE public_key_spki.crv
+          WHEN 1 THEN '\x06082a8648ce3d030107'::bytea -- secp256r1
+          WHEN 2 THEN '\x06052b81040022'::bytea -- secp384r1
+          WHEN 3 THEN '\x06052b81040023'::bytea -- secp521r1
+        END
+      )
+      WHEN public_key_spki.kty = 1 AND public_key_spki.crv = 6 THEN '\x300506032b6570'::bytea -- id-Ed25519
+      WHEN public_key_spki.kty = 1 AND public_key_spki.crv = 7 THEN '\x300506032b6571'::bytea -- id-Ed448
+      ELSE webauthn.raise_error('Unsupported public key', json_build_object('kty', public_key_spki.kty, 'crv', public_key_spki.crv), NULL::bytea)
+    END AS algorithm_identifier,
+    CASE public_key_spki.kty
+      WHEN 2 THEN '\x04'::bytea || public_key_spki.public_key
+      ELSE public_key_spki.public_key
+    END AS subject_public_key
+) AS spki
+$$;
@@ -0,0 +1,23 @@
+CREATE OR REPLACE FUNCTION webauthn.recent_verification(
+  user_id bytea,
+  max_age interval,
+  require_uv boolean DEFAULT TRUE,
+  at timestamptz DEFAULT now()
+)
+RETURNS timestamptz
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns when the user most recently proved presence, with user verification if required,
+-- within max_age before the given time, or NULL if there is no such verified assertion.
+--
+SELECT max(assertions.verified_at)
+FROM webauthn.assertions
+WHERE assertions.user_id = recent_verification.user_id
+AND assertions.verified_at >  recent_verification.at - recent_verification.max_age
+AND assertions.verified_at <= recent_verification.at
+AND (assertions.user_verified OR NOT recent_verification.require_uv)
+$$;
@@ -0,0 +1,33 @@
+CREATE OR REPLACE FUNCTION webauthn.record_assertion_failure(
+  credential_id bytea,
+  user_name text,
+  relying_party_id text,
+  challenge bytea,
+  sqlstate text,
+  error_message text,
+  failed_at timestamptz
+)
+RETURNS void
+LANGUAGE sql
+AS $$
+INSERT INTO webauthn.assertion_failures
+       (credential_id, user_name, relying_party_id, challenge, sqlstate, error_message, failed_at)
+VALUES (credential_id, user_name, relying_party_id, challenge, sqlstate, error_message, failed_at);
+
+INSERT INTO webauthn.lockouts (scope, credential_id, user_name, relying_party_id, locked_at, locked_until)
+SELECT
+  lockout_policies.scope,
+  CASE lockout_policies.scope WHEN 'credential' THEN record_assertion_failure.credential_id END,
+  CASE lockout_policies.scope WHEN 'user_name' THEN record_assertion_failure.user_name END,
+  record_assertion_failure.relying_party_id,
+  record_assertion_failure.failed_at,
+  record_assertion_failure.failed_at + lockout_policies.cooldown
+FROM webauthn.lockout_policies
+WHERE lockout_policies.max_failures <= webauthn.count_assertion_failures(
+  scope := lockout_policies.scope,
+  credential_id := record_assertion_failure.credential_id,
+  user_name := record_assertion_failure.user_name,
+  relying_party_id := record_assertion_failure.relying_party_id,
+  since := record_assertion_failure.failed_at - lockout_policies.failure_window
+)
+$$;
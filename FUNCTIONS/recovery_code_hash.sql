@@ -0,0 +1,10 @@
+CREATE OR REPLACE FUNCTION webauthn.recovery_code_hash(code text)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE sql AS $$
+--
+-- Codes are hashed without separators and case insensitively,
+-- so that "1a2b-3c4d-5e6f-7a8b" and "1A2B3C4D5E6F7A8B" are the same code.
+--
+SELECT public.digest(lower(regexp_replace(code,'[^0-9A-Fa-f]','','g')),'sha256')
+$$;
@@ -0,0 +1,21 @@
+CREATE OR REPLACE FUNCTION webauthn.redeem_recovery_code(
+  user_id bytea,
+  code text,
+  redeemed_at timestamptz DEFAULT now()
+)
+RETURNS boolean
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+WITH redeemed_code AS (
+  UPDATE webauthn.recovery_codes SET
+    redeemed_at = redeem_recovery_code.redeemed_at
+  WHERE recovery_codes.user_id = redeem_recovery_code.user_id
+  AND recovery_codes.code_hash = webauthn.recovery_code_hash(redeem_recovery_code.code)
+  AND recovery_codes.redeemed_at IS NULL
+  AND recovery_codes.revoked_at IS NULL
+  RETURNING TRUE
+)
+SELECT EXISTS (SELECT FROM redeemed_code)
+$$;
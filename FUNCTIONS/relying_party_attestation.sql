@@ -0,0 +1,14 @@
+CREATE OR REPLACE FUNCTION webauthn.relying_party_attestation(relying_party_id text, attestation webauthn.attestation_conveyance_preference)
+RETURNS webauthn.attestation_conveyance_preference
+STABLE
+LANGUAGE sql AS $$
+--
+-- Enterprise attestation is only requested for relying parties on the allow-list,
+-- and replaced by direct attestation for any other relying party.
+--
+SELECT CASE
+  WHEN $2 = 'enterprise' AND (SELECT enterprise_attestation FROM webauthn.relying_parties WHERE relying_party_id = $1) IS NOT TRUE
+  THEN 'direct'::webauthn.attestation_conveyance_preference
+  ELSE $2
+END
+$$;
@@ -0,0 +1,12 @@
+CREATE OR REPLACE FUNCTION webauthn.relying_party_conditional_mediation_timeout(relying_party_id text)
+RETURNS interval
+STABLE
+LANGUAGE sql AS $$
+--
+-- Challenges without a relying party get the default of the conditional_mediation_timeout column.
+--
+SELECT COALESCE(
+  (SELECT conditional_mediation_timeout FROM webauthn.relying_parties WHERE relying_party_id = $1),
+  '1 hour'::interval
+)
+$$;
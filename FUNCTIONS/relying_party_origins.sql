@@ -0,0 +1,6 @@
+CREATE OR REPLACE FUNCTION webauthn.relying_party_origins(relying_party_id text)
+RETURNS text[]
+STABLE
+LANGUAGE sql AS $$
+SELECT origins FROM webauthn.relying_parties WHERE relying_party_id = $1
+$$;
@@ -0,0 +1,6 @@
+CREATE OR REPLACE FUNCTION webauthn.relying_party_token_binding(relying_party_id text)
+RETURNS webauthn.token_binding_policy
+STABLE
+LANGUAGE sql AS $$
+SELECT token_binding FROM webauthn.relying_parties WHERE relying_party_id = $1
+$$;
@@ -0,0 +1,6 @@
+CREATE OR REPLACE FUNCTION webauthn.relying_party_top_origins(relying_party_id text)
+RETURNS text[]
+STABLE
+LANGUAGE sql AS $$
+SELECT top_origins FROM webauthn.relying_parties WHERE relying_party_id = $1
+$$;
@@ -0,0 +1,10 @@
+CREATE OR REPLACE FUNCTION webauthn.relying_party_user_verification(relying_party_id text, user_verification webauthn.user_verification_requirement)
+RETURNS webauthn.user_verification_requirement
+STABLE
+LANGUAGE sql AS $$
+SELECT CASE
+  WHEN (SELECT require_user_verification FROM webauthn.relying_parties WHERE relying_party_id = $1)
+  THEN 'required'::webauthn.user_verification_requirement
+  ELSE $2
+END
+$$;
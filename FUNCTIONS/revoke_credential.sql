@@ -0,0 +1,25 @@
+CREATE OR REPLACE FUNCTION webauthn.revoke_credential(
+  OUT user_id bytea,
+  credential_id text,
+  revoked_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+WITH revoked_credential AS (
+  UPDATE webauthn.credentials SET
+    revoked_at = revoke_credential.revoked_at
+  WHERE credentials.credential_id = webauthn.base64url_decode(revoke_credential.credential_id)
+  AND credentials.revoked_at IS NULL
+  RETURNING credentials.credential_id, credentials.user_id
+), revoked_sessions AS (
+  UPDATE webauthn.sessions SET
+    revoked_at = revoke_credential.revoked_at
+  FROM revoked_credential
+  WHERE sessions.credential_id = revoked_credential.credential_id
+  AND sessions.revoked_at IS NULL
+)
+SELECT revoked_credential.user_id FROM revoked_credential
+$$;
@@ -0,0 +1,29 @@
+CREATE OR REPLACE FUNCTION webauthn.revoke_public_access()
+RETURNS void
+LANGUAGE plpgsql
+AS $$
+DECLARE
+_table regclass;
+BEGIN
+--
+-- The functions are never executable by PUBLIC, but privileges on the tables
+-- might have been granted to PUBLIC after the extension was created.
+--
+REVOKE ALL ON ALL TABLES IN SCHEMA webauthn FROM PUBLIC;
+REVOKE ALL ON ALL SEQUENCES IN SCHEMA webauthn FROM PUBLIC;
+
+--
+-- Row level security limits roles other than the owner to the rows of the relying parties
+-- whose role_name they're a member of, by the relying_party_role policies of the tables,
+-- even if privileges on the tables are granted, e.g. for reporting per relying party.
+-- The lockout_policies table is global and has no policy, so no rows are visible to other roles.
+--
+FOR _table IN
+  SELECT pg_class.oid FROM pg_catalog.pg_class
+  WHERE pg_class.relnamespace = 'webauthn'::regnamespace
+  AND pg_class.relkind = 'r'
+LOOP
+  EXECUTE format('ALTER TABLE %s ENABLE ROW LEVEL SECURITY', _table);
+END LOOP;
+END;
+$$;
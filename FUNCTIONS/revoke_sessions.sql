@@ -0,0 +1,18 @@
+CREATE OR REPLACE FUNCTION webauthn.revoke_sessions(
+  user_id bytea,
+  revoked_at timestamptz DEFAULT now()
+)
+RETURNS bigint
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+WITH revoked_sessions AS (
+  UPDATE webauthn.sessions SET
+    revoked_at = revoke_sessions.revoked_at
+  WHERE sessions.user_id = revoke_sessions.user_id
+  AND sessions.revoked_at IS NULL
+  RETURNING TRUE
+)
+SELECT count(*) FROM revoked_sessions
+$$;
@@ -0,0 +1,36 @@
+CREATE OR REPLACE FUNCTION webauthn.signal_all_accepted_credentials(
+  user_id bytea,
+  relying_party_id text
+)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the options for PublicKeyCredential.signalAllAcceptedCredentials(),
+-- listing the credentials of the user that have not been revoked.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-allacceptedcredentialsoptions
+--
+
+--
+-- Without the relying_party_id, no credential would match,
+-- which would tell the client to remove all the user's credentials.
+--
+SELECT webauthn.raise_error('Relying party ID missing', json_build_object('user_id', webauthn.base64url_encode(signal_all_accepted_credentials.user_id)), NULL::boolean)
+WHERE signal_all_accepted_credentials.relying_party_id IS NULL;
+
+SELECT jsonb_build_object(
+  'rpId', signal_all_accepted_credentials.relying_party_id,
+  'userId', webauthn.base64url_encode(signal_all_accepted_credentials.user_id),
+  'allAcceptedCredentialIds', COALESCE(jsonb_agg(
+    webauthn.base64url_encode(credentials.credential_id)
+  ORDER BY credentials.credential_id),jsonb_build_array())
+)
+FROM webauthn.credentials
+WHERE credentials.user_id = signal_all_accepted_credentials.user_id
+AND credentials.relying_party_id = signal_all_accepted_credentials.relying_party_id
+AND credentials.revoked_at IS NULL
+$$;
@@ -0,0 +1,38 @@
+CREATE OR REPLACE FUNCTION webauthn.signal_current_user_details(
+  user_id bytea,
+  relying_party_id text
+)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the options for PublicKeyCredential.signalCurrentUserDetails(),
+-- using the user_name and user_display_name of the most recently stored credential,
+-- or NULL if the user has no credentials that have not been revoked.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-currentuserdetailsoptions
+--
+
+--
+-- Without the relying_party_id, no credential would match.
+--
+SELECT webauthn.raise_error('Relying party ID missing', json_build_object('user_id', webauthn.base64url_encode(signal_current_user_details.user_id)), NULL::boolean)
+WHERE signal_current_user_details.relying_party_id IS NULL;
+
+SELECT jsonb_build_object(
+  'rpId', signal_current_user_details.relying_party_id,
+  'userId', webauthn.base64url_encode(signal_current_user_details.user_id),
+  'name', credential_challenges.user_name,
+  'displayName', credential_challenges.user_display_name
+)
+FROM webauthn.credentials
+JOIN webauthn.credential_challenges ON credential_challenges.challenge = credentials.challenge
+WHERE credentials.user_id = signal_current_user_details.user_id
+AND credentials.relying_party_id = signal_current_user_details.relying_party_id
+AND credentials.revoked_at IS NULL
+ORDER BY credentials.credential_at DESC
+LIMIT 1
+$$;
@@ -0,0 +1,35 @@
+CREATE OR REPLACE FUNCTION webauthn.signal_unknown_credential(
+  credential_id text,
+  relying_party_id text
+)
+RETURNS jsonb
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Returns the options for PublicKeyCredential.signalUnknownCredential(),
+-- or NULL if the credential exists and has not been revoked.
+--
+-- https://www.w3.org/TR/webauthn-3/#dictdef-unknowncredentialoptions
+--
+
+--
+-- Without the relying_party_id, no credential would match,
+-- which would tell the client to remove a credential that exists.
+--
+SELECT webauthn.raise_error('Relying party ID missing', json_build_object('credential_id', signal_unknown_credential.credential_id), NULL::boolean)
+WHERE signal_unknown_credential.relying_party_id IS NULL;
+
+SELECT jsonb_build_object(
+  'rpId', signal_unknown_credential.relying_party_id,
+  'credentialId', signal_unknown_credential.credential_id
+)
+WHERE NOT EXISTS (
+  SELECT 1 FROM webauthn.credentials
+  WHERE credentials.credential_id = webauthn.base64url_decode(signal_unknown_credential.credential_id)
+  AND credentials.relying_party_id = signal_unknown_credential.relying_party_id
+  AND credentials.revoked_at IS NULL
+)
+$$;
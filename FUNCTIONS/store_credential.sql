@@ -4,12 +4,16 @@ CREATE OR REPLACE FUNCTION webauthn.store_credential(
   credential_type webauthn.credential_type,
   attestation_object text,
   client_data_json text,
-  credential_at timestamptz DEFAULT now()
+  credential_at timestamptz DEFAULT now(),
+  client_extension_results jsonb DEFAULT NULL,
+  authenticator_attachment webauthn.authenticator_attachment DEFAULT NULL
 )
 RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
 LANGUAGE sql
 AS $$
-INSERT INTO webauthn.credentials (credential_id, credential_type, attestation_object, client_data_json, challenge, user_name, user_id, credential_at)
+INSERT INTO webauthn.credentials (credential_id, credential_type, attestation_object, client_data_json, challenge, user_name, user_id, credential_at, client_extension_results, relying_party_id, authenticator_attachment)
 SELECT
   webauthn.base64url_decode(store_credential.credential_id),
   store_credential.credential_type,
@@ -18,8 +22,36 @@ SELECT
   credential_challenges.challenge,
   credential_challenges.user_name,
   credential_challenges.user_id,
-  store_credential.credential_at
+  store_credential.credential_at,
+  store_credential.client_extension_results,
+  credential_challenges.relying_party_id,
+  store_credential.authenticator_attachment
 FROM webauthn.credential_challenges
-WHERE credential_challenges.challenge = webauthn.base64url_decode(webauthn.from_utf8(webauthn.base64url_decode(store_credential.client_data_json))::jsonb->>'challenge')
+WHERE credential_challenges.challenge = webauthn.base64url_decode(webauthn.parse_client_data_json(webauthn.base64url_decode(store_credential.client_data_json))->>'challenge')
 RETURNING credentials.user_id
 $$;
+
+CREATE OR REPLACE FUNCTION webauthn.store_credential(
+  OUT user_id bytea,
+  credential jsonb,
+  credential_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+SELECT webauthn.store_credential(
+  credential_id := public_key_credential->>'id',
+  credential_type := (public_key_credential->>'type')::webauthn.credential_type,
+  attestation_object := public_key_credential->'response'->>'attestationObject',
+  client_data_json := public_key_credential->'response'->>'clientDataJSON',
+  credential_at := store_credential.credential_at,
+  client_extension_results := public_key_credential->'clientExtensionResults',
+  authenticator_attachment := (public_key_credential->>'authenticatorAttachment')::webauthn.authenticator_attachment
+)
+FROM webauthn.check_public_key_credential(
+  credential := store_credential.credential,
+  response_members := ARRAY['clientDataJSON','attestationObject']
+) AS public_key_credential
+$$;
@@ -0,0 +1,64 @@
+CREATE OR REPLACE FUNCTION webauthn.validate_authenticator_data(authenticator_data bytea)
+RETURNS bytea
+IMMUTABLE
+LANGUAGE plpgsql
+AS $$
+--
+-- Returns the authenticator data if its structure matches its flags,
+-- i.e. the attested credential data if AT is set, followed by the extensions if ED is set,
+-- without any trailing bytes, or raises an error describing the first violation.
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-authenticator-data
+-- https://www.w3.org/TR/webauthn-2/#sctn-attested-credential-data
+--
+DECLARE
+_length integer := length(authenticator_data);
+_flags integer;
+_credential_id_length integer;
+_end integer := 37;
+_item_end integer;
+BEGIN
+IF _length < 37 THEN
+  PERFORM webauthn.raise_error('Authenticator data too short', json_build_object('length', _length, 'minimum_length', 37), NULL::boolean);
+END IF;
+
+_flags := get_byte(authenticator_data,32);
+
+IF _flags>>6&1 = 1 THEN
+  IF _length < 55 THEN
+    PERFORM webauthn.raise_error('Attested credential data too short', json_build_object('length', _length, 'minimum_length', 55), NULL::boolean);
+  END IF;
+  _credential_id_length := (get_byte(authenticator_data,53)<<8) + get_byte(authenticator_data,54);
+  IF 55 + _credential_id_length >= _length THEN
+    PERFORM webauthn.raise_error('Credential ID length out of range', json_build_object('credential_id_length', _credential_id_length, 'remaining_length', _length - 55), NULL::boolean);
+  END IF;
+  _end := 55 + _credential_id_length;
+  _item_end := webauthn.cbor_item_end(authenticator_data,_end);
+  IF _item_end IS NULL THEN
+    PERFORM webauthn.raise_error('Malformed credential public key', json_build_object('offset', _end), NULL::boolean);
+  ELSIF get_byte(authenticator_data,_end)>>5 <> 5 THEN
+    PERFORM webauthn.raise_error('Credential public key is not a CBOR map', json_build_object('offset', _end), NULL::boolean);
+  END IF;
+  _end := _item_end;
+END IF;
+
+IF _flags>>7&1 = 1 THEN
+  IF _end = _length THEN
+    PERFORM webauthn.raise_error('Extension data flag set without extensions', json_build_object('offset', _end), NULL::boolean);
+  END IF;
+  _item_end := webauthn.cbor_item_end(authenticator_data,_end);
+  IF _item_end IS NULL THEN
+    PERFORM webauthn.raise_error('Malformed extensions', json_build_object('offset', _end), NULL::boolean);
+  ELSIF get_byte(authenticator_data,_end)>>5 <> 5 THEN
+    PERFORM webauthn.raise_error('Extensions are not a CBOR map', json_build_object('offset', _end), NULL::boolean);
+  END IF;
+  _end := _item_end;
+END IF;
+
+IF _end <> _length THEN
+  PERFORM webauthn.raise_error('Trailing bytes after authenticator data', json_build_object('length', _length, 'expected_length', _end), NULL::boolean);
+END IF;
+
+RETURN authenticator_data;
+END;
+$$;
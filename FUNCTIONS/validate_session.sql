@@ -0,0 +1,18 @@
+CREATE OR REPLACE FUNCTION webauthn.validate_session(
+  OUT user_id bytea,
+  token text,
+  validated_at timestamptz DEFAULT now()
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+UPDATE webauthn.sessions SET
+  last_used_at = greatest(sessions.last_used_at, validate_session.validated_at)
+WHERE sessions.token_hash = public.digest(webauthn.base64url_decode(validate_session.token),'sha256')
+AND sessions.revoked_at IS NULL
+AND validate_session.validated_at < sessions.expires_at
+AND validate_session.validated_at < sessions.last_used_at + sessions.idle_timeout
+RETURNING sessions.user_id
+$$;
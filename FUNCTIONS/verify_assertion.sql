@@ -6,37 +6,111 @@ CREATE OR REPLACE FUNCTION webauthn.verify_assertion(
   client_data_json text,
   signature text,
   user_handle text,
-  verified_at timestamptz DEFAULT now()
+  verified_at timestamptz DEFAULT now(),
+  client_extension_results jsonb DEFAULT NULL,
+  relying_party_id text DEFAULT NULL
 )
 RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE plpgsql
+AS $$
+DECLARE
+_credential_id bytea := webauthn.base64url_decode(verify_assertion.credential_id);
+_challenge bytea := webauthn.base64url_decode(webauthn.parse_client_data_json(webauthn.base64url_decode(verify_assertion.client_data_json))->>'challenge');
+_user_name text;
+_relying_party_id text;
+_sqlstate text;
+_error_message text := 'No matching credential';
+BEGIN
+SELECT credentials.user_name, credentials.relying_party_id INTO _user_name, _relying_party_id
+FROM webauthn.credentials WHERE credentials.credential_id = _credential_id;
+IF NOT FOUND THEN
+  SELECT assertion_challenges.user_name, assertion_challenges.relying_party_id INTO _user_name, _relying_party_id
+  FROM webauthn.assertion_challenges WHERE assertion_challenges.challenge = _challenge;
+END IF;
+
+PERFORM webauthn.check_lockout(_credential_id, _user_name, _relying_party_id, verify_assertion.verified_at);
+
+BEGIN
+  --
+  -- When scoped to a relying party, assertions for challenges
+  -- of any other relying party are treated as not matching.
+  --
+  IF verify_assertion.relying_party_id IS NULL
+  OR verify_assertion.relying_party_id = webauthn.assertion_challenge_relying_party_id(_challenge)
+  THEN
+    user_id := webauthn.insert_assertion(
+      credential_id := _credential_id,
+      credential_type := verify_assertion.credential_type,
+      authenticator_data := webauthn.base64url_decode(verify_assertion.authenticator_data),
+      client_data_json := webauthn.base64url_decode(verify_assertion.client_data_json),
+      challenge := _challenge,
+      signature := webauthn.base64url_decode(verify_assertion.signature),
+      user_handle := webauthn.base64url_decode(NULLIF(verify_assertion.user_handle,'')),
+      verified_at := verify_assertion.verified_at,
+      client_extension_results := verify_assertion.client_extension_results
+    );
+  END IF;
+EXCEPTION WHEN OTHERS THEN
+  --
+  -- Raising the error would roll back the recorded failure,
+  -- so when lockouts are enabled, an assertion for a known credential failing verification
+  -- is recorded and NULL is returned instead, like when no credential matches.
+  -- This includes a malformed signature or authenticator data, raised by the functions parsing them,
+  -- and not only the violated check constraints of webauthn.assertions, such as verified_signature.
+  -- Any other error, such as a replay or an unknown credential, is raised as usual.
+  --
+  GET STACKED DIAGNOSTICS _sqlstate = RETURNED_SQLSTATE, _error_message = MESSAGE_TEXT;
+  IF _sqlstate = 'P0001' OR left(_sqlstate,2) IN ('22','23') AND _sqlstate <> '23505' THEN
+    IF NOT EXISTS (SELECT FROM webauthn.lockout_policies)
+    OR NOT EXISTS (SELECT FROM webauthn.credentials WHERE credentials.credential_id = _credential_id)
+    THEN
+      RAISE;
+    END IF;
+  ELSE
+    RAISE;
+  END IF;
+END;
+
+IF user_id IS NULL AND EXISTS (SELECT FROM webauthn.lockout_policies) THEN
+  PERFORM webauthn.record_assertion_failure(
+    credential_id := _credential_id,
+    user_name := _user_name,
+    relying_party_id := _relying_party_id,
+    challenge := _challenge,
+    sqlstate := _sqlstate,
+    error_message := _error_message,
+    failed_at := verify_assertion.verified_at
+  );
+END IF;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.verify_assertion(
+  OUT user_id bytea,
+  credential jsonb,
+  verified_at timestamptz DEFAULT now(),
+  relying_party_id text DEFAULT NULL
+)
+RETURNS bytea
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
 LANGUAGE sql
 AS $$
-WITH
-decoded_input AS (
-  SELECT
-    webauthn.base64url_decode(credential_id) AS credential_id,
-    credential_type,
-    webauthn.base64url_decode(authenticator_data) AS authenticator_data,
-    webauthn.base64url_decode(client_data_json) AS client_data_json,
-    webauthn.base64url_decode(webauthn.from_utf8(webauthn.base64url_decode(client_data_json))::jsonb->>'challenge') AS challenge,
-    webauthn.base64url_decode(signature) AS signature,
-    webauthn.base64url_decode(NULLIF(user_handle,'')) AS user_handle,
-    verified_at
+SELECT webauthn.verify_assertion(
+  credential_id := public_key_credential->>'id',
+  credential_type := (public_key_credential->>'type')::webauthn.credential_type,
+  authenticator_data := public_key_credential->'response'->>'authenticatorData',
+  client_data_json := public_key_credential->'response'->>'clientDataJSON',
+  signature := public_key_credential->'response'->>'signature',
+  user_handle := public_key_credential->'response'->>'userHandle',
+  verified_at := verify_assertion.verified_at,
+  client_extension_results := public_key_credential->'clientExtensionResults',
+  relying_party_id := verify_assertion.relying_party_id
 )
-INSERT INTO webauthn.assertions (signature, credential_id, challenge, authenticator_data, client_data_json, user_id, user_handle, verified_at)
-SELECT
-  decoded_input.signature,
-  credentials.credential_id,
-  assertion_challenges.challenge,
-  decoded_input.authenticator_data,
-  decoded_input.client_data_json,
-  credentials.user_id,
-  decoded_input.user_handle,
-  decoded_input.verified_at
-FROM decoded_input
-JOIN webauthn.assertion_challenges ON assertion_challenges.challenge = decoded_input.challenge
-JOIN webauthn.credentials ON  credentials.credential_id   = decoded_input.credential_id
-                         AND  credentials.credential_type = decoded_input.credential_type
-                         AND (credentials.user_name      <> assertion_challenges.user_name) IS NOT TRUE
-RETURNING assertions.user_id
+FROM webauthn.check_public_key_credential(
+  credential := verify_assertion.credential,
+  response_members := ARRAY['clientDataJSON','authenticatorData','signature']
+) AS public_key_credential
 $$;
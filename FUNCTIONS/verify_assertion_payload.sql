@@ -0,0 +1,69 @@
+CREATE OR REPLACE FUNCTION webauthn.verify_assertion_payload(
+  OUT user_id bytea,
+  OUT payload jsonb,
+  credential_id text,
+  credential_type webauthn.credential_type,
+  authenticator_data text,
+  client_data_json text,
+  signature text,
+  user_handle text,
+  verified_at timestamptz DEFAULT now(),
+  client_extension_results jsonb DEFAULT NULL,
+  relying_party_id text DEFAULT NULL
+)
+RETURNS record
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+--
+-- Like webauthn.verify_assertion(), but also returns the payload
+-- the challenge commits to, once the assertion has been verified.
+--
+SELECT
+  verified_assertion.user_id,
+  CASE WHEN verified_assertion.user_id IS NOT NULL THEN assertion_challenges.payload END
+FROM webauthn.verify_assertion(
+  credential_id := verify_assertion_payload.credential_id,
+  credential_type := verify_assertion_payload.credential_type,
+  authenticator_data := verify_assertion_payload.authenticator_data,
+  client_data_json := verify_assertion_payload.client_data_json,
+  signature := verify_assertion_payload.signature,
+  user_handle := verify_assertion_payload.user_handle,
+  verified_at := verify_assertion_payload.verified_at,
+  client_extension_results := verify_assertion_payload.client_extension_results,
+  relying_party_id := verify_assertion_payload.relying_party_id
+) AS verified_assertion
+LEFT JOIN webauthn.assertion_challenges
+  ON assertion_challenges.challenge = webauthn.base64url_decode(webauthn.parse_client_data_json(webauthn.base64url_decode(verify_assertion_payload.client_data_json))->>'challenge')
+$$;
+
+CREATE OR REPLACE FUNCTION webauthn.verify_assertion_payload(
+  OUT user_id bytea,
+  OUT payload jsonb,
+  credential jsonb,
+  verified_at timestamptz DEFAULT now(),
+  relying_party_id text DEFAULT NULL
+)
+RETURNS record
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+LANGUAGE sql
+AS $$
+SELECT verified_assertion.user_id, verified_assertion.payload
+FROM webauthn.check_public_key_credential(
+  credential := verify_assertion_payload.credential,
+  response_members := ARRAY['clientDataJSON','authenticatorData','signature']
+) AS public_key_credential
+CROSS JOIN LATERAL webauthn.verify_assertion_payload(
+  credential_id := public_key_credential->>'id',
+  credential_type := (public_key_credential->>'type')::webauthn.credential_type,
+  authenticator_data := public_key_credential->'response'->>'authenticatorData',
+  client_data_json := public_key_credential->'response'->>'clientDataJSON',
+  signature := public_key_credential->'response'->>'signature',
+  user_handle := public_key_credential->'response'->>'userHandle',
+  verified_at := verify_assertion_payload.verified_at,
+  client_extension_results := public_key_credential->'clientExtensionResults',
+  relying_party_id := verify_assertion_payload.relying_party_id
+) AS verified_assertion
+$$;
@@ -5,8 +5,27 @@ user_verification webauthn.user_verification_requirement NOT NULL,
 timeout interval NOT NULL,
 relying_party_id text,
 challenge_at timestamptz NOT NULL,
+extensions jsonb,
+payment jsonb,
+nonce bytea,
+payload jsonb,
+user_id bytea,
+allow_cross_origin boolean,
+mediation webauthn.credential_mediation_requirement,
+hints webauthn.public_key_credential_hint[],
 PRIMARY KEY (challenge),
-CONSTRAINT reasonable_timeout CHECK (timeout BETWEEN '30000 ms' AND '600000 ms')
+--
+-- Challenges for conditional mediation may be longer lived,
+-- see webauthn.relying_parties.conditional_mediation_timeout.
+--
+CONSTRAINT reasonable_timeout CHECK (timeout BETWEEN '30000 ms' AND CASE WHEN mediation = 'conditional' THEN interval '24 hours' ELSE interval '600000 ms' END),
+CONSTRAINT conditional_mediation_discoverable CHECK (mediation IS DISTINCT FROM 'conditional' OR num_nulls(user_name, user_id) = 2),
+CONSTRAINT extensions_object CHECK (jsonb_typeof(extensions) = 'object'),
+CONSTRAINT hints_not_empty CHECK (cardinality(hints) > 0),
+CONSTRAINT payment_payee CHECK (payment ?| ARRAY['payeeName','payeeOrigin']),
+CONSTRAINT payload_challenge CHECK (COALESCE(challenge = webauthn.payload_challenge(nonce, payload), num_nulls(nonce, payload) = 2)),
+CONSTRAINT relying_party_user_verification CHECK (user_verification = webauthn.relying_party_user_verification(relying_party_id, user_verification)),
+FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties
 );
 
 SELECT pg_catalog.pg_extension_config_dump('assertion_challenges', '');
@@ -18,3 +37,13 @@ COMMENT ON COLUMN webauthn.assertion_challenges.relying_party_id IS 'https://www
 COMMENT ON COLUMN webauthn.assertion_challenges.user_name IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialcreationoptions-user';
 COMMENT ON COLUMN webauthn.assertion_challenges.timeout IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialrequestoptions-timeout';
 COMMENT ON COLUMN webauthn.assertion_challenges.challenge_at IS 'Timestamp of when the challenge was created by webauthn.get_credentials()';
+COMMENT ON COLUMN webauthn.assertion_challenges.extensions IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialrequestoptions-extensions';
+COMMENT ON COLUMN webauthn.assertion_challenges.payment IS 'The transaction details expected to be confirmed, set by webauthn.get_payment_credentials(), https://www.w3.org/TR/secure-payment-confirmation/#sctn-collectedclientadditionalpaymentdata-dictionary';
+COMMENT ON COLUMN webauthn.assertion_challenges.nonce IS 'The nonce given to webauthn.get_credentials() for a challenge committing to a payload';
+COMMENT ON COLUMN webauthn.assertion_challenges.payload IS 'The payload the challenge commits to, returned by webauthn.verify_assertion_payload() once verified';
+COMMENT ON COLUMN webauthn.assertion_challenges.user_id IS 'Set by webauthn.get_credentials() for a re-authentication challenge, restricted to credentials of the already known user';
+COMMENT ON COLUMN webauthn.assertion_challenges.allow_cross_origin IS 'Whether the challenge may be used in a cross-origin iframe, set by webauthn.get_credentials(), or NULL to use the policy of the relying party';
+COMMENT ON COLUMN webauthn.assertion_challenges.mediation IS 'https://w3c.github.io/webappsec-credential-management/#dom-credentialrequestoptions-mediation';
+COMMENT ON COLUMN webauthn.assertion_challenges.hints IS 'https://www.w3.org/TR/webauthn-3/#dom-publickeycredentialrequestoptions-hints';
+
+CREATE POLICY relying_party_role ON webauthn.assertion_challenges USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
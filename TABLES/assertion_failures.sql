@@ -0,0 +1,29 @@
+CREATE TABLE webauthn.assertion_failures (
+assertion_failure_id bigint NOT NULL GENERATED ALWAYS AS IDENTITY,
+credential_id bytea,
+user_name text,
+relying_party_id text,
+challenge bytea,
+sqlstate text,
+error_message text NOT NULL,
+failed_at timestamptz NOT NULL,
+PRIMARY KEY (assertion_failure_id),
+FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties
+);
+
+CREATE INDEX ON webauthn.assertion_failures (credential_id, failed_at);
+CREATE INDEX ON webauthn.assertion_failures (user_name, failed_at);
+
+SELECT pg_catalog.pg_extension_config_dump('assertion_failures', '');
+
+COMMENT ON TABLE webauthn.assertion_failures IS 'Used by webauthn.verify_assertion() to record failed assertions when webauthn.lockout_policies is non-empty.';
+
+COMMENT ON COLUMN webauthn.assertion_failures.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.assertion_failures.user_name IS 'user_name of the credential, or of the assertion challenge if the credential is unknown';
+COMMENT ON COLUMN webauthn.assertion_failures.relying_party_id IS 'relying_party_id of the credential, or of the assertion challenge if the credential is unknown, since user_name is only unique per relying party';
+COMMENT ON COLUMN webauthn.assertion_failures.challenge IS 'https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-challenge';
+COMMENT ON COLUMN webauthn.assertion_failures.sqlstate IS 'SQLSTATE of the error that caused the failure, or NULL if no matching credential was found';
+COMMENT ON COLUMN webauthn.assertion_failures.error_message IS 'Error message of the error that caused the failure';
+COMMENT ON COLUMN webauthn.assertion_failures.failed_at IS 'Timestamp of when the assertion failed in webauthn.verify_assertion()';
+
+CREATE POLICY relying_party_role ON webauthn.assertion_failures USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
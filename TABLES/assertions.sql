@@ -3,23 +3,44 @@ signature bytea NOT NULL,
 challenge bytea NOT NULL REFERENCES webauthn.assertion_challenges,
 credential_id bytea NOT NULL REFERENCES webauthn.credentials,
 authenticator_data bytea NOT NULL,
-rp_id_hash bytea NOT NULL GENERATED ALWAYS AS ((webauthn.parse_authenticator_data(authenticator_data)).rp_id_hash) STORED,
-user_present boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_authenticator_data(authenticator_data)).user_present) STORED,
-user_verified boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_authenticator_data(authenticator_data)).user_verified) STORED,
-attested_credential_data_included boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_authenticator_data(authenticator_data)).attested_credential_data_included) STORED,
-extension_data_included boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_authenticator_data(authenticator_data)).extension_data_included) STORED,
-sign_count bigint NOT NULL GENERATED ALWAYS AS ((webauthn.parse_authenticator_data(authenticator_data)).sign_count) STORED,
+rp_id_hash bytea NOT NULL,
+user_present boolean NOT NULL,
+user_verified boolean NOT NULL,
+attested_credential_data_included boolean NOT NULL,
+extension_data_included boolean NOT NULL,
+sign_count bigint NOT NULL,
 client_data_json bytea NOT NULL,
 origin text NOT NULL GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->>'origin') STORED,
 cross_origin boolean GENERATED ALWAYS AS ((webauthn.from_utf8(client_data_json)::jsonb->'crossOrigin')::boolean) STORED,
 user_id bytea NOT NULL,
 user_handle bytea,
 verified_at timestamptz NOT NULL,
+prf_salts jsonb,
+client_extension_results jsonb,
+payment jsonb GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->'payment') STORED,
+top_origin text GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->>'topOrigin') STORED,
+client_data jsonb NOT NULL,
 PRIMARY KEY (signature),
 UNIQUE (challenge),
-CONSTRAINT client_data_json_type CHECK ('webauthn.get' = webauthn.from_utf8(client_data_json)::jsonb->>'type'),
+CONSTRAINT client_data_json_type CHECK (CASE WHEN webauthn.assertion_challenge_payment(challenge) IS NULL THEN 'webauthn.get' ELSE 'payment.get' END = webauthn.from_utf8(client_data_json)::jsonb->>'type'),
 CONSTRAINT client_data_json_challenge CHECK (challenge = webauthn.base64url_decode(webauthn.from_utf8(client_data_json)::jsonb->>'challenge')),
 CONSTRAINT user_handle_equal_or_null CHECK (user_handle = user_id),
+--
+-- With conditional mediation, the user is only known from the user handle of the discoverable credential.
+--
+CONSTRAINT user_handle_if_conditional CHECK (user_handle IS NOT NULL OR webauthn.assertion_challenge_mediation(challenge) IS DISTINCT FROM 'conditional'),
+CONSTRAINT client_extension_results_object CHECK (jsonb_typeof(client_extension_results) = 'object'),
+CONSTRAINT payment_matches CHECK (payment - 'topOrigin' IS NOT DISTINCT FROM webauthn.assertion_challenge_payment(challenge)),
+--
+-- https://www.w3.org/TR/webauthn-2/#sctn-appid-extension
+-- For a credential imported with an AppID, the rpIdHash must be the hash of the RP ID,
+-- or if the client reports that the AppID was used, the hash of the AppID.
+--
+CONSTRAINT rp_id_hash_matches CHECK (webauthn.credential_appid(credential_id) IS NULL OR COALESCE(
+  rp_id_hash = public.digest(webauthn.assertion_challenge_relying_party_id(challenge),'sha256')
+  OR (client_extension_results->'appid')::boolean AND rp_id_hash = public.digest(webauthn.credential_appid(credential_id),'sha256'),
+FALSE)),
+CONSTRAINT top_origin_only_if_cross_origin CHECK (top_origin IS NULL OR cross_origin),
 CONSTRAINT user_verified_or_not_required CHECK (user_verified OR webauthn.assertion_challenge_user_verification(challenge) <> 'required'),
 CONSTRAINT verified_before_timeout CHECK (verified_at < webauthn.assertion_challenge_expiration(challenge)),
 CONSTRAINT verified_signature CHECK (COALESCE(ecdsa_verify.ecdsa_verify(
@@ -30,8 +51,32 @@ CONSTRAINT verified_signature CHECK (COALESCE(ecdsa_verify.ecdsa_verify(
   curve_name := 'secp256r1'),FALSE))
 );
 
+CREATE INDEX ON webauthn.assertions (user_id, verified_at);
+
 SELECT pg_catalog.pg_extension_config_dump('assertions', '');
 
+--
+-- The columns derived from the authenticator data are set by this trigger.
+--
+CREATE TRIGGER parse_authenticator_data
+BEFORE INSERT OR UPDATE ON webauthn.assertions
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_assertion_authenticator_data();
+
+--
+-- The client data is parsed by this trigger, which raises an error if it is invalid.
+--
+CREATE TRIGGER parse_client_data
+BEFORE INSERT OR UPDATE ON webauthn.assertions
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_client_data();
+
+--
+-- The client data is checked against the relying party's policies by this trigger,
+-- which fires after parse_client_data, since triggers fire in alphabetical order.
+--
+CREATE TRIGGER relying_party_policy
+BEFORE INSERT ON webauthn.assertions
+FOR EACH ROW EXECUTE FUNCTION webauthn.check_relying_party_policy();
+
 COMMENT ON TABLE webauthn.assertions IS 'Used by webauthn.verify_assertion() to store verified assertions.';
 
 COMMENT ON COLUMN webauthn.assertions.signature IS 'https://www.w3.org/TR/webauthn-2/#assertion-signature';
@@ -50,3 +95,10 @@ COMMENT ON COLUMN webauthn.assertions.cross_origin IS 'https://www.w3.org/TR/web
 COMMENT ON COLUMN webauthn.assertions.user_id IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialuserentity-id';
 COMMENT ON COLUMN webauthn.assertions.user_handle IS 'https://www.w3.org/TR/webauthn-2/#dom-authenticatorassertionresponse-userhandle';
 COMMENT ON COLUMN webauthn.assertions.verified_at IS 'Timestamp of when the assertion was verified by webauthn.verify_assertion()';
+COMMENT ON COLUMN webauthn.assertions.prf_salts IS 'The PRF salts requested for the credential by webauthn.get_credentials(), https://www.w3.org/TR/webauthn-3/#dictdef-authenticationextensionsprfvalues';
+COMMENT ON COLUMN webauthn.assertions.client_extension_results IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredential-getclientextensionresults';
+COMMENT ON COLUMN webauthn.assertions.payment IS 'The transaction confirmed with Secure Payment Confirmation, https://www.w3.org/TR/secure-payment-confirmation/#sctn-collectedclientadditionalpaymentdata-dictionary';
+COMMENT ON COLUMN webauthn.assertions.top_origin IS 'https://www.w3.org/TR/webauthn-3/#dom-collectedclientdata-toporigin';
+COMMENT ON COLUMN webauthn.assertions.client_data IS 'The parsed client data, kept for audit, https://www.w3.org/TR/webauthn-2/#dictdef-collectedclientdata';
+
+CREATE POLICY relying_party_role ON webauthn.assertions USING (credential_id IN (SELECT credentials.credential_id FROM webauthn.credentials));
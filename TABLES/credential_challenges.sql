@@ -10,8 +10,16 @@ attestation webauthn.attestation_conveyance_preference NOT NULL,
 timeout interval NOT NULL,
 challenge_at timestamptz NOT NULL,
 require_resident_key boolean NOT NULL DEFAULT FALSE,
+extensions jsonb,
+allow_cross_origin boolean,
+authenticator_attachment webauthn.authenticator_attachment,
+hints webauthn.public_key_credential_hint[],
 PRIMARY KEY (challenge),
-CONSTRAINT reasonable_timeout CHECK (timeout BETWEEN '30000 ms' AND '600000 ms')
+CONSTRAINT reasonable_timeout CHECK (timeout BETWEEN '30000 ms' AND '600000 ms'),
+CONSTRAINT extensions_object CHECK (jsonb_typeof(extensions) = 'object'),
+CONSTRAINT hints_not_empty CHECK (cardinality(hints) > 0),
+CONSTRAINT relying_party_user_verification CHECK (user_verification = webauthn.relying_party_user_verification(relying_party_id, user_verification)),
+FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties
 );
 
 SELECT pg_catalog.pg_extension_config_dump('credential_challenges', '');
@@ -29,3 +37,9 @@ COMMENT ON COLUMN webauthn.credential_challenges.relying_party_name IS 'https://
 COMMENT ON COLUMN webauthn.credential_challenges.relying_party_id IS 'https://www.w3.org/TR/webauthn-2/#relying-party-identifier';
 COMMENT ON COLUMN webauthn.credential_challenges.challenge_at IS 'Timestamp of when the challenge was created by webauthn.init_credential()';
 COMMENT ON COLUMN webauthn.credential_challenges.require_resident_key IS 'https://www.w3.org/TR/webauthn-2/#dom-authenticatorselectioncriteria-requireresidentkey';
+COMMENT ON COLUMN webauthn.credential_challenges.extensions IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialcreationoptions-extensions';
+COMMENT ON COLUMN webauthn.credential_challenges.allow_cross_origin IS 'Whether the challenge may be used in a cross-origin iframe, set by webauthn.init_credential(), or NULL to use the policy of the relying party';
+COMMENT ON COLUMN webauthn.credential_challenges.authenticator_attachment IS 'https://www.w3.org/TR/webauthn-2/#dom-authenticatorselectioncriteria-authenticatorattachment';
+COMMENT ON COLUMN webauthn.credential_challenges.hints IS 'https://www.w3.org/TR/webauthn-3/#dom-publickeycredentialcreationoptions-hints';
+
+CREATE POLICY relying_party_role ON webauthn.credential_challenges USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
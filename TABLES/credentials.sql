@@ -2,14 +2,14 @@ CREATE TABLE webauthn.credentials (
 credential_id bytea NOT NULL,
 credential_type webauthn.credential_type NOT NULL,
 attestation_object bytea NOT NULL,
-rp_id_hash bytea NOT NULL GENERATED ALWAYS AS ((webauthn.parse_attestation_object(attestation_object)).rp_id_hash) STORED,
-user_present boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_attestation_object(attestation_object)).user_present) STORED,
-user_verified boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_attestation_object(attestation_object)).user_verified) STORED,
-attested_credential_data_included boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_attestation_object(attestation_object)).attested_credential_data_included) STORED,
-extension_data_included boolean NOT NULL GENERATED ALWAYS AS ((webauthn.parse_attestation_object(attestation_object)).extension_data_included) STORED,
-sign_count bigint NOT NULL GENERATED ALWAYS AS ((webauthn.parse_attestation_object(attestation_object)).sign_count) STORED,
-aaguid bytea NOT NULL GENERATED ALWAYS AS ((webauthn.parse_attestation_object(attestation_object)).aaguid) STORED,
-public_key bytea NOT NULL GENERATED ALWAYS AS (webauthn.cose_ecdha_to_pkcs((webauthn.parse_attestation_object(attestation_object)).credential_public_key)) STORED,
+rp_id_hash bytea NOT NULL,
+user_present boolean NOT NULL,
+user_verified boolean NOT NULL,
+attested_credential_data_included boolean NOT NULL,
+extension_data_included boolean NOT NULL,
+sign_count bigint NOT NULL,
+aaguid bytea NOT NULL,
+public_key bytea NOT NULL,
 client_data_json bytea NOT NULL,
 origin text NOT NULL GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->>'origin') STORED,
 cross_origin boolean GENERATED ALWAYS AS ((webauthn.from_utf8(client_data_json)::jsonb->'crossOrigin')::boolean) STORED,
@@ -17,17 +17,67 @@ challenge bytea NOT NULL,
 user_name text NOT NULL,
 user_id bytea NOT NULL,
 credential_at timestamptz NOT NULL,
+revoked_at timestamptz,
+authenticator_extension_outputs jsonb,
+client_extension_results jsonb,
+prf_enabled boolean GENERATED ALWAYS AS ((client_extension_results->'prf'->>'enabled')::boolean) STORED,
+large_blob_supported boolean GENERATED ALWAYS AS ((client_extension_results->'largeBlob'->>'supported')::boolean) STORED,
+appid text,
+relying_party_id text,
+kty integer NOT NULL,
+alg integer NOT NULL,
+crv integer NOT NULL,
+top_origin text GENERATED ALWAYS AS (webauthn.from_utf8(client_data_json)::jsonb->>'topOrigin') STORED,
+client_data jsonb NOT NULL,
+authenticator_attachment webauthn.authenticator_attachment,
+attestation_certificate_serial bytea,
 PRIMARY KEY (credential_id),
 UNIQUE (challenge),
 CONSTRAINT client_data_json_type CHECK ('webauthn.create' = webauthn.from_utf8(client_data_json)::jsonb->>'type'),
 CONSTRAINT client_data_json_challenge CHECK (challenge = webauthn.base64url_decode(webauthn.from_utf8(client_data_json)::jsonb->>'challenge')),
-CONSTRAINT attestation_object_credential_id CHECK (credential_id = (webauthn.parse_attestation_object(attestation_object)).credential_id),
 CONSTRAINT user_verified_or_not_required CHECK (user_verified OR webauthn.credential_challenge_user_verification(challenge) <> 'required'),
-CONSTRAINT credential_before_timeout CHECK (credential_at < webauthn.credential_challenge_expiration(challenge))
+CONSTRAINT credential_before_timeout CHECK (credential_at < webauthn.credential_challenge_expiration(challenge)),
+CONSTRAINT revoked_after_credential CHECK (revoked_at >= credential_at),
+CONSTRAINT cred_protect_at_least_requested CHECK (COALESCE((authenticator_extension_outputs->>'credProtect')::integer,1) >= webauthn.credential_challenge_cred_protect(challenge)),
+CONSTRAINT client_extension_results_object CHECK (jsonb_typeof(client_extension_results) = 'object'),
+CONSTRAINT large_blob_supported_if_required CHECK (large_blob_supported IS TRUE OR webauthn.credential_challenge_large_blob_support(challenge) IS DISTINCT FROM 'required'),
+CONSTRAINT rp_id_hash_appid CHECK (rp_id_hash = public.digest(appid,'sha256')),
+CONSTRAINT relying_party_id_matches_challenge CHECK (relying_party_id IS NOT DISTINCT FROM webauthn.credential_challenge_relying_party_id(challenge)),
+CONSTRAINT top_origin_only_if_cross_origin CHECK (top_origin IS NULL OR cross_origin),
+--
+-- Signatures are only verified with ES256, see the verified_signature check in webauthn.assertions.
+--
+CONSTRAINT public_key_algorithm_supported CHECK (kty = 2 AND alg = -7 AND crv = 1),
+FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties
 );
 
 SELECT pg_catalog.pg_extension_config_dump('credentials', '');
 
+--
+-- The columns derived from the attestation object are set by this trigger,
+-- which also checks the credential_id matches the attested credential data,
+-- and that the credential public key is a valid COSE_Key.
+-- Updates of other columns, e.g. by webauthn.revoke_credential(), don't parse it again.
+--
+CREATE TRIGGER parse_attestation_object
+BEFORE INSERT OR UPDATE OF attestation_object ON webauthn.credentials
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_credential_attestation_object();
+
+--
+-- The client data is parsed by this trigger, which raises an error if it is invalid.
+--
+CREATE TRIGGER parse_client_data
+BEFORE INSERT OR UPDATE ON webauthn.credentials
+FOR EACH ROW EXECUTE FUNCTION webauthn.parse_client_data();
+
+--
+-- The client data is checked against the relying party's policies by this trigger,
+-- which fires after parse_client_data, since triggers fire in alphabetical order.
+--
+CREATE TRIGGER relying_party_policy
+BEFORE INSERT ON webauthn.credentials
+FOR EACH ROW EXECUTE FUNCTION webauthn.check_relying_party_policy();
+
 --
 -- Storing "user_name" and "user_id" in webauthn.credentials is a denormalization decision
 -- to avoid having to JOIN webauthn.credential_challenges for every webauthn.get_credentials() call
@@ -66,3 +116,19 @@ COMMENT ON COLUMN webauthn.credentials.origin IS 'https://www.w3.org/TR/webauthn
 COMMENT ON COLUMN webauthn.credentials.cross_origin IS 'https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-crossorigin';
 COMMENT ON COLUMN webauthn.credentials.user_id IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialuserentity-id';
 COMMENT ON COLUMN webauthn.credentials.credential_at IS 'Timestamp of when the credential was created by webauthn.store_credential()';
+COMMENT ON COLUMN webauthn.credentials.revoked_at IS 'Timestamp of when the credential was revoked by webauthn.revoke_credential()';
+COMMENT ON COLUMN webauthn.credentials.authenticator_extension_outputs IS 'https://www.w3.org/TR/webauthn-2/#authenticator-extension-output';
+COMMENT ON COLUMN webauthn.credentials.client_extension_results IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredential-getclientextensionresults';
+COMMENT ON COLUMN webauthn.credentials.prf_enabled IS 'https://www.w3.org/TR/webauthn-3/#dom-authenticationextensionsprfoutputs-enabled';
+COMMENT ON COLUMN webauthn.credentials.large_blob_supported IS 'https://www.w3.org/TR/webauthn-2/#dom-authenticationextensionslargebloboutputs-supported';
+COMMENT ON COLUMN webauthn.credentials.appid IS 'The AppID of a U2F registration imported by webauthn.import_u2f_credential(), https://www.w3.org/TR/webauthn-2/#sctn-appid-extension';
+COMMENT ON COLUMN webauthn.credentials.relying_party_id IS 'https://www.w3.org/TR/webauthn-2/#relying-party-identifier';
+COMMENT ON COLUMN webauthn.credentials.kty IS 'https://www.rfc-editor.org/rfc/rfc8152#section-7.1';
+COMMENT ON COLUMN webauthn.credentials.alg IS 'https://www.w3.org/TR/webauthn-2/#typedefdef-cosealgorithmidentifier';
+COMMENT ON COLUMN webauthn.credentials.crv IS 'https://www.rfc-editor.org/rfc/rfc8152#section-13.1.1';
+COMMENT ON COLUMN webauthn.credentials.top_origin IS 'https://www.w3.org/TR/webauthn-3/#dom-collectedclientdata-toporigin';
+COMMENT ON COLUMN webauthn.credentials.client_data IS 'The parsed client data, kept for audit, https://www.w3.org/TR/webauthn-2/#dictdef-collectedclientdata';
+COMMENT ON COLUMN webauthn.credentials.authenticator_attachment IS 'The attachment reported by the client when the credential was created, https://www.w3.org/TR/webauthn-3/#dom-publickeycredential-authenticatorattachment';
+COMMENT ON COLUMN webauthn.credentials.attestation_certificate_serial IS 'The serial number of the attestation certificate of an enterprise attestation, identifying the authenticator as reported by the client, since neither the attestation statement nor its certificate chain are verified, https://www.w3.org/TR/webauthn-2/#dom-attestationconveyancepreference-enterprise';
+
+CREATE POLICY relying_party_role ON webauthn.credentials USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
@@ -0,0 +1,24 @@
+CREATE TABLE webauthn.large_blobs (
+credential_id bytea NOT NULL REFERENCES webauthn.credentials,
+version integer NOT NULL,
+blob_hash bytea NOT NULL,
+blob_size integer NOT NULL,
+challenge bytea NOT NULL REFERENCES webauthn.assertion_challenges,
+written_at timestamptz NOT NULL,
+PRIMARY KEY (credential_id, version),
+UNIQUE (challenge),
+CONSTRAINT positive_version CHECK (version > 0)
+);
+
+SELECT pg_catalog.pg_extension_config_dump('large_blobs', '');
+
+COMMENT ON TABLE webauthn.large_blobs IS 'Used by webauthn.verify_assertion() to store which blob version each credential holds, written with the largeBlob extension.';
+
+COMMENT ON COLUMN webauthn.large_blobs.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.large_blobs.version IS 'Incremented for every blob written to the credential';
+COMMENT ON COLUMN webauthn.large_blobs.blob_hash IS 'SHA-256 hash of the blob, https://www.w3.org/TR/webauthn-2/#dom-authenticationextensionslargeblobinputs-write';
+COMMENT ON COLUMN webauthn.large_blobs.blob_size IS 'Size of the blob in bytes';
+COMMENT ON COLUMN webauthn.large_blobs.challenge IS 'https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-challenge';
+COMMENT ON COLUMN webauthn.large_blobs.written_at IS 'Timestamp of when the write was confirmed by webauthn.verify_assertion()';
+
+CREATE POLICY relying_party_role ON webauthn.large_blobs USING (credential_id IN (SELECT credentials.credential_id FROM webauthn.credentials));
@@ -0,0 +1,19 @@
+CREATE TABLE webauthn.lockout_policies (
+scope webauthn.lockout_scope NOT NULL,
+max_failures integer NOT NULL,
+failure_window interval NOT NULL,
+cooldown interval NOT NULL,
+PRIMARY KEY (scope),
+CONSTRAINT positive_max_failures CHECK (max_failures > 0),
+CONSTRAINT positive_failure_window CHECK (failure_window > '0'::interval),
+CONSTRAINT positive_cooldown CHECK (cooldown > '0'::interval)
+);
+
+SELECT pg_catalog.pg_extension_config_dump('lockout_policies', '');
+
+COMMENT ON TABLE webauthn.lockout_policies IS 'Thresholds for failed webauthn.verify_assertion() calls, after which webauthn.lockouts are created. No rows means lockouts are disabled.';
+
+COMMENT ON COLUMN webauthn.lockout_policies.scope IS 'Whether failures are counted per credential_id or per user_name';
+COMMENT ON COLUMN webauthn.lockout_policies.max_failures IS 'Number of failures within failure_window that triggers a lockout';
+COMMENT ON COLUMN webauthn.lockout_policies.failure_window IS 'How far back failures are counted';
+COMMENT ON COLUMN webauthn.lockout_policies.cooldown IS 'How long a triggered lockout lasts';
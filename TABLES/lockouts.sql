@@ -0,0 +1,32 @@
+CREATE TABLE webauthn.lockouts (
+lockout_id bigint NOT NULL GENERATED ALWAYS AS IDENTITY,
+scope webauthn.lockout_scope NOT NULL,
+credential_id bytea,
+user_name text,
+relying_party_id text,
+locked_at timestamptz NOT NULL,
+locked_until timestamptz NOT NULL,
+PRIMARY KEY (lockout_id),
+CONSTRAINT scope_subject CHECK (CASE scope
+  WHEN 'credential' THEN credential_id IS NOT NULL AND user_name IS NULL
+  WHEN 'user_name' THEN user_name IS NOT NULL AND credential_id IS NULL
+END),
+CONSTRAINT locked_until_after_locked_at CHECK (locked_until > locked_at),
+FOREIGN KEY (relying_party_id) REFERENCES webauthn.relying_parties
+);
+
+CREATE INDEX ON webauthn.lockouts (credential_id, locked_until);
+CREATE INDEX ON webauthn.lockouts (user_name, locked_until);
+
+SELECT pg_catalog.pg_extension_config_dump('lockouts', '');
+
+COMMENT ON TABLE webauthn.lockouts IS 'Used by webauthn.verify_assertion() to store lockouts triggered by webauthn.lockout_policies.';
+
+COMMENT ON COLUMN webauthn.lockouts.scope IS 'Whether the lockout applies to credential_id or to user_name';
+COMMENT ON COLUMN webauthn.lockouts.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.lockouts.user_name IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialcreationoptions-user';
+COMMENT ON COLUMN webauthn.lockouts.relying_party_id IS 'https://www.w3.org/TR/webauthn-2/#relying-party-identifier, the lockout only applies to this relying party';
+COMMENT ON COLUMN webauthn.lockouts.locked_at IS 'Timestamp of the failure that triggered the lockout';
+COMMENT ON COLUMN webauthn.lockouts.locked_until IS 'Timestamp of when the lockout expires';
+
+CREATE POLICY relying_party_role ON webauthn.lockouts USING (relying_party_id IN (SELECT relying_parties.relying_party_id FROM webauthn.relying_parties));
@@ -0,0 +1,26 @@
+CREATE TABLE webauthn.recovery_codes (
+recovery_code_id bigint NOT NULL GENERATED ALWAYS AS IDENTITY,
+user_id bytea NOT NULL,
+code_hash bytea NOT NULL,
+generated_at timestamptz NOT NULL,
+redeemed_at timestamptz,
+revoked_at timestamptz,
+PRIMARY KEY (recovery_code_id),
+UNIQUE (code_hash),
+CONSTRAINT redeemed_after_generated CHECK (redeemed_at >= generated_at),
+CONSTRAINT revoked_after_generated CHECK (revoked_at >= generated_at)
+);
+
+CREATE INDEX ON webauthn.recovery_codes (user_id);
+
+SELECT pg_catalog.pg_extension_config_dump('recovery_codes', '');
+
+COMMENT ON TABLE webauthn.recovery_codes IS 'Used by webauthn.generate_recovery_codes() to store recovery codes, a fallback for users who have lost all their authenticators.';
+
+COMMENT ON COLUMN webauthn.recovery_codes.user_id IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialuserentity-id';
+COMMENT ON COLUMN webauthn.recovery_codes.code_hash IS 'SHA-256 hash of the normalized recovery code, which is only returned by webauthn.generate_recovery_codes()';
+COMMENT ON COLUMN webauthn.recovery_codes.generated_at IS 'Timestamp of when the code was generated by webauthn.generate_recovery_codes()';
+COMMENT ON COLUMN webauthn.recovery_codes.redeemed_at IS 'Timestamp of when the code was redeemed by webauthn.redeem_recovery_code()';
+COMMENT ON COLUMN webauthn.recovery_codes.revoked_at IS 'Timestamp of when the code was replaced by a new set of codes from webauthn.generate_recovery_codes()';
+
+CREATE POLICY relying_party_role ON webauthn.recovery_codes USING (user_id IN (SELECT credentials.user_id FROM webauthn.credentials));
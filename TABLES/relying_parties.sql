@@ -0,0 +1,37 @@
+CREATE TABLE webauthn.relying_parties (
+relying_party_id text NOT NULL,
+relying_party_name text NOT NULL,
+origins text[],
+require_user_verification boolean NOT NULL DEFAULT FALSE,
+allow_cross_origin boolean NOT NULL DEFAULT FALSE,
+top_origins text[],
+token_binding webauthn.token_binding_policy NOT NULL DEFAULT 'ignore',
+conditional_mediation_timeout interval NOT NULL DEFAULT '1 hour',
+enterprise_attestation boolean NOT NULL DEFAULT FALSE,
+role_name name,
+PRIMARY KEY (relying_party_id),
+CONSTRAINT origins_not_empty CHECK (cardinality(origins) > 0),
+CONSTRAINT top_origins_not_empty CHECK (cardinality(top_origins) > 0),
+CONSTRAINT reasonable_conditional_mediation_timeout CHECK (conditional_mediation_timeout BETWEEN '30000 ms' AND '24 hours')
+);
+
+SELECT pg_catalog.pg_extension_config_dump('relying_parties', '');
+
+COMMENT ON TABLE webauthn.relying_parties IS 'Registry of relying parties, referenced by challenges and credentials. Registered by webauthn.init_credential() on first use, unless inserted beforehand to set its policies.';
+
+COMMENT ON COLUMN webauthn.relying_parties.relying_party_id IS 'https://www.w3.org/TR/webauthn-2/#relying-party-identifier';
+COMMENT ON COLUMN webauthn.relying_parties.relying_party_name IS 'https://www.w3.org/TR/webauthn-2/#dictionary-rp-credential-params';
+COMMENT ON COLUMN webauthn.relying_parties.origins IS 'Origins allowed in the client data of the relying party''s credentials and assertions, or NULL to allow any origin, https://www.w3.org/TR/webauthn-2/#sctn-validating-origin';
+COMMENT ON COLUMN webauthn.relying_parties.require_user_verification IS 'Whether all challenges of the relying party require user verification, regardless of the user_verification requested';
+COMMENT ON COLUMN webauthn.relying_parties.allow_cross_origin IS 'Whether credentials and assertions may be created in a cross-origin iframe, unless overridden by the challenge, https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-crossorigin';
+COMMENT ON COLUMN webauthn.relying_parties.top_origins IS 'Top-level origins allowed in the client data of cross-origin credentials and assertions, or NULL to allow any top-level origin, https://www.w3.org/TR/webauthn-3/#dom-collectedclientdata-toporigin';
+COMMENT ON COLUMN webauthn.relying_parties.token_binding IS 'Whether the relying party''s credentials and assertions may, must not or must have the tokenBinding status "present" in their client data, https://www.w3.org/TR/webauthn-2/#dom-collectedclientdata-tokenbinding';
+COMMENT ON COLUMN webauthn.relying_parties.conditional_mediation_timeout IS 'The timeout of challenges created by webauthn.get_credentials() for conditional mediation, i.e. passkey autofill, which stay open while the user fills in the sign-in form, https://w3c.github.io/webappsec-credential-management/#dom-credentialmediationrequirement-conditional';
+COMMENT ON COLUMN webauthn.relying_parties.enterprise_attestation IS 'Whether the relying party is on the allow-list for enterprise attestation, otherwise requested as direct attestation, https://www.w3.org/TR/webauthn-2/#dom-attestationconveyancepreference-enterprise';
+COMMENT ON COLUMN webauthn.relying_parties.role_name IS 'The role allowed to access the rows of the relying party once row level security is enabled by webauthn.revoke_public_access(), including members of the role';
+
+--
+-- Once row level security is enabled by webauthn.revoke_public_access(), a role only sees the relying parties
+-- it's a member of the role_name of, and in the other tables only the rows of those relying parties.
+--
+CREATE POLICY relying_party_role ON webauthn.relying_parties USING (EXISTS (SELECT FROM pg_catalog.pg_roles WHERE pg_roles.rolname = relying_parties.role_name AND pg_catalog.pg_has_role(pg_roles.oid, 'MEMBER')));
@@ -0,0 +1,35 @@
+CREATE TABLE webauthn.sessions (
+token_hash bytea NOT NULL,
+signature bytea NOT NULL REFERENCES webauthn.assertions,
+credential_id bytea NOT NULL REFERENCES webauthn.credentials,
+user_id bytea NOT NULL,
+created_at timestamptz NOT NULL,
+expires_at timestamptz NOT NULL,
+idle_timeout interval NOT NULL,
+last_used_at timestamptz NOT NULL,
+revoked_at timestamptz,
+PRIMARY KEY (token_hash),
+UNIQUE (signature),
+CONSTRAINT expires_after_created CHECK (expires_at > created_at),
+CONSTRAINT positive_idle_timeout CHECK (idle_timeout > '0'::interval),
+CONSTRAINT revoked_after_created CHECK (revoked_at >= created_at)
+);
+
+CREATE INDEX ON webauthn.sessions (user_id);
+CREATE INDEX ON webauthn.sessions (credential_id);
+
+SELECT pg_catalog.pg_extension_config_dump('sessions', '');
+
+COMMENT ON TABLE webauthn.sessions IS 'Used by webauthn.create_session() to store sessions issued for verified assertions.';
+
+COMMENT ON COLUMN webauthn.sessions.token_hash IS 'SHA-256 hash of the session token, which is only returned by webauthn.create_session()';
+COMMENT ON COLUMN webauthn.sessions.signature IS 'The signature of the verified assertion the session was issued for, https://www.w3.org/TR/webauthn-2/#assertion-signature';
+COMMENT ON COLUMN webauthn.sessions.credential_id IS 'https://www.w3.org/TR/webauthn-2/#credential-id';
+COMMENT ON COLUMN webauthn.sessions.user_id IS 'https://www.w3.org/TR/webauthn-2/#dom-publickeycredentialuserentity-id';
+COMMENT ON COLUMN webauthn.sessions.created_at IS 'Timestamp of when the session was created by webauthn.create_session()';
+COMMENT ON COLUMN webauthn.sessions.expires_at IS 'Timestamp after which webauthn.validate_session() no longer accepts the session';
+COMMENT ON COLUMN webauthn.sessions.idle_timeout IS 'Maximum time between uses of the session in webauthn.validate_session()';
+COMMENT ON COLUMN webauthn.sessions.last_used_at IS 'Timestamp of when the session was created or last validated by webauthn.validate_session()';
+COMMENT ON COLUMN webauthn.sessions.revoked_at IS 'Timestamp of when the session was revoked by webauthn.revoke_sessions() or webauthn.revoke_credential()';
+
+CREATE POLICY relying_party_role ON webauthn.sessions USING (credential_id IN (SELECT credentials.credential_id FROM webauthn.credentials));
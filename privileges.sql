@@ -0,0 +1,5 @@
+--
+-- The API functions are SECURITY DEFINER, so none of the functions are executable by PUBLIC,
+-- only by the owner and the roles granted access by webauthn.grant_api_access().
+--
+REVOKE EXECUTE ON ALL FUNCTIONS IN SCHEMA webauthn FROM PUBLIC;
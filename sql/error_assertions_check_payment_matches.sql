@@ -0,0 +1,48 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT jsonb_pretty(webauthn.init_credential(
+  challenge := '\x4a3b2c1d00112233445566778899aabbccddeeff00112233445566778899aabb'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'ACME Bank',
+  relying_party_id := 'example.com',
+  user_verification := 'required',
+  timeout := '00:05:00',
+  challenge_at := '2020-12-15 08:30:09.384246+01',
+  extensions := '{"payment": {"isPayment": true}}'
+));
+
+SELECT * FROM webauthn.store_credential(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  attestation_object := 'o2NmbXRkbm9uZWdhdHRTdG10oGhhdXRoRGF0YViko3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUdFAAAAAAAAAAAAAAAAAAAAAAAAAAAAILIau3UQ9NvR6RD3Ey3c0CMaYwL86ZNx7fFkvZFbEoXlpQECAyYgASFYILI2Cb0pWWF1EWBLVQUEctgthL7qpqk3CjSBovX_RQ8SIlggfP0IUdxWJ_C1CyEb8PZr4AQxKQVG8aAMDTMhWqPCWI8',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uY3JlYXRlIiwiY2hhbGxlbmdlIjoiU2pzc0hRQVJJak5FVldaM2lKbXF1OHpkN3Y4QUVTSXpSRlZtZDRpWnFycyIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  credential_at := '2020-12-15 08:30:12.395851+01'
+);
+
+SELECT jsonb_pretty(webauthn.get_payment_credentials(
+  challenge := '\x0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  relying_party_id := 'example.com',
+  instrument := '{"displayName": "ACME Card ****1234", "icon": "https://example.com/card.png"}',
+  total := '{"currency": "EUR", "value": "15.00"}',
+  payee_origin := 'https://merchant.example',
+  user_verification := 'required',
+  timeout := '00:05:00',
+  challenge_at := '2020-12-15 08:30:13.733084+01'
+));
+
+SELECT * FROM webauthn.verify_assertion(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  authenticator_data := 'o3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUcFAAAAAQ',
+  client_data_json := 'eyJ0eXBlIjoicGF5bWVudC5nZXQiLCJjaGFsbGVuZ2UiOiJEeDR0UEV0YWFYaUhscVcwdzlMaDhBOGVMVHhMV21sNGg1YWx0TVBTNGZBIiwib3JpZ2luIjoiaHR0cHM6Ly9leGFtcGxlLmNvbSIsImNyb3NzT3JpZ2luIjpmYWxzZSwicGF5bWVudCI6eyJycElkIjoiZXhhbXBsZS5jb20iLCJ0b3BPcmlnaW4iOiJodHRwczovL21lcmNoYW50LmV4YW1wbGUiLCJwYXllZU9yaWdpbiI6Imh0dHBzOi8vbWVyY2hhbnQuZXhhbXBsZSIsInRvdGFsIjp7InZhbHVlIjoiMTUwMC4wMCIsImN1cnJlbmN5IjoiRVVSIn0sImluc3RydW1lbnQiOnsiaWNvbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20vY2FyZC5wbmciLCJkaXNwbGF5TmFtZSI6IkFDTUUgQ2FyZCAqKioqMTIzNCJ9fX0',
+  signature := 'MEUCIAifKcF71wbu1iyTKG8qEOdRmMJwL7kX2IuyR8HxCDO9AiEA44V4Kb5_N1N7aHxJEUmKeHYzv_hs00lh_Z8b8nU9aBI',
+  user_handle := NULL,
+  verified_at := '2020-12-15 08:30:14.679551+01'
+);
+
+ROLLBACK;
@@ -0,0 +1,37 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT webauthn.base64url_encode(webauthn.import_u2f_credential(
+  key_handle := 'Cl6bn1QVWgf-IQ-CQb7mI_lwQ7UPn2OOUPAqYEiA46QRLo0_UYwKbzf8kAujzYbU0g63b7EaNEiOsv41OcAvuw',
+  public_key := 'BMLN5ZpLwGLSJ5KsvkFpv10yXyPTUdsomqEPVeFUUAqvFFgFwqyuV9IeIAV73VRuVDfEMMRBBWQZE8i4li7SHww',
+  appid := 'https://example.com/u2f/app-id.json',
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'ACME Corporation',
+  relying_party_id := 'example.com',
+  sign_count := 7,
+  imported_at := '2020-12-15 08:30:09.384246+01'
+)) AS credential_id;
+
+SELECT jsonb_pretty(webauthn.get_credentials(
+  challenge := '\x5a1e1d4b7d1bd0f1a0ad5f0e33b27bc3fb3b2d0b0e6ac8fe5d7e1b0f6d3f7a21'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_verification := 'discouraged',
+  timeout := '00:05:00',
+  relying_party_id := 'example.com',
+  challenge_at := '2020-12-15 08:30:13.733084+01'
+));
+
+SELECT * FROM webauthn.verify_assertion(
+  credential_id := 'Cl6bn1QVWgf-IQ-CQb7mI_lwQ7UPn2OOUPAqYEiA46QRLo0_UYwKbzf8kAujzYbU0g63b7EaNEiOsv41OcAvuw',
+  credential_type := 'public-key',
+  authenticator_data := 'euGntQifo6qE-qzZwt4TE1yU7nhY6xParT4IYoM5OKsBAAAACQ',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uZ2V0IiwiY2hhbGxlbmdlIjoiV2g0ZFMzMGIwUEdnclY4T003Sjd3X3M3TFFzT2Fzai1YWDRiRDIwX2VpRSIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  signature := 'MEUCIQDst8GlKtIQyZ6h3dTRMpCWMozI16rIaHNmvegVEgDgFgIgK8vOQ2omiw-ItN3TfwGc_MdDZPSHK8IckibWjbeIVVk',
+  user_handle := NULL,
+  verified_at := '2020-12-15 08:30:14.679551+01'
+);
+
+ROLLBACK;
@@ -0,0 +1,54 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT decode(cbor.to_jsonb(
+  cbor := webauthn.base64url_decode('o2NmbXRkbm9uZWdhdHRTdG10oGhhdXRoRGF0YVjESZYN5YgOjGh0NBcPZHZgW4_krrmihjLHmVzzuoMdl2NBAAAAAAAAAAAAAAAAAAAAAAAAAAAAQEzL3PXIEOEtx-PfEHs9opUHZA0tNrNLEe8F8omiq1KYV0ETaoj9aX86AT7BHsvMIxw1F4fwPvdc6j_x9G5HqISlAQIDJiABIVggf6kt0GZu7nwT3be2JJsMj5-6Q2CFfE4V0vxjSitaH48iWCDbmYOzGUadNecZo7k-GsKShUzT_yrVCJhoGwoy_7y8ag'),
+  encode_binary_format := 'base64'
+)->>'authData','base64') AS authenticator_data \gset
+
+SELECT length(:'authenticator_data'::bytea), * FROM webauthn.parse_authenticator_data(:'authenticator_data'::bytea);
+
+SELECT * FROM webauthn.parse_authenticator_data(webauthn.base64url_decode('SZYN5YgOjGh0NBcPZHZgW4_krrmihjLHmVzzuoMdl2MBAAAAAQ'));
+
+SAVEPOINT too_short;
+SELECT * FROM webauthn.parse_authenticator_data(substring(:'authenticator_data'::bytea,1,36));
+ROLLBACK TO too_short;
+
+SAVEPOINT attested_credential_data_too_short;
+SELECT * FROM webauthn.parse_authenticator_data(substring(:'authenticator_data'::bytea,1,54));
+ROLLBACK TO attested_credential_data_too_short;
+
+SAVEPOINT credential_id_length_out_of_range;
+SELECT * FROM webauthn.parse_authenticator_data(overlay(:'authenticator_data'::bytea placing '\xffff' from 54 for 2));
+ROLLBACK TO credential_id_length_out_of_range;
+
+SAVEPOINT truncated_credential_public_key;
+SELECT * FROM webauthn.parse_authenticator_data(substring(:'authenticator_data'::bytea,1,length(:'authenticator_data'::bytea)-1));
+ROLLBACK TO truncated_credential_public_key;
+
+SAVEPOINT credential_public_key_not_a_map;
+SELECT * FROM webauthn.parse_authenticator_data(set_byte(:'authenticator_data'::bytea,119,1));
+ROLLBACK TO credential_public_key_not_a_map;
+
+SAVEPOINT trailing_bytes;
+SELECT * FROM webauthn.parse_authenticator_data(:'authenticator_data'::bytea || '\x00'::bytea);
+ROLLBACK TO trailing_bytes;
+
+SAVEPOINT attested_credential_data_without_flag;
+SELECT * FROM webauthn.parse_authenticator_data(set_byte(:'authenticator_data'::bytea,32,1));
+ROLLBACK TO attested_credential_data_without_flag;
+
+SAVEPOINT extension_data_flag_without_extensions;
+SELECT * FROM webauthn.parse_authenticator_data(set_byte(:'authenticator_data'::bytea,32,193));
+ROLLBACK TO extension_data_flag_without_extensions;
+
+SAVEPOINT truncated_extensions;
+SELECT * FROM webauthn.parse_authenticator_data(set_byte(:'authenticator_data'::bytea,32,193) || '\xa1'::bytea);
+ROLLBACK TO truncated_extensions;
+
+SAVEPOINT extensions_not_a_map;
+SELECT * FROM webauthn.parse_authenticator_data(set_byte(:'authenticator_data'::bytea,32,193) || '\x01'::bytea);
+ROLLBACK TO extensions_not_a_map;
+
+ROLLBACK;
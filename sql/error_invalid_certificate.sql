@@ -0,0 +1,39 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT webauthn.base64url_decode('MIICLTCCAdSgAwIBAgIJAJ86J8ThuF0GMAoGCCqGSM49BAMCMHIxCzAJBgNVBAYTAlNFMRkwFwYDVQQKDBBBQ01FIENvcnBvcmF0aW9uMSIwIAYDVQQLDBlBdXRoZW50aWNhdG9yIEF0dGVzdGF0aW9uMSQwIgYDVQQDDBtBQ01FIEVudGVycHJpc2UgQXR0ZXN0YXRpb24wHhcNMjYxMDE5MDcxODQyWhcNMzYxMDE2MDcxODQyWjByMQswCQYDVQQGEwJTRTEZMBcGA1UECgwQQUNNRSBDb3Jwb3JhdGlvbjEiMCAGA1UECwwZQXV0aGVudGljYXRvciBBdHRlc3RhdGlvbjEkMCIGA1UEAwwbQUNNRSBFbnRlcnByaXNlIEF0dGVzdGF0aW9uMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE7PvhbjZucRnwVCwj1mZcx4_dKMzxtdX_Bvo7ub7wsau0uIensf1RsNHhLZEaUb5qEfUW467-K0fsSkztliQ686NTMFEwHQYDVR0OBBYEFFbGKTySWEOIBwRsHcGiiyjSFhWeMB8GA1UdIwQYMBaAFFbGKTySWEOIBwRsHcGiiyjSFhWeMA8GA1UdEwEB_wQFMAMBAf8wCgYIKoZIzj0EAwIDRwAwRAIgdoP9E9ycLsIeLQUkr1yl74FO6TpX3hdLCmBr4QJbzdICIFWz0ZEYqbCzutpUJtqx7Ux8O29lvvLUu00TIhIEDqtI') AS certificate \gset
+
+SELECT encode(webauthn.certificate_serial_number(:'certificate'::bytea),'hex');
+
+SELECT encode(webauthn.certificate_serial_number('\x300730050203010203'::bytea),'hex') AS v1_certificate;
+
+SAVEPOINT truncated;
+SELECT webauthn.certificate_serial_number(substring(:'certificate'::bytea,1,100));
+ROLLBACK TO truncated;
+
+SAVEPOINT trailing_bytes;
+SELECT webauthn.certificate_serial_number(:'certificate'::bytea || '\x00'::bytea);
+ROLLBACK TO trailing_bytes;
+
+SAVEPOINT unexpected_sequence_tag;
+SELECT webauthn.certificate_serial_number(set_byte(:'certificate'::bytea,0,49));
+ROLLBACK TO unexpected_sequence_tag;
+
+SAVEPOINT unexpected_integer_tag;
+SELECT webauthn.certificate_serial_number(set_byte(:'certificate'::bytea,13,4));
+ROLLBACK TO unexpected_integer_tag;
+
+SAVEPOINT indefinite_length;
+SELECT webauthn.certificate_serial_number(set_byte(:'certificate'::bytea,1,128));
+ROLLBACK TO indefinite_length;
+
+SAVEPOINT length_not_minimal;
+SELECT webauthn.certificate_serial_number('\x308103020101'::bytea);
+ROLLBACK TO length_not_minimal;
+
+SAVEPOINT empty_serial_number;
+SELECT webauthn.certificate_serial_number('\x300430020200'::bytea);
+ROLLBACK TO empty_serial_number;
+
+ROLLBACK;
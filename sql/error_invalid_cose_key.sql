@@ -0,0 +1,58 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT credential_public_key AS cose_key FROM webauthn.parse_attested_credential_data(decode(cbor.to_jsonb(
+  cbor := webauthn.base64url_decode('o2NmbXRkbm9uZWdhdHRTdG10oGhhdXRoRGF0YVjESZYN5YgOjGh0NBcPZHZgW4_krrmihjLHmVzzuoMdl2NBAAAAAAAAAAAAAAAAAAAAAAAAAAAAQEzL3PXIEOEtx-PfEHs9opUHZA0tNrNLEe8F8omiq1KYV0ETaoj9aX86AT7BHsvMIxw1F4fwPvdc6j_x9G5HqISlAQIDJiABIVggf6kt0GZu7nwT3be2JJsMj5-6Q2CFfE4V0vxjSitaH48iWCDbmYOzGUadNecZo7k-GsKShUzT_yrVCJhoGwoy_7y8ag'),
+  encode_binary_format := 'base64'
+)->>'authData','base64')) \gset
+
+SELECT * FROM webauthn.parse_cose_key(:'cose_key'::bytea);
+
+-- The well-formed base points of P-384 and P-521, and an Ed25519 key, are refused since only ES256 is supported
+SAVEPOINT es384;
+SELECT * FROM webauthn.parse_cose_key('\xa5010203382220022158' || '\x30aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a385502f25dbf55296c3a545e3872760ab7'::bytea || '\x2258' || '\x303617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c00a60b1ce1d7e819d7a431d7c90ea0e5f'::bytea);
+ROLLBACK TO es384;
+
+SAVEPOINT es512;
+SELECT * FROM webauthn.parse_cose_key('\xa5010203382320032158' || '\x4200c6858e06b70404e9cd9e3ecb662395b4429c648139053fb521f828af606b4d3dbaa14b5e77efe75928fe1dc127a2ffa8de3348b3c1856a429bf97e7e31c2e5bd66'::bytea || '\x2258' || '\x42011839296a789a3bc0045c8a5fb42c7d1bd998f54449579b446817afbd17273e662c97ee72995ef42640c550b9013fad0761353c7086a272c24088be94769fd16650'::bytea);
+ROLLBACK TO es512;
+
+SAVEPOINT ed25519;
+SELECT * FROM webauthn.parse_cose_key('\xa401010327200621' || '\x5820d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a'::bytea);
+ROLLBACK TO ed25519;
+
+-- A P-384 point that's not on the curve is still reported as such
+SAVEPOINT es384_not_on_curve;
+SELECT * FROM webauthn.parse_cose_key('\xa5010203382220022158' || '\x30aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a385502f25dbf55296c3a545e3872760ab7'::bytea || '\x2258' || '\x303617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c00a60b1ce1d7e819d7a431d7c90ea0e5e'::bytea);
+ROLLBACK TO es384_not_on_curve;
+
+SAVEPOINT not_a_map;
+SELECT * FROM webauthn.parse_cose_key('\x80'::bytea);
+ROLLBACK TO not_a_map;
+
+SAVEPOINT kty_missing;
+SELECT * FROM webauthn.parse_cose_key(overlay(:'cose_key'::bytea placing '\xa4' from 1 for 3));
+ROLLBACK TO kty_missing;
+
+SAVEPOINT unsupported_algorithm;
+SELECT * FROM webauthn.parse_cose_key(overlay(:'cose_key'::bytea placing '\x03390100' from 4 for 2));
+ROLLBACK TO unsupported_algorithm;
+
+SAVEPOINT ec2_key_with_eddsa;
+SELECT * FROM webauthn.parse_cose_key(set_byte(:'cose_key'::bytea,4,39));
+ROLLBACK TO ec2_key_with_eddsa;
+
+SAVEPOINT curve_inconsistent_with_algorithm;
+SELECT * FROM webauthn.parse_cose_key(set_byte(:'cose_key'::bytea,6,2));
+ROLLBACK TO curve_inconsistent_with_algorithm;
+
+SAVEPOINT short_coordinate;
+SELECT * FROM webauthn.parse_cose_key(overlay(:'cose_key'::bytea placing '\x581f' from 9 for 3));
+ROLLBACK TO short_coordinate;
+
+SAVEPOINT point_not_on_curve;
+SELECT * FROM webauthn.parse_cose_key(set_byte(:'cose_key'::bytea,76,get_byte(:'cose_key'::bytea,76) # 1));
+ROLLBACK TO point_not_on_curve;
+
+ROLLBACK;
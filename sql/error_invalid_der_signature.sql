@@ -0,0 +1,65 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT webauthn.base64url_decode('MEQCIBD6sBMH8-7Vm8EWASZe-qtSS1DQF72c3-7E9hsByqjWAiBpxun42by9uk5UeMt1sIQzLVGwviwhcBsVfHyHq7mAVw') AS signature \gset
+
+SELECT webauthn.decode_asn1_der_signature(:'signature'::bytea);
+
+SELECT webauthn.decode_asn1_der_signature('\x3008020200800202008f'::bytea, 'secp384r1');
+
+SELECT webauthn.decode_asn1_der_signature('\x3081880242'::bytea || decode(repeat('01',66),'hex') || '\x0242'::bytea || decode(repeat('01',66),'hex'), 'secp521r1');
+
+SAVEPOINT unsupported_curve;
+SELECT webauthn.decode_asn1_der_signature(:'signature'::bytea, 'secp256k1');
+ROLLBACK TO unsupported_curve;
+
+SAVEPOINT truncated;
+SELECT webauthn.decode_asn1_der_signature(substring(:'signature'::bytea,1,69));
+ROLLBACK TO truncated;
+
+SAVEPOINT unexpected_sequence_tag;
+SELECT webauthn.decode_asn1_der_signature(set_byte(:'signature'::bytea,0,49));
+ROLLBACK TO unexpected_sequence_tag;
+
+SAVEPOINT unexpected_integer_tag;
+SELECT webauthn.decode_asn1_der_signature(set_byte(:'signature'::bytea,36,3));
+ROLLBACK TO unexpected_integer_tag;
+
+SAVEPOINT indefinite_length;
+SELECT webauthn.decode_asn1_der_signature(set_byte(:'signature'::bytea,1,128));
+ROLLBACK TO indefinite_length;
+
+SAVEPOINT length_not_minimally_encoded;
+SELECT webauthn.decode_asn1_der_signature(overlay(:'signature'::bytea placing '\x308144' from 1 for 2));
+ROLLBACK TO length_not_minimally_encoded;
+
+SAVEPOINT trailing_bytes;
+SELECT webauthn.decode_asn1_der_signature(:'signature'::bytea || '\x00'::bytea);
+ROLLBACK TO trailing_bytes;
+
+SAVEPOINT trailing_integer;
+SELECT webauthn.decode_asn1_der_signature('\x3009020101020101020101'::bytea);
+ROLLBACK TO trailing_integer;
+
+SAVEPOINT empty_integer;
+SELECT webauthn.decode_asn1_der_signature('\x30050200020101'::bytea);
+ROLLBACK TO empty_integer;
+
+SAVEPOINT negative_integer;
+SELECT webauthn.decode_asn1_der_signature(set_byte(:'signature'::bytea,4,144));
+ROLLBACK TO negative_integer;
+
+SAVEPOINT integer_not_minimally_encoded;
+SELECT webauthn.decode_asn1_der_signature('\x30080203000001020101'::bytea);
+ROLLBACK TO integer_not_minimally_encoded;
+
+SAVEPOINT zero_integer;
+SELECT webauthn.decode_asn1_der_signature('\x3006020100020101'::bytea);
+ROLLBACK TO zero_integer;
+
+SAVEPOINT integer_too_long;
+SELECT webauthn.decode_asn1_der_signature('\x30260221'::bytea || decode(repeat('01',33),'hex') || '\x020101'::bytea);
+ROLLBACK TO integer_too_long;
+
+ROLLBACK;
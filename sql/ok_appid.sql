@@ -0,0 +1,74 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT webauthn.base64url_encode(webauthn.import_u2f_credential(
+  key_handle := 'Cl6bn1QVWgf-IQ-CQb7mI_lwQ7UPn2OOUPAqYEiA46QRLo0_UYwKbzf8kAujzYbU0g63b7EaNEiOsv41OcAvuw',
+  public_key := 'BMLN5ZpLwGLSJ5KsvkFpv10yXyPTUdsomqEPVeFUUAqvFFgFwqyuV9IeIAV73VRuVDfEMMRBBWQZE8i4li7SHww',
+  appid := 'https://example.com/u2f/app-id.json',
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'ACME Corporation',
+  relying_party_id := 'example.com',
+  sign_count := 7,
+  imported_at := '2020-12-15 08:30:09.384246+01'
+)) AS credential_id;
+
+-- Another AppID for the same user and relying party could never be used, since only one is requested
+SAVEPOINT conflicting_appid;
+SELECT webauthn.import_u2f_credential(
+  key_handle := 'Zm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFy',
+  public_key := 'BMLN5ZpLwGLSJ5KsvkFpv10yXyPTUdsomqEPVeFUUAqvFFgFwqyuV9IeIAV73VRuVDfEMMRBBWQZE8i4li7SHww',
+  appid := 'https://login.example.com/u2f/app-id.json',
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'ACME Corporation',
+  relying_party_id := 'example.com',
+  imported_at := '2020-12-15 08:30:10+01'
+);
+ROLLBACK TO conflicting_appid;
+
+-- U2F counters are unsigned 32-bit integers
+SAVEPOINT large_sign_count;
+SELECT webauthn.base64url_encode(webauthn.import_u2f_credential(
+  key_handle := 'Zm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFyZm9vYmFy',
+  public_key := 'BMLN5ZpLwGLSJ5KsvkFpv10yXyPTUdsomqEPVeFUUAqvFFgFwqyuV9IeIAV73VRuVDfEMMRBBWQZE8i4li7SHww',
+  appid := 'https://example.com/u2f/app-id.json',
+  user_name := 'bob@example.com',
+  user_id := '\x0b0b'::bytea,
+  user_display_name := 'Bob',
+  relying_party_name := 'ACME Corporation',
+  relying_party_id := 'example.com',
+  sign_count := 4294967295,
+  imported_at := '2020-12-15 08:30:10+01'
+)) AS credential_id;
+SELECT user_name, sign_count FROM webauthn.credentials WHERE user_name = 'bob@example.com';
+ROLLBACK TO large_sign_count;
+
+SELECT appid, rp_id_hash = public.digest(appid,'sha256') AS rp_id_hash_appid, sign_count, origin, encode(public_key,'hex') AS public_key FROM webauthn.credentials;
+
+SELECT jsonb_pretty(webauthn.get_credentials(
+  challenge := '\x9f0d2cbb7d4cb1b44e15e8fa6d5bc5bd3a3e0e1d4a95c76b1d0d0bb2a0ba1f3e'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_verification := 'discouraged',
+  timeout := '00:05:00',
+  relying_party_id := 'example.com',
+  challenge_at := '2020-12-15 08:30:13.733084+01'
+));
+
+SELECT * FROM webauthn.verify_assertion(
+  credential_id := 'Cl6bn1QVWgf-IQ-CQb7mI_lwQ7UPn2OOUPAqYEiA46QRLo0_UYwKbzf8kAujzYbU0g63b7EaNEiOsv41OcAvuw',
+  credential_type := 'public-key',
+  authenticator_data := 'euGntQifo6qE-qzZwt4TE1yU7nhY6xParT4IYoM5OKsBAAAACA',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uZ2V0IiwiY2hhbGxlbmdlIjoibncwc3UzMU1zYlJPRmVqNmJWdkZ2VG8tRGgxS2xjZHJIUTBMc3FDNkh6NCIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  signature := 'MEUCIQD7QuT0il6kb9FwyB-mTq39yCAYfkeggdWffsYUAM1KpgIgc16hgXdc452w4wZYqKkKYygwNV2Ahq1qVCfF30Evfwc',
+  user_handle := NULL,
+  verified_at := '2020-12-15 08:30:14.679551+01',
+  client_extension_results := '{"appid": true}'
+);
+
+SELECT sign_count, client_extension_results FROM webauthn.assertions;
+
+ROLLBACK;
@@ -0,0 +1,60 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name, enterprise_attestation)
+VALUES ('localhost', 'ACME Corporation', TRUE);
+
+--
+-- Enterprise attestation is replaced by direct attestation for relying parties not on the allow-list.
+--
+SELECT webauthn.init_credential(
+  challenge := '\x6a19f4c245388de79290f5338196c51e19fc33273afb1891d4e90296bfe06d0b'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'Example Tenant',
+  relying_party_id := 'example.com',
+  user_verification := 'discouraged',
+  attestation := 'enterprise',
+  timeout := '00:05:00',
+  challenge_at := '2020-12-15 08:30:08.384246+01'
+)->'publicKey'->'attestation';
+
+SELECT jsonb_pretty(webauthn.init_credential(
+  challenge := '\xd4ef72bc4cd34733abb91602e4aa5cc4d446fae92aa3dbcf9e2c2052a5fc9857'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'ACME Corporation',
+  relying_party_id := 'localhost',
+  user_verification := 'discouraged',
+  attestation := 'enterprise',
+  timeout := '00:05:00',
+  challenge_at := '2020-12-15 08:30:09.384246+01'
+));
+
+--
+-- A packed attestation with the enterprise attestation certificate in x5c.
+--
+SELECT * FROM webauthn.store_credential(
+  credential_id := 'TMvc9cgQ4S3H498Qez2ilQdkDS02s0sR7wXyiaKrUphXQRNqiP1pfzoBPsEey8wjHDUXh_A-91zqP_H0bkeohA',
+  credential_type := 'public-key',
+  attestation_object := 'o2NmbXRmcGFja2VkZ2F0dFN0bXSjY2FsZyZjc2lnWEcwRQIhAKK_legY-isyapwHHJIYRH6dzkZppiPP92NvS2ZRE4paAiBGDQp84braDi1uAUm_EybDx_VSR-Bp4g0gM2xf_s6apmN4NWOBWQIxMIICLTCCAdSgAwIBAgIJAJ86J8ThuF0GMAoGCCqGSM49BAMCMHIxCzAJBgNVBAYTAlNFMRkwFwYDVQQKDBBBQ01FIENvcnBvcmF0aW9uMSIwIAYDVQQLDBlBdXRoZW50aWNhdG9yIEF0dGVzdGF0aW9uMSQwIgYDVQQDDBtBQ01FIEVudGVycHJpc2UgQXR0ZXN0YXRpb24wHhcNMjYxMDE5MDcxODQyWhcNMzYxMDE2MDcxODQyWjByMQswCQYDVQQGEwJTRTEZMBcGA1UECgwQQUNNRSBDb3Jwb3JhdGlvbjEiMCAGA1UECwwZQXV0aGVudGljYXRvciBBdHRlc3RhdGlvbjEkMCIGA1UEAwwbQUNNRSBFbnRlcnByaXNlIEF0dGVzdGF0aW9uMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE7PvhbjZucRnwVCwj1mZcx4_dKMzxtdX_Bvo7ub7wsau0uIensf1RsNHhLZEaUb5qEfUW467-K0fsSkztliQ686NTMFEwHQYDVR0OBBYEFFbGKTySWEOIBwRsHcGiiyjSFhWeMB8GA1UdIwQYMBaAFFbGKTySWEOIBwRsHcGiiyjSFhWeMA8GA1UdEwEB_wQFMAMBAf8wCgYIKoZIzj0EAwIDRwAwRAIgdoP9E9ycLsIeLQUkr1yl74FO6TpX3hdLCmBr4QJbzdICIFWz0ZEYqbCzutpUJtqx7Ux8O29lvvLUu00TIhIEDqtIaGF1dGhEYXRhWMRJlg3liA6MaHQ0Fw9kdmBbj-SuuaKGMseZXPO6gx2XY0EAAAAAAAAAAAAAAAAAAAAAAAAAAABATMvc9cgQ4S3H498Qez2ilQdkDS02s0sR7wXyiaKrUphXQRNqiP1pfzoBPsEey8wjHDUXh_A-91zqP_H0bkeohKUBAgMmIAEhWCB_qS3QZm7ufBPdt7YkmwyPn7pDYIV8ThXS_GNKK1ofjyJYINuZg7MZRp015xmjuT4awpKFTNP_KtUImGgbCjL_vLxq',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uY3JlYXRlIiwiY2hhbGxlbmdlIjoiMU85eXZFelRSek9ydVJZQzVLcGN4TlJHLXVrcW85dlBuaXdnVXFYOG1GYyIsIm9yaWdpbiI6Imh0dHA6Ly9sb2NhbGhvc3QiLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  credential_at := '2020-12-15 08:30:12.395851+01'
+);
+
+SELECT encode(attestation_certificate_serial,'hex') FROM webauthn.credentials;
+
+-- The serial number is kept when the credential is updated after the relying party left the allow-list
+UPDATE webauthn.relying_parties SET enterprise_attestation = FALSE;
+
+SELECT * FROM webauthn.revoke_credential(
+  credential_id := 'TMvc9cgQ4S3H498Qez2ilQdkDS02s0sR7wXyiaKrUphXQRNqiP1pfzoBPsEey8wjHDUXh_A-91zqP_H0bkeohA',
+  revoked_at := '2020-12-15 08:30:13+01'
+);
+
+SELECT encode(attestation_certificate_serial,'hex') FROM webauthn.credentials;
+
+ROLLBACK;
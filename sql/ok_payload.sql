@@ -0,0 +1,64 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT jsonb_pretty(webauthn.init_credential(
+  challenge := '\x4a3b2c1d00112233445566778899aabbccddeeff00112233445566778899aabb'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'ACME Bank',
+  relying_party_id := 'example.com',
+  user_verification := 'required',
+  timeout := '00:05:00',
+  challenge_at := '2020-12-15 08:30:09.384246+01'
+));
+
+SELECT * FROM webauthn.store_credential(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  attestation_object := 'o2NmbXRkbm9uZWdhdHRTdG10oGhhdXRoRGF0YViko3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUdFAAAAAAAAAAAAAAAAAAAAAAAAAAAAILIau3UQ9NvR6RD3Ey3c0CMaYwL86ZNx7fFkvZFbEoXlpQECAyYgASFYILI2Cb0pWWF1EWBLVQUEctgthL7qpqk3CjSBovX_RQ8SIlggfP0IUdxWJ_C1CyEb8PZr4AQxKQVG8aAMDTMhWqPCWI8',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uY3JlYXRlIiwiY2hhbGxlbmdlIjoiU2pzc0hRQVJJak5FVldaM2lKbXF1OHpkN3Y4QUVTSXpSRlZtZDRpWnFycyIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  credential_at := '2020-12-15 08:30:12.395851+01'
+);
+
+SELECT jsonb_pretty(webauthn.get_credentials(
+  challenge := '\x7c1f0e2d3b4a59687786a5b4c3d2e1f07c1f0e2d3b4a59687786a5b4c3d2e1f0'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_verification := 'required',
+  timeout := '00:05:00',
+  relying_party_id := 'example.com',
+  challenge_at := '2020-12-15 08:30:13.733084+01',
+  payload := '{"action": "transfer", "amount": {"currency": "EUR", "value": "500.00"}, "recipient": "DE89370400440532013000"}'
+));
+
+SELECT
+  encode(challenge,'hex') AS challenge,
+  challenge = public.digest(nonce || convert_to(webauthn.canonical_json(payload),'UTF8'),'sha256') AS commits_to_payload
+FROM webauthn.assertion_challenges;
+
+SELECT webauthn.canonical_json(payload) FROM webauthn.assertion_challenges;
+
+-- https://www.rfc-editor.org/rfc/rfc8785#section-3.2.2
+SELECT webauthn.canonical_json($${"numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001], "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/", "literals": [null, true, false]}$$);
+
+-- https://www.rfc-editor.org/rfc/rfc8785#section-3.2.3
+SELECT webauthn.canonical_json($${"\u20ac": "Euro Sign", "\r": "Carriage Return", "\ufb33": "Hebrew Letter Dalet With Dagesh", "1": "One", "\ud83d\ude00": "Emoji: Grinning Face", "\u0080": "Control", "\u00f6": "Latin Small Letter O With Diaeresis"}$$);
+
+-- https://www.rfc-editor.org/rfc/rfc8785#appendix-B
+SELECT number, webauthn.canonical_json(to_jsonb(number)) FROM unnest(ARRAY[
+  '-0', '5e-324', '-5e-324', '1.7976931348623157e308', '9007199254740992', '295147905179352830000',
+  '1e+21', '1e+23', '0.000001', '1e-7', '333333333.3333333', '4.5', '0.002', '1e-27', '1.2e-3', '100'
+]::float8[]) AS number;
+
+SELECT * FROM webauthn.verify_assertion_payload(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  authenticator_data := 'o3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUcFAAAAAQ',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uZ2V0IiwiY2hhbGxlbmdlIjoiVXJCTzVPd180N0hUNHNfc01IdEJzTldMdjlteklBTUo1QUtIdGNPc0h3RSIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  signature := 'MEYCIQDaC1jkzEzzvCRbV0djiNZPKvf8tPXdUzwDfsLG92ekWwIhAJLOGKm_fGSOh_MK7LZJT3qDSp3XV38qW2x2UUDmz4Co',
+  user_handle := NULL,
+  verified_at := '2020-12-15 08:30:14.679551+01'
+);
+
+ROLLBACK;
@@ -0,0 +1,168 @@
+BEGIN;
+
+CREATE EXTENSION IF NOT EXISTS webauthn CASCADE;
+
+SELECT jsonb_pretty(webauthn.init_credential(
+  challenge := '\x4a3b2c1d00112233445566778899aabbccddeeff00112233445566778899aabb'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  user_display_name := 'Alex P. Müller',
+  relying_party_name := 'ACME Bank',
+  relying_party_id := 'example.com',
+  user_verification := 'required',
+  timeout := '00:05:00',
+  challenge_at := '2020-12-15 08:30:09.384246+01'
+));
+
+SELECT * FROM webauthn.store_credential(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  attestation_object := 'o2NmbXRkbm9uZWdhdHRTdG10oGhhdXRoRGF0YViko3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUdFAAAAAAAAAAAAAAAAAAAAAAAAAAAAILIau3UQ9NvR6RD3Ey3c0CMaYwL86ZNx7fFkvZFbEoXlpQECAyYgASFYILI2Cb0pWWF1EWBLVQUEctgthL7qpqk3CjSBovX_RQ8SIlggfP0IUdxWJ_C1CyEb8PZr4AQxKQVG8aAMDTMhWqPCWI8',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uY3JlYXRlIiwiY2hhbGxlbmdlIjoiU2pzc0hRQVJJak5FVldaM2lKbXF1OHpkN3Y4QUVTSXpSRlZtZDRpWnFycyIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  credential_at := '2020-12-15 08:30:12.395851+01'
+);
+
+SELECT jsonb_typeof(webauthn.get_credentials(
+  challenge := '\x1b2c3d4e5f60718293a4b5c6d7e8f9011b2c3d4e5f60718293a4b5c6d7e8f901'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_verification := 'required',
+  relying_party_id := 'example.com',
+  challenge_at := '2020-12-15 08:30:13+01'
+)) AS request_options;
+
+SELECT * FROM webauthn.verify_assertion(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  authenticator_data := 'o3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUcFAAAAAQ',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uZ2V0IiwiY2hhbGxlbmdlIjoiR3l3OVRsOWdjWUtUcExYRzEtajVBUnNzUFU1ZllIR0NrNlMxeHRmby1RRSIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  signature := 'MEQCICOI1mfX5T_BfGBBtcuS3dHUcqsArOVZRVhP8vq56b6EAiAccmE2Uxnbic8qCC3uL1z-PbTsaXAcOie0oCXnjjMDSw',
+  user_handle := NULL,
+  verified_at := '2020-12-15 08:30:14+01'
+);
+
+SELECT webauthn.create_session(
+  signature := 'MEQCICOI1mfX5T_BfGBBtcuS3dHUcqsArOVZRVhP8vq56b6EAiAccmE2Uxnbic8qCC3uL1z-PbTsaXAcOie0oCXnjjMDSw',
+  expires_in := '12 hours',
+  idle_timeout := '30 minutes',
+  created_at := '2020-12-15 08:30:15+01'
+) AS idle_token \gset
+
+SELECT length(:'idle_token') AS token_length, count(*) AS sessions, bool_and(token_hash = public.digest(webauthn.base64url_decode(:'idle_token'),'sha256')) AS token_hashed FROM webauthn.sessions;
+
+SELECT webauthn.validate_session(:'idle_token', validated_at := '2020-12-15 08:45:00+01') AS user_id;
+
+SELECT webauthn.validate_session(:'idle_token', validated_at := '2020-12-15 09:20:00+01') AS user_id;
+
+SELECT jsonb_typeof(webauthn.get_credentials(
+  challenge := '\x2c3d4e5f60718293a4b5c6d7e8f901122c3d4e5f60718293a4b5c6d7e8f90112'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_verification := 'discouraged',
+  relying_party_id := 'example.com',
+  challenge_at := '2020-12-15 09:20:00+01'
+)) AS request_options;
+
+SELECT * FROM webauthn.verify_assertion(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  authenticator_data := 'o3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUcBAAAAAg',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uZ2V0IiwiY2hhbGxlbmdlIjoiTEQxT1gyQnhncE9rdGNiWDZQa0JFaXc5VGw5Z2NZS1RwTFhHMS1qNUFSSSIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  signature := 'MEUCIQCk6Cs_w2q6gzUZdmg33w5Eak-YNMT7hJYbFtU6jujPfwIgf6lKxpLZ8wecBiihxW9vPjrowsqhbbtAcPtkeNY6oNI',
+  user_handle := NULL,
+  verified_at := '2020-12-15 09:20:01+01'
+);
+
+SELECT webauthn.create_session(
+  signature := 'MEUCIQCk6Cs_w2q6gzUZdmg33w5Eak-YNMT7hJYbFtU6jujPfwIgf6lKxpLZ8wecBiihxW9vPjrowsqhbbtAcPtkeNY6oNI',
+  expires_in := '1 hour',
+  idle_timeout := '30 minutes',
+  created_at := '2020-12-15 09:20:02+01'
+) AS expiring_token \gset
+
+SELECT webauthn.validate_session(:'expiring_token', validated_at := '2020-12-15 09:50:00+01') AS user_id;
+
+SELECT webauthn.validate_session(:'expiring_token', validated_at := '2020-12-15 10:15:00+01') AS user_id;
+
+SELECT webauthn.validate_session(:'expiring_token', validated_at := '2020-12-15 10:25:00+01') AS user_id;
+
+SELECT jsonb_typeof(webauthn.get_credentials(
+  challenge := '\x3d4e5f60718293a4b5c6d7e8f90112233d4e5f60718293a4b5c6d7e8f9011223'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_verification := 'required',
+  relying_party_id := 'example.com',
+  challenge_at := '2020-12-15 10:30:00+01'
+)) AS request_options;
+
+SELECT * FROM webauthn.verify_assertion(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  authenticator_data := 'o3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUcFAAAAAw',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uZ2V0IiwiY2hhbGxlbmdlIjoiUFU1ZllIR0NrNlMxeHRmby1RRVNJejFPWDJCeGdwT2t0Y2JYNlBrQkVpTSIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  signature := 'MEYCIQDKj3VPQWXQ9UUfLa_Chj9xen5v_-hfNyXEerWvPTVXKgIhAK_snWwOqsdf95NAlnF_wyLGTgfGn4SuMRXug14fP9JE',
+  user_handle := NULL,
+  verified_at := '2020-12-15 10:30:01+01'
+);
+
+SELECT webauthn.create_session(
+  signature := 'MEYCIQDKj3VPQWXQ9UUfLa_Chj9xen5v_-hfNyXEerWvPTVXKgIhAK_snWwOqsdf95NAlnF_wyLGTgfGn4SuMRXug14fP9JE',
+  expires_in := '12 hours',
+  idle_timeout := '30 minutes',
+  created_at := '2020-12-15 10:30:02+01'
+) AS revoked_token \gset
+
+SELECT webauthn.validate_session(:'revoked_token', validated_at := '2020-12-15 10:31:00+01') AS user_id;
+
+SELECT webauthn.revoke_sessions(
+  user_id := '\xc172e425a2e82488bda49038fd66970a94cfa9f3bfa740d421f6040cdb3cb44f57cb3326ac4d0f7e16ed9afe66499ad8ded1f9ce29db45c8e48ba989da60e163'::bytea,
+  revoked_at := '2020-12-15 10:32:00+01'
+);
+
+SELECT webauthn.validate_session(:'revoked_token', validated_at := '2020-12-15 10:33:00+01') AS user_id;
+
+SELECT jsonb_typeof(webauthn.get_credentials(
+  challenge := '\x4e5f60718293a4b5c6d7e8f90112233d4e5f60718293a4b5c6d7e8f901122334'::bytea,
+  user_name := 'alex.p.mueller@example.com',
+  user_verification := 'required',
+  relying_party_id := 'example.com',
+  challenge_at := '2020-12-15 10:34:00+01'
+)) AS request_options;
+
+SELECT * FROM webauthn.verify_assertion(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  credential_type := 'public-key',
+  authenticator_data := 'o3mm9u6vuaVeN4wRgDTidR5oL6ufLTCrE9ISVYbOGUcFAAAABA',
+  client_data_json := 'eyJ0eXBlIjoid2ViYXV0aG4uZ2V0IiwiY2hhbGxlbmdlIjoiVGw5Z2NZS1RwTFhHMS1qNUFSSWpQVTVmWUhHQ2s2UzF4dGZvLVFFU0l6USIsIm9yaWdpbiI6Imh0dHBzOi8vZXhhbXBsZS5jb20iLCJjcm9zc09yaWdpbiI6ZmFsc2V9',
+  signature := 'MEQCIBKOKoQkNK3-qsCGpuRT9P3Y87zKbs4xlJNm1IcL_sPHAiAqcO8JWemyJualF8nZas8cXqykRH9Y1qupkzQwoemCZA',
+  user_handle := NULL,
+  verified_at := '2020-12-15 10:34:01+01'
+);
+
+-- No session is issued for a stale assertion, nor before the assertion was verified
+SELECT webauthn.create_session(
+  signature := 'MEQCIBKOKoQkNK3-qsCGpuRT9P3Y87zKbs4xlJNm1IcL_sPHAiAqcO8JWemyJualF8nZas8cXqykRH9Y1qupkzQwoemCZA',
+  created_at := '2020-12-15 10:39:02+01'
+) AS stale_token;
+
+SELECT webauthn.create_session(
+  signature := 'MEQCIBKOKoQkNK3-qsCGpuRT9P3Y87zKbs4xlJNm1IcL_sPHAiAqcO8JWemyJualF8nZas8cXqykRH9Y1qupkzQwoemCZA',
+  created_at := '2020-12-15 10:34:00+01'
+) AS premature_token;
+
+SELECT webauthn.create_session(
+  signature := 'MEQCIBKOKoQkNK3-qsCGpuRT9P3Y87zKbs4xlJNm1IcL_sPHAiAqcO8JWemyJualF8nZas8cXqykRH9Y1qupkzQwoemCZA',
+  expires_in := '12 hours',
+  idle_timeout := '30 minutes',
+  created_at := '2020-12-15 10:34:02+01'
+) AS credential_token \gset
+
+SELECT webauthn.validate_session(:'credential_token', validated_at := '2020-12-15 10:35:00+01') AS user_id;
+
+SELECT * FROM webauthn.revoke_credential(
+  credential_id := 'shq7dRD029HpEPcTLdzQIxpjAvzpk3Ht8WS9kVsSheU',
+  revoked_at := '2020-12-15 10:36:00+01'
+);
+
+SELECT webauthn.validate_session(:'credential_token', validated_at := '2020-12-15 10:37:00+01') AS user_id;
+
+SELECT created_at, expires_at, last_used_at, revoked_at FROM webauthn.sessions ORDER BY created_at;
+
+ROLLBACK;
@@ -2,10 +2,13 @@ use anyhow::{Context, Result};
 use base64_url as base64url;
 use ciborium::Value as CborValue;
 use ecdsa::signature::Signer;
+use hmac::{Hmac, Mac};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
 use p256::{
     ecdsa::{DerSignature, SigningKey, VerifyingKey},
 };
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 
@@ -13,10 +16,55 @@ use std::collections::HashMap;
 pub struct SoftwareAuthenticator {
     credentials: Vec<StoredCredential>,
     counter: u32,
+    hmac_secret: bool,
+    cose_key_variant: CoseKeyVariant,
+    // Top-level origin of the page embedding the cross-origin iframe, if any
+    top_origin: Option<String>,
     // Map credential_id (base64) -> VerifyingKey for easy lookup
     pub verifying_keys: HashMap<String, VerifyingKey>,
 }
 
+/// How the credential public key is encoded as a COSE_Key,
+/// either validly, or as one of the invalid variants used by the negative tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoseKeyVariant {
+    Valid,
+    MissingKeyType,
+    UnsupportedAlgorithm,
+    EdDsaAlgorithm,
+    WrongCurve,
+    ShortCoordinate,
+    OffCurvePoint,
+    Es384,
+}
+
+impl CoseKeyVariant {
+    pub const INVALID: [CoseKeyVariant; 7] = [
+        CoseKeyVariant::MissingKeyType,
+        CoseKeyVariant::UnsupportedAlgorithm,
+        CoseKeyVariant::EdDsaAlgorithm,
+        CoseKeyVariant::WrongCurve,
+        CoseKeyVariant::ShortCoordinate,
+        CoseKeyVariant::OffCurvePoint,
+        CoseKeyVariant::Es384,
+    ];
+
+    /// The beginning of the error message the extension should raise when storing the credential
+    pub fn expected_error(self) -> Option<&'static str> {
+        match self {
+            CoseKeyVariant::Valid => None,
+            CoseKeyVariant::MissingKeyType => Some("COSE key parameter missing"),
+            CoseKeyVariant::UnsupportedAlgorithm => Some("Unsupported COSE algorithm"),
+            CoseKeyVariant::EdDsaAlgorithm => Some("COSE key type inconsistent with algorithm"),
+            CoseKeyVariant::WrongCurve => Some("COSE curve inconsistent with algorithm"),
+            CoseKeyVariant::ShortCoordinate => Some("COSE key coordinate length invalid"),
+            CoseKeyVariant::OffCurvePoint => Some("COSE key point not on curve"),
+            // A valid P-384 key, refused since only ES256 signatures are verified
+            CoseKeyVariant::Es384 => Some("Unsupported COSE algorithm"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct StoredCredential {
     credential_id: Vec<u8>,
@@ -25,6 +73,8 @@ struct StoredCredential {
     public_key: VerifyingKey,
     #[allow(dead_code)]
     user_id: Vec<u8>,
+    // CredRandom of the hmac-secret extension
+    cred_random: [u8; 32],
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +85,8 @@ pub struct ClientDataJson {
     pub origin: String,
     #[serde(rename = "crossOrigin")]
     pub cross_origin: bool,
+    #[serde(rename = "topOrigin", skip_serializing_if = "Option::is_none")]
+    pub top_origin: Option<String>,
 }
 
 impl SoftwareAuthenticator {
@@ -42,10 +94,36 @@ impl SoftwareAuthenticator {
         Self {
             credentials: Vec::new(),
             counter: 0,
+            hmac_secret: false,
+            cose_key_variant: CoseKeyVariant::Valid,
+            top_origin: None,
             verifying_keys: HashMap::new(),
         }
     }
 
+    /// Report the hmac-secret extension as enabled for created credentials,
+    /// each with its own CredRandom for evaluate_prf()
+    pub fn with_hmac_secret(mut self) -> Self {
+        self.hmac_secret = true;
+        self
+    }
+
+    /// Encode the public keys of created credentials as the given COSE_Key variant
+    pub fn with_cose_key_variant(mut self, cose_key_variant: CoseKeyVariant) -> Self {
+        self.cose_key_variant = cose_key_variant;
+        self
+    }
+
+    /// Create credentials and assertions in a cross-origin iframe embedded by the given top-level origin
+    pub fn with_top_origin(mut self, top_origin: &str) -> Self {
+        self.top_origin = Some(top_origin.to_string());
+        self
+    }
+
+    pub fn top_origin(&self) -> Option<&str> {
+        self.top_origin.as_deref()
+    }
+
     pub fn create_credential(
         &mut self,
         challenge: &[u8],
@@ -68,6 +146,7 @@ impl SoftwareAuthenticator {
             private_key: private_key.clone(),
             public_key: public_key.clone(),
             user_id: user_id.to_vec(),
+            cred_random: rand::random(),
         });
 
         // Create client data JSON
@@ -75,14 +154,15 @@ impl SoftwareAuthenticator {
             typ: "webauthn.create".to_string(),
             challenge: base64url::encode(challenge),
             origin: "http://localhost".to_string(),
-            cross_origin: false,
+            cross_origin: self.top_origin.is_some(),
+            top_origin: self.top_origin.clone(),
         };
         let client_data_json = serde_json::to_string(&client_data)?;
         let _client_data_hash = Sha256::digest(client_data_json.as_bytes());
 
         // Create authenticator data
         let rp_id_hash = Sha256::digest(relying_party_id.as_bytes());
-        let flags = self.build_flags(true, user_verification, true, false);
+        let flags = self.build_flags(true, user_verification, true, self.hmac_secret);
         self.counter += 1;
 
         // Build attested credential data
@@ -106,6 +186,12 @@ impl SoftwareAuthenticator {
         auth_data.extend_from_slice(&credential_id_length);
         auth_data.extend_from_slice(&credential_id);
         auth_data.extend_from_slice(&cose_key_bytes);
+        if self.hmac_secret {
+            let extensions = CborValue::Map(vec![
+                (CborValue::Text("hmac-secret".to_string()), CborValue::Bool(true)),
+            ]);
+            auth_data.extend_from_slice(&self.encode_cbor(&extensions)?);
+        }
 
         // Create attestation object
         let mut attestation_map = Vec::new();
@@ -145,7 +231,8 @@ impl SoftwareAuthenticator {
             typ: "webauthn.get".to_string(),
             challenge: base64url::encode(challenge),
             origin: "http://localhost".to_string(),
-            cross_origin: false,
+            cross_origin: self.top_origin.is_some(),
+            top_origin: self.top_origin.clone(),
         };
         let client_data_json = serde_json::to_string(&client_data)?;
         let client_data_hash = Sha256::digest(client_data_json.as_bytes());
@@ -177,6 +264,109 @@ impl SoftwareAuthenticator {
         ))
     }
 
+    /// Emulate a legacy FIDO U2F registration, returning the key handle and the
+    /// uncompressed P-256 public key the way a U2F relying party would have stored them
+    pub fn register_u2f(&mut self, user_id: &[u8]) -> Result<(String, String)> {
+        let private_key = SigningKey::random(&mut rand::thread_rng());
+        let public_key = private_key.verifying_key();
+
+        let key_handle: Vec<u8> = (0..64).map(|_| rand::random::<u8>()).collect();
+
+        let key_handle_b64 = base64url::encode(&key_handle);
+        self.verifying_keys.insert(key_handle_b64.clone(), *public_key);
+        self.credentials.push(StoredCredential {
+            credential_id: key_handle,
+            private_key: private_key.clone(),
+            public_key: *public_key,
+            user_id: user_id.to_vec(),
+            cred_random: rand::random(),
+        });
+
+        let public_key_point = public_key.to_encoded_point(false);
+
+        Ok((key_handle_b64, base64url::encode(public_key_point.as_bytes())))
+    }
+
+    /// Create an assertion for a U2F credential, with the rpIdHash computed from the AppID
+    /// instead of the RP ID, as when the client used the AppID extension
+    pub fn create_assertion_with_appid(
+        &mut self,
+        credential_id: &str,
+        challenge: &[u8],
+        appid: &str,
+    ) -> Result<(String, String, String, String, String, Option<String>)> {
+        // U2F authenticators never perform user verification
+        self.create_assertion(credential_id, challenge, appid, false)
+    }
+
+    /// Evaluate the PRF extension for a credential, i.e. what the client returns in
+    /// prf.results.first for the given input, computed with hmac-secret as in
+    /// https://www.w3.org/TR/webauthn-3/#prf-extension
+    pub fn evaluate_prf(&self, credential_id: &str, input: &[u8]) -> Result<Vec<u8>> {
+        let credential_id_bytes = base64url::decode(credential_id)
+            .context("Failed to decode credential ID")?;
+
+        let credential = self.credentials
+            .iter()
+            .find(|c| c.credential_id == credential_id_bytes)
+            .context("Credential not found")?;
+
+        let mut salt = Sha256::new();
+        salt.update(b"WebAuthn PRF");
+        salt.update([0u8]);
+        salt.update(input);
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&credential.cred_random)
+            .context("Failed to initialize HMAC")?;
+        mac.update(&salt.finalize());
+        Ok(mac.finalize().into_bytes().to_vec())
+    }
+
+    /// Serialize a registration result the way PublicKeyCredential.toJSON() does,
+    /// i.e. as a RegistrationResponseJSON
+    pub fn registration_response_json(
+        credential_id: &str,
+        attestation_object: &str,
+        client_data_json: &str,
+    ) -> JsonValue {
+        json!({
+            "id": credential_id,
+            "rawId": credential_id,
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": client_data_json,
+                "attestationObject": attestation_object,
+                "transports": ["internal"]
+            },
+            "authenticatorAttachment": "platform",
+            "clientExtensionResults": {}
+        })
+    }
+
+    /// Serialize an assertion result the way PublicKeyCredential.toJSON() does,
+    /// i.e. as an AuthenticationResponseJSON
+    pub fn authentication_response_json(
+        credential_id: &str,
+        authenticator_data: &str,
+        client_data_json: &str,
+        signature: &str,
+        user_handle: Option<&str>,
+    ) -> JsonValue {
+        json!({
+            "id": credential_id,
+            "rawId": credential_id,
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": client_data_json,
+                "authenticatorData": authenticator_data,
+                "signature": signature,
+                "userHandle": user_handle
+            },
+            "authenticatorAttachment": "platform",
+            "clientExtensionResults": {}
+        })
+    }
+
     fn build_flags(&self, up: bool, uv: bool, at: bool, ed: bool) -> u8 {
         let mut flags = 0u8;
         if up { flags |= 0x01; }  // User present
@@ -187,18 +377,41 @@ impl SoftwareAuthenticator {
     }
 
     fn build_cose_key(&self, x: &[u8], y: &[u8]) -> Result<CborValue> {
+        let mut alg = -7; // ES256
+        let mut crv = 1; // P-256
+        let mut x = x.to_vec();
+        let mut y = y.to_vec();
+
+        match self.cose_key_variant {
+            CoseKeyVariant::Valid | CoseKeyVariant::MissingKeyType => {}
+            CoseKeyVariant::UnsupportedAlgorithm => alg = -257, // RS256
+            CoseKeyVariant::EdDsaAlgorithm => alg = -8,
+            CoseKeyVariant::WrongCurve => crv = 2,
+            CoseKeyVariant::ShortCoordinate => { x.pop(); }
+            CoseKeyVariant::OffCurvePoint => y[31] ^= 0x01,
+            CoseKeyVariant::Es384 => {
+                let point = p384::SecretKey::random(&mut rand::thread_rng()).public_key().to_encoded_point(false);
+                alg = -35;
+                crv = 2;
+                x = point.x().context("P-384 point has no x coordinate")?.to_vec();
+                y = point.y().context("P-384 point has no y coordinate")?.to_vec();
+            }
+        }
+
         let mut cose_key = Vec::new();
 
         // kty: EC2 (2)
-        cose_key.push((CborValue::Integer(1.into()), CborValue::Integer(2.into())));
-        // alg: ES256 (-7)
-        cose_key.push((CborValue::Integer(3.into()), CborValue::Integer((-7).into())));
-        // crv: P-256 (1)
-        cose_key.push((CborValue::Integer((-1).into()), CborValue::Integer(1.into())));
+        if self.cose_key_variant != CoseKeyVariant::MissingKeyType {
+            cose_key.push((CborValue::Integer(1.into()), CborValue::Integer(2.into())));
+        }
+        // alg
+        cose_key.push((CborValue::Integer(3.into()), CborValue::Integer(alg.into())));
+        // crv
+        cose_key.push((CborValue::Integer((-1).into()), CborValue::Integer(crv.into())));
         // x coordinate
-        cose_key.push((CborValue::Integer((-2).into()), CborValue::Bytes(x.to_vec())));
+        cose_key.push((CborValue::Integer((-2).into()), CborValue::Bytes(x)));
         // y coordinate
-        cose_key.push((CborValue::Integer((-3).into()), CborValue::Bytes(y.to_vec())));
+        cose_key.push((CborValue::Integer((-3).into()), CborValue::Bytes(y)));
 
         Ok(CborValue::Map(cose_key))
     }
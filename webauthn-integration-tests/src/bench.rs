@@ -0,0 +1,120 @@
+use crate::authenticator::SoftwareAuthenticator;
+use crate::database::TestDatabase;
+use crate::utils::{generate_challenge, generate_user_id};
+use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
+
+/// Time spent in the insert paths of the extension, i.e. the calls that
+/// parse the attestation object and the authenticator data
+pub struct BenchResult {
+    pub rounds: usize,
+    pub store_credential: Duration,
+    pub verify_assertion: Duration,
+}
+
+impl BenchResult {
+    fn per_call_ms(total: Duration, rounds: usize) -> f64 {
+        total.as_secs_f64() * 1000.0 / rounds as f64
+    }
+
+    pub fn store_credential_ms(&self) -> f64 {
+        Self::per_call_ms(self.store_credential, self.rounds)
+    }
+
+    pub fn verify_assertion_ms(&self) -> f64 {
+        Self::per_call_ms(self.verify_assertion, self.rounds)
+    }
+
+    pub fn print(&self, label: &str) {
+        println!("{}", label);
+        println!("  store_credential:  {:>8.3} ms/call ({:.2}s total)",
+                 self.store_credential_ms(), self.store_credential.as_secs_f64());
+        println!("  verify_assertion:  {:>8.3} ms/call ({:.2}s total)",
+                 self.verify_assertion_ms(), self.verify_assertion.as_secs_f64());
+    }
+}
+
+/// Register and sign in `rounds` users, timing only the store_credential and
+/// verify_assertion calls. The SQL sticks to arguments accepted by every
+/// extension version since 1.6, so the same run can be made against a baseline.
+pub fn run_benchmark(db: &mut TestDatabase, rounds: usize) -> Result<BenchResult> {
+    let mut authenticator = SoftwareAuthenticator::new();
+    let rp_id = "localhost";
+    let mut store_credential = Duration::ZERO;
+    let mut verify_assertion = Duration::ZERO;
+
+    for round in 0..rounds {
+        let user_id = generate_user_id();
+        let user_name = format!("bench.user.{}.{}@example.com", round, rand::random::<u32>());
+        let challenge = generate_challenge();
+
+        db.client().query_one(
+            "SELECT webauthn.init_credential(
+                challenge => $1,
+                user_name => $2,
+                user_id => $3,
+                user_display_name => 'Bench User',
+                relying_party_name => 'Bench Corp',
+                relying_party_id => $4,
+                user_verification => 'discouraged'::webauthn.user_verification_requirement
+            )",
+            &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &rp_id],
+        ).context("Failed to call init_credential")?;
+
+        let (credential_id, _credential_type, attestation_object, client_data_json) =
+            authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+        let start = Instant::now();
+        db.client().query_one(
+            "SELECT webauthn.store_credential(
+                credential_id => $1,
+                credential_type => 'public-key'::webauthn.credential_type,
+                attestation_object => $2,
+                client_data_json => $3
+            )",
+            &[&credential_id, &attestation_object, &client_data_json],
+        ).context("Failed to call store_credential")?;
+        store_credential += start.elapsed();
+
+        let auth_challenge = generate_challenge();
+        db.client().query_one(
+            "SELECT webauthn.get_credentials(
+                challenge => $1,
+                user_name => $2,
+                user_verification => 'discouraged'::webauthn.user_verification_requirement,
+                relying_party_id => $3
+            )",
+            &[&auth_challenge.as_slice(), &user_name, &rp_id],
+        ).context("Failed to call get_credentials")?;
+
+        let (cred_id, _cred_type, auth_data, client_json, signature, user_handle) =
+            authenticator.create_assertion(&credential_id, &auth_challenge, rp_id, false)?;
+
+        let start = Instant::now();
+        db.client().query_one(
+            "SELECT webauthn.verify_assertion(
+                credential_id => $1,
+                credential_type => 'public-key'::webauthn.credential_type,
+                authenticator_data => $2,
+                client_data_json => $3,
+                signature => $4,
+                user_handle => $5
+            )",
+            &[&cred_id, &auth_data, &client_json, &signature, &user_handle],
+        ).context("Failed to call verify_assertion")?;
+        verify_assertion += start.elapsed();
+    }
+
+    // Make sure the timed calls did the work, since a failed verification returns early
+    let row = db.client().query_one("SELECT count(*) FROM webauthn.assertions", &[])?;
+    let verified: i64 = row.get(0);
+    if verified != rounds as i64 {
+        anyhow::bail!("Expected {} verified assertions, found {}", rounds, verified);
+    }
+
+    Ok(BenchResult {
+        rounds,
+        store_credential,
+        verify_assertion,
+    })
+}
@@ -1,24 +1,196 @@
 use anyhow::{Context, Result};
-use postgres::{Client, NoTls, Row};
+use native_tls::{Certificate, TlsConnector};
+use postgres::config::SslMode;
+use postgres::{Client, Config, NoTls, Row};
+use postgres_native_tls::MakeTlsConnector;
 use std::env;
+use std::fs;
+
+/// How to connect to the server, from a libpq-style connection string or URL
+/// and the standard PG* environment variables, e.g. PGHOST, PGPORT and PGUSER
+#[derive(Clone)]
+pub struct ConnectionOptions {
+    config: Config,
+    maintenance_db: String,
+    test_user: Option<String>,
+    test_password: Option<String>,
+    tls: Option<MakeTlsConnector>,
+}
+
+impl ConnectionOptions {
+    /// Parse the connection string or URL, if any, filling in what it leaves unset from the
+    /// PG* environment variables, or else connect to localhost as $USER like psql does.
+    ///
+    /// The connection is used to create and drop the test databases, so its role needs CREATEDB.
+    /// With a `test_user`, the tests run as that role instead, with the password in
+    /// WEBAUTHN_TEST_PASSWORD if needed, and the role needs no privileges of its own.
+    pub fn new(database_url: Option<&str>, test_user: Option<&str>) -> Result<Self> {
+        // Like libpq, a root certificate makes the server certificate verified even with sslmode=require
+        let mut verify_certificate = env::var("PGSSLROOTCERT").is_ok();
+        let mut verify_hostname = false;
+        // The postgres crate doesn't know verify-ca and verify-full, so the sslmode
+        // is taken out of the connection string and applied here, like PGSSLMODE
+        let (mut config, ssl_mode) = match database_url {
+            Some(url) => {
+                let (url, ssl_mode) = take_ssl_mode(url);
+                (url.parse::<Config>().context("Failed to parse database URL")?, ssl_mode)
+            }
+            None => (Config::new(), None),
+        };
+        if let Some(ssl_mode) = ssl_mode.or_else(|| env::var("PGSSLMODE").ok()) {
+            config.ssl_mode(parse_ssl_mode(&ssl_mode)?);
+            verify_certificate |= ssl_mode.starts_with("verify-");
+            verify_hostname = ssl_mode == "verify-full";
+        }
+
+        if config.get_hosts().is_empty() {
+            let hosts = env::var("PGHOST").unwrap_or_else(|_| "localhost".to_string());
+            for host in hosts.split(',') {
+                config.host(host);
+            }
+        }
+        if config.get_ports().is_empty() {
+            if let Ok(port) = env::var("PGPORT") {
+                config.port(port.parse().context("Failed to parse PGPORT")?);
+            }
+        }
+        if config.get_user().is_none() {
+            let user = env::var("PGUSER").or_else(|_| env::var("USER"))
+                .context("Neither PGUSER nor USER environment variable set")?;
+            config.user(&user);
+        }
+        if config.get_password().is_none() {
+            if let Ok(password) = env::var("PGPASSWORD") {
+                config.password(password);
+            }
+        }
+        let maintenance_db = match config.get_dbname() {
+            Some(dbname) => dbname.to_string(),
+            None => env::var("PGDATABASE").unwrap_or_else(|_| "postgres".to_string()),
+        };
+
+        let tls = match config.get_ssl_mode() {
+            SslMode::Disable => None,
+            _ => Some(tls_connector(verify_certificate, verify_hostname)?),
+        };
+
+        Ok(Self {
+            config,
+            maintenance_db,
+            test_user: test_user.map(str::to_string),
+            test_password: env::var("WEBAUTHN_TEST_PASSWORD").ok(),
+            tls,
+        })
+    }
+
+    /// Connect to the given database, as the test role if `as_test_user` and one is configured
+    fn connect(&self, dbname: &str, as_test_user: bool) -> Result<Client, postgres::Error> {
+        let mut config = self.config.clone();
+        config.dbname(dbname);
+        if let (true, Some(test_user)) = (as_test_user, &self.test_user) {
+            config.user(test_user);
+            match &self.test_password {
+                Some(password) => config.password(password),
+                None => config.password(Vec::new()),
+            };
+        }
+        match &self.tls {
+            Some(tls) => config.connect(tls.clone()),
+            None => config.connect(NoTls),
+        }
+    }
+}
+
+/// Remove the sslmode from a URL's query string or from a key=value connection string,
+/// returning the rest of it and the sslmode, if any
+fn take_ssl_mode(database_url: &str) -> (String, Option<String>) {
+    if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+        let Some((base, query)) = database_url.split_once('?') else {
+            return (database_url.to_string(), None);
+        };
+        let mut ssl_mode = None;
+        let params: Vec<&str> = query.split('&').filter(|param| match param.strip_prefix("sslmode=") {
+            Some(value) => {
+                ssl_mode = Some(value.to_string());
+                false
+            }
+            None => true,
+        }).collect();
+        return match params.is_empty() {
+            true => (base.to_string(), ssl_mode),
+            false => (format!("{}?{}", base, params.join("&")), ssl_mode),
+        };
+    }
+
+    // Find "sslmode = value" at the start of a key, with optional spaces around the "="
+    let mut start = 0;
+    while let Some(offset) = database_url[start..].find("sslmode") {
+        let key = start + offset;
+        start = key + "sslmode".len();
+        if key > 0 && !database_url[..key].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let rest = database_url[start..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else { continue };
+        let rest = rest.trim_start();
+        let value_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let value = rest[..value_len].trim_matches('\'');
+        let end = database_url.len() - rest.len() + value_len;
+        let remaining = format!("{} {}", database_url[..key].trim_end(), database_url[end..].trim_start());
+        return (remaining.trim().to_string(), Some(value.to_string()));
+    }
+    (database_url.to_string(), None)
+}
+
+/// Map the libpq sslmode values to those supported by the postgres crate,
+/// where verify-ca and verify-full also require TLS
+fn parse_ssl_mode(ssl_mode: &str) -> Result<SslMode> {
+    match ssl_mode {
+        "disable" => Ok(SslMode::Disable),
+        "allow" | "prefer" => Ok(SslMode::Prefer),
+        "require" | "verify-ca" | "verify-full" => Ok(SslMode::Require),
+        _ => anyhow::bail!("Invalid sslmode: {}", ssl_mode),
+    }
+}
+
+/// Verify the server certificate against the root certificate in PGSSLROOTCERT, or else the system's,
+/// or like libpq with sslmode=prefer or require, don't verify it at all
+fn tls_connector(verify_certificate: bool, verify_hostname: bool) -> Result<MakeTlsConnector> {
+    let mut builder = TlsConnector::builder();
+    if let Ok(path) = env::var("PGSSLROOTCERT") {
+        let pem = fs::read(&path).with_context(|| format!("Failed to read PGSSLROOTCERT {}", path))?;
+        builder.add_root_certificate(Certificate::from_pem(&pem).context("Failed to parse PGSSLROOTCERT")?);
+    }
+    builder.danger_accept_invalid_certs(!verify_certificate);
+    builder.danger_accept_invalid_hostnames(!verify_hostname);
+    Ok(MakeTlsConnector::new(builder.build().context("Failed to build TLS connector")?))
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
 
 pub struct TestDatabase {
     client: Client,
+    options: ConnectionOptions,
     db_name: String,
     should_cleanup: bool,
     debug: bool,
 }
 
 impl TestDatabase {
-    pub fn new() -> Result<Self> {
-        let user = env::var("USER").context("USER environment variable not set")?;
+    pub fn new(options: &ConnectionOptions) -> Result<Self> {
+        Self::with_extension_version(options, None)
+    }
+
+    /// Create a test database with the given version of the webauthn extension,
+    /// or the default version if `None`
+    pub fn with_extension_version(options: &ConnectionOptions, version: Option<&str>) -> Result<Self> {
         let db_name = format!("webauthn_test_{}", rand::random::<u32>());
 
-        // Connect to postgres database to create test database
-        let mut client = Client::connect(
-            &format!("host=localhost port=5432 user={} dbname=postgres", user),
-            NoTls,
-        ).context("Failed to connect to PostgreSQL")?;
+        // Connect to the maintenance database to create test database
+        let mut client = options.connect(&options.maintenance_db, false)
+            .context("Failed to connect to PostgreSQL")?;
 
         // Create test database
         client.execute(
@@ -26,24 +198,49 @@ impl TestDatabase {
             &[],
         ).context("Failed to create test database")?;
 
-        // Disconnect from postgres and connect to test database
+        // Disconnect from the maintenance database and connect to test database
         drop(client);
 
-        let mut client = Client::connect(
-            &format!("host=localhost port=5432 user={} dbname={}", user, db_name),
-            NoTls,
-        ).context("Failed to connect to test database")?;
+        let mut client = options.connect(&db_name, false)
+            .context("Failed to connect to test database")?;
 
         // Install webauthn extension
+        let create_extension = match version {
+            Some(version) => format!("CREATE EXTENSION IF NOT EXISTS webauthn VERSION '{}' CASCADE", version),
+            None => "CREATE EXTENSION IF NOT EXISTS webauthn CASCADE".to_string(),
+        };
         client.execute(
-            "CREATE EXTENSION IF NOT EXISTS webauthn CASCADE",
+            &create_extension,
             &[],
         ).context("Failed to create webauthn extension")?;
 
+        // The tests also read and write the tables directly, not only through the API functions,
+        // call the internal functions, which aren't executable by PUBLIC, and call functions of the extensions webauthn depends on, e.g. in the cbor schema
+        if let Some(test_user) = &options.test_user {
+            let role = quote_identifier(test_user);
+            for row in client.query(
+                "SELECT DISTINCT extnamespace::regnamespace::text FROM pg_extension",
+                &[],
+            )? {
+                let schema: String = row.get(0);
+                client.execute(&format!("GRANT USAGE ON SCHEMA {} TO {}", schema, role), &[])
+                    .context("Failed to grant schema usage to test role")?;
+            }
+            client.batch_execute(&format!(
+                "GRANT ALL ON ALL TABLES IN SCHEMA webauthn TO {role};
+                 GRANT ALL ON ALL SEQUENCES IN SCHEMA webauthn TO {role};
+                 GRANT EXECUTE ON ALL FUNCTIONS IN SCHEMA webauthn TO {role};"
+            )).context("Failed to grant access to test role")?;
+            drop(client);
+            client = options.connect(&db_name, true)
+                .context("Failed to connect to test database as test role")?;
+        }
+
         println!("  Created database: {}", db_name);
 
         Ok(Self {
             client,
+            options: options.clone(),
             db_name,
             should_cleanup: true,
             debug: false,
@@ -128,14 +325,9 @@ impl TestDatabase {
             return Ok(());
         }
 
-        // Disconnect from test database
-        let user = env::var("USER").context("USER environment variable not set")?;
-
-        // Create new connection to postgres database to drop test database
-        let mut client = Client::connect(
-            &format!("host=localhost port=5432 user={} dbname=postgres", user),
-            NoTls,
-        ).context("Failed to connect to PostgreSQL for cleanup")?;
+        // Create new connection to the maintenance database to drop test database
+        let mut client = self.options.connect(&self.options.maintenance_db, false)
+            .context("Failed to connect to PostgreSQL for cleanup")?;
 
         // Terminate connections to test database
         client.execute(
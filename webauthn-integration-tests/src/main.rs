@@ -1,5 +1,7 @@
 mod authenticator;
+mod bench;
 mod database;
+mod mutator;
 mod test_positive;
 mod test_negative;
 mod utils;
@@ -7,7 +9,7 @@ mod verifier;
 
 use anyhow::Result;
 use clap::Parser;
-use database::TestDatabase;
+use database::{ConnectionOptions, TestDatabase};
 use std::process;
 use std::time::{Duration, Instant};
 
@@ -21,24 +23,60 @@ struct Args {
     /// Enable debug output for SQL queries
     #[clap(short = 'd', long)]
     debug: bool,
+
+    /// Instead of running the tests, time this many store_credential and verify_assertion calls
+    #[clap(long, value_name = "ROUNDS")]
+    bench: Option<usize>,
+
+    /// Also run the benchmark against this extension version, e.g. 1.6, and report the speedup
+    #[clap(long, value_name = "VERSION", requires = "bench")]
+    baseline: Option<String>,
+
+    /// libpq connection string or postgresql:// URL of a role that can create databases,
+    /// defaulting to the PG* environment variables
+    #[clap(long, value_name = "URL")]
+    database_url: Option<String>,
+
+    /// Run the tests as this role instead, which then needs no CREATEDB,
+    /// with the password in the WEBAUTHN_TEST_PASSWORD environment variable if needed
+    #[clap(long, value_name = "ROLE")]
+    test_user: Option<String>,
 }
 
 fn main() {
     let args = Args::parse();
 
-    match run_tests(args.iterations, args.debug) {
+    let options = match ConnectionOptions::new(args.database_url.as_deref(), args.test_user.as_deref()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("\n❌ Invalid connection options: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Some(rounds) = args.bench {
+        match run_benchmarks(&options, rounds, args.baseline.as_deref()) {
+            Ok(_) => process::exit(0),
+            Err(e) => {
+                eprintln!("\n❌ Benchmark failed: {:#}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    match run_tests(&options, args.iterations, args.debug) {
         Ok(_) => {
             println!("\n✅ All tests passed!");
             process::exit(0);
         }
         Err(e) => {
-            eprintln!("\n❌ Test suite failed: {}", e);
+            eprintln!("\n❌ Test suite failed: {:#}", e);
             process::exit(1);
         }
     }
 }
 
-fn run_tests(iterations: usize, debug: bool) -> Result<()> {
+fn run_tests(options: &ConnectionOptions, iterations: usize, debug: bool) -> Result<()> {
     println!("===========================================");
     println!("   WebAuthn PostgreSQL Integration Tests   ");
     println!("===========================================");
@@ -46,7 +84,7 @@ fn run_tests(iterations: usize, debug: bool) -> Result<()> {
 
     // Create test database
     println!("\n📦 Setting up test database...");
-    let mut db = TestDatabase::new()?;
+    let mut db = TestDatabase::new(options)?;
     db.disable_cleanup();  // Don't cleanup database after tests
     db.set_debug(debug);
     if debug {
@@ -89,11 +127,38 @@ fn run_tests(iterations: usize, debug: bool) -> Result<()> {
     println!("Total iterations:    {}", iterations);
     println!("Total time:          {:.2}s", total_time.as_secs_f64());
     println!("Average per iteration: {:.2}s", avg_time.as_secs_f64());
-    println!("Tests per iteration: 10 (4 positive, 6 negative)");
-    println!("Total tests run:     {}", iterations * 10);
+    println!("Tests per iteration: 19 (8 positive, 11 negative)");
+    println!("Total tests run:     {}", iterations * 19);
 
     println!("\n📌 Test database preserved for debugging");
     // Database not cleaned up - disabled cleanup
 
     Ok(())
-}
\ No newline at end of file
+}
+
+fn run_benchmarks(options: &ConnectionOptions, rounds: usize, baseline: Option<&str>) -> Result<()> {
+    println!("===========================================");
+    println!("   WebAuthn PostgreSQL Benchmark           ");
+    println!("===========================================");
+    println!("\nRounds: {}", rounds);
+
+    let mut db = TestDatabase::new(options)?;
+    let current = bench::run_benchmark(&mut db, rounds)?;
+    drop(db);
+    println!();
+    current.print("Installed default version:");
+
+    if let Some(version) = baseline {
+        let mut db = TestDatabase::with_extension_version(options, Some(version))?;
+        let previous = bench::run_benchmark(&mut db, rounds)?;
+        drop(db);
+        println!();
+        previous.print(&format!("Baseline version {}:", version));
+
+        println!("\nSpeedup over {}:", version);
+        println!("  store_credential:  {:.2}x", previous.store_credential_ms() / current.store_credential_ms());
+        println!("  verify_assertion:  {:.2}x", previous.verify_assertion_ms() / current.verify_assertion_ms());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,403 @@
+use anyhow::{Context, Result};
+use base64_url as base64url;
+use ciborium::Value as CborValue;
+use std::ops::Range;
+
+/// Offsets of the fields of authenticator data, see
+/// https://www.w3.org/TR/webauthn-2/#sctn-authenticator-data
+#[derive(Debug)]
+struct AuthDataLayout {
+    flags: u8,
+    credential_id: Option<Range<usize>>,
+    credential_public_key: Option<Range<usize>>,
+}
+
+const FLAGS: usize = 32;
+const SIGN_COUNT: Range<usize> = 33..37;
+const AAGUID: Range<usize> = 37..53;
+const CREDENTIAL_ID_LENGTH: Range<usize> = 53..55;
+
+const FLAG_AT: u8 = 0x40;
+const FLAG_ED: u8 = 0x80;
+
+impl AuthDataLayout {
+    fn parse(auth_data: &[u8]) -> Result<Self> {
+        if auth_data.len() < SIGN_COUNT.end {
+            anyhow::bail!("Authenticator data is only {} bytes", auth_data.len());
+        }
+        let flags = auth_data[FLAGS];
+        if flags & FLAG_AT == 0 {
+            return Ok(Self { flags, credential_id: None, credential_public_key: None });
+        }
+
+        let length_bytes = auth_data.get(CREDENTIAL_ID_LENGTH)
+            .context("Attested credential data is truncated")?;
+        let credential_id_length = u16::from_be_bytes([length_bytes[0], length_bytes[1]]) as usize;
+        let credential_id = CREDENTIAL_ID_LENGTH.end..CREDENTIAL_ID_LENGTH.end + credential_id_length;
+
+        // Decoding from a slice advances it past the COSE key, which gives its length
+        let mut rest = auth_data.get(credential_id.end..)
+            .context("Credential ID is truncated")?;
+        let before = rest.len();
+        let _: CborValue = ciborium::from_reader(&mut rest)
+            .context("Failed to decode credential public key")?;
+        let credential_public_key = credential_id.end..credential_id.end + before - rest.len();
+
+        Ok(Self {
+            flags,
+            credential_id: Some(credential_id),
+            credential_public_key: Some(credential_public_key),
+        })
+    }
+}
+
+/// A structure-aware mutation of authenticator data, truncating or extending one of its
+/// fields instead of flipping random bits, so that each mutation hits a specific check
+#[derive(Debug, Clone, Copy)]
+pub enum AuthDataMutation {
+    TruncateRpIdHash,
+    TruncateSignCount,
+    TruncateAaguid,
+    TruncateCredentialIdLength,
+    ExtendCredentialIdLength,
+    TruncateCredentialId,
+    TruncateCredentialPublicKey,
+    StripAttestedCredentialData,
+    ClearAttestedCredentialDataFlag,
+    SetExtensionDataFlagWithoutExtensions,
+    AppendTruncatedExtensions,
+    AppendTrailingBytes,
+}
+
+impl AuthDataMutation {
+    pub const ALL: [AuthDataMutation; 12] = [
+        AuthDataMutation::TruncateRpIdHash,
+        AuthDataMutation::TruncateSignCount,
+        AuthDataMutation::TruncateAaguid,
+        AuthDataMutation::TruncateCredentialIdLength,
+        AuthDataMutation::ExtendCredentialIdLength,
+        AuthDataMutation::TruncateCredentialId,
+        AuthDataMutation::TruncateCredentialPublicKey,
+        AuthDataMutation::StripAttestedCredentialData,
+        AuthDataMutation::ClearAttestedCredentialDataFlag,
+        AuthDataMutation::SetExtensionDataFlagWithoutExtensions,
+        AuthDataMutation::AppendTruncatedExtensions,
+        AuthDataMutation::AppendTrailingBytes,
+    ];
+
+    /// Whether the mutation only needs the fixed-size header,
+    /// and thus also applies to the authenticator data of an assertion
+    pub fn applies_to_assertion(self) -> bool {
+        matches!(
+            self,
+            AuthDataMutation::TruncateRpIdHash
+                | AuthDataMutation::TruncateSignCount
+                | AuthDataMutation::SetExtensionDataFlagWithoutExtensions
+                | AuthDataMutation::AppendTruncatedExtensions
+                | AuthDataMutation::AppendTrailingBytes
+        )
+    }
+
+    /// The beginning of the error message the extension should raise
+    pub fn expected_error(self) -> &'static str {
+        match self {
+            AuthDataMutation::TruncateRpIdHash
+            | AuthDataMutation::TruncateSignCount => "Authenticator data too short",
+            AuthDataMutation::TruncateAaguid
+            | AuthDataMutation::TruncateCredentialIdLength => "Attested credential data too short",
+            AuthDataMutation::ExtendCredentialIdLength
+            | AuthDataMutation::TruncateCredentialId => "Credential ID length out of range",
+            AuthDataMutation::TruncateCredentialPublicKey => "Malformed credential public key",
+            AuthDataMutation::StripAttestedCredentialData => "Attested credential data missing",
+            AuthDataMutation::ClearAttestedCredentialDataFlag
+            | AuthDataMutation::AppendTrailingBytes => "Trailing bytes after authenticator data",
+            AuthDataMutation::SetExtensionDataFlagWithoutExtensions => "Extension data flag set without extensions",
+            AuthDataMutation::AppendTruncatedExtensions => "Malformed extensions",
+        }
+    }
+
+    /// Apply the mutation to authenticator data without extensions
+    pub fn apply(self, auth_data: &[u8]) -> Result<Vec<u8>> {
+        let layout = AuthDataLayout::parse(auth_data)?;
+        if layout.flags & FLAG_ED != 0 {
+            anyhow::bail!("Mutations expect authenticator data without extensions");
+        }
+        let attested = || -> Result<(Range<usize>, Range<usize>)> {
+            Ok((
+                layout.credential_id.clone().context("Mutation needs attested credential data")?,
+                layout.credential_public_key.clone().context("Mutation needs attested credential data")?,
+            ))
+        };
+
+        let mut mutated = auth_data.to_vec();
+        match self {
+            AuthDataMutation::TruncateRpIdHash => mutated.truncate(FLAGS - 12),
+            AuthDataMutation::TruncateSignCount => mutated.truncate(SIGN_COUNT.end - 2),
+            AuthDataMutation::TruncateAaguid => {
+                attested()?;
+                mutated.truncate(AAGUID.start + 8);
+            }
+            AuthDataMutation::TruncateCredentialIdLength => {
+                attested()?;
+                mutated.truncate(CREDENTIAL_ID_LENGTH.start + 1);
+            }
+            AuthDataMutation::ExtendCredentialIdLength => {
+                let (credential_id, _) = attested()?;
+                // Point the length past the end of the data
+                let length = (auth_data.len() - credential_id.start) as u16;
+                mutated[CREDENTIAL_ID_LENGTH].copy_from_slice(&length.to_be_bytes());
+            }
+            AuthDataMutation::TruncateCredentialId => {
+                let (credential_id, _) = attested()?;
+                mutated.truncate(credential_id.start + credential_id.len() / 2);
+            }
+            AuthDataMutation::TruncateCredentialPublicKey => {
+                let (_, credential_public_key) = attested()?;
+                mutated.truncate(credential_public_key.end - 1);
+            }
+            AuthDataMutation::StripAttestedCredentialData => {
+                attested()?;
+                mutated.truncate(SIGN_COUNT.end);
+                mutated[FLAGS] &= !FLAG_AT;
+            }
+            AuthDataMutation::ClearAttestedCredentialDataFlag => {
+                attested()?;
+                mutated[FLAGS] &= !FLAG_AT;
+            }
+            AuthDataMutation::SetExtensionDataFlagWithoutExtensions => {
+                mutated[FLAGS] |= FLAG_ED;
+            }
+            AuthDataMutation::AppendTruncatedExtensions => {
+                // A map header announcing one pair, without the pair
+                mutated[FLAGS] |= FLAG_ED;
+                mutated.push(0xa1);
+            }
+            AuthDataMutation::AppendTrailingBytes => mutated.extend_from_slice(&[0x00, 0x00]),
+        }
+        Ok(mutated)
+    }
+
+    /// Apply the mutation to base64url encoded authenticator data, as returned by an assertion
+    pub fn apply_base64(self, auth_data: &str) -> Result<String> {
+        let bytes = base64url::decode(auth_data).context("Failed to decode authenticator data")?;
+        Ok(base64url::encode(&self.apply(&bytes)?))
+    }
+
+    /// Apply the mutation to the authData of a base64url encoded attestation object
+    pub fn apply_to_attestation_object(self, attestation_object: &str) -> Result<String> {
+        let bytes = base64url::decode(attestation_object).context("Failed to decode attestation object")?;
+        let value: CborValue = ciborium::from_reader(bytes.as_slice())
+            .context("Failed to decode attestation object CBOR")?;
+        let mut entries = match value {
+            CborValue::Map(entries) => entries,
+            _ => anyhow::bail!("Attestation object is not a CBOR map"),
+        };
+
+        let auth_data = entries.iter_mut()
+            .find_map(|(key, value)| match (key, value) {
+                (CborValue::Text(key), CborValue::Bytes(auth_data)) if key == "authData" => Some(auth_data),
+                _ => None,
+            })
+            .context("Attestation object has no authData")?;
+        *auth_data = self.apply(auth_data)?;
+
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&CborValue::Map(entries), &mut encoded)
+            .context("Failed to encode attestation object")?;
+        Ok(base64url::encode(&encoded))
+    }
+}
+
+/// A DER encoded ECDSA-Sig-Value split into the contents of its r and s INTEGERs
+#[derive(Debug, Clone)]
+struct DerSignatureParts {
+    r: Vec<u8>,
+    s: Vec<u8>,
+}
+
+impl DerSignatureParts {
+    fn parse(der: &[u8]) -> Result<Self> {
+        let (sequence, rest) = read_tlv(der, 0x30)?;
+        if !rest.is_empty() {
+            anyhow::bail!("Trailing bytes after DER signature");
+        }
+        let (r, rest) = read_tlv(sequence, 0x02)?;
+        let (s, rest) = read_tlv(rest, 0x02)?;
+        if !rest.is_empty() {
+            anyhow::bail!("Trailing bytes in DER signature SEQUENCE");
+        }
+        Ok(Self { r: r.to_vec(), s: s.to_vec() })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut integers = encode_tlv(0x02, &self.r);
+        integers.extend(encode_tlv(0x02, &self.s));
+        encode_tlv(0x30, &integers)
+    }
+}
+
+/// Read a TLV with a short or long form length, returning its content and what follows it
+fn read_tlv(der: &[u8], tag: u8) -> Result<(&[u8], &[u8])> {
+    match der {
+        [t, ..] if *t != tag => anyhow::bail!("Expected tag {:#04x}, found {:#04x}", tag, t),
+        [_, length, rest @ ..] if *length < 0x80 => {
+            let length = *length as usize;
+            anyhow::ensure!(rest.len() >= length, "DER content is truncated");
+            Ok(rest.split_at(length))
+        }
+        [_, length_bytes, rest @ ..] => {
+            let length_bytes = (*length_bytes & 0x7f) as usize;
+            anyhow::ensure!(rest.len() >= length_bytes, "DER length is truncated");
+            let (length, rest) = rest.split_at(length_bytes);
+            let length = length.iter().fold(0usize, |length, byte| (length << 8) | *byte as usize);
+            anyhow::ensure!(rest.len() >= length, "DER content is truncated");
+            Ok(rest.split_at(length))
+        }
+        _ => anyhow::bail!("DER header is truncated"),
+    }
+}
+
+/// Encode a TLV with the minimal length encoding DER requires
+fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut tlv = vec![tag];
+    tlv.extend(encode_length(content.len()));
+    tlv.extend_from_slice(content);
+    tlv
+}
+
+fn encode_length(length: usize) -> Vec<u8> {
+    if length < 0x80 {
+        return vec![length as u8];
+    }
+    let bytes: Vec<u8> = length.to_be_bytes().into_iter().skip_while(|byte| *byte == 0).collect();
+    let mut encoded = vec![0x80 | bytes.len() as u8];
+    encoded.extend(bytes);
+    encoded
+}
+
+/// Strip the zero byte DER prepends to keep the sign bit of an INTEGER clear
+fn unsigned_integer(content: &[u8]) -> &[u8] {
+    match content {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        _ => content,
+    }
+}
+
+/// A mutation of a DER encoded ECDSA signature violating one rule of DER
+/// or of the range of r and s, as produced by the `ecdsa` crate's `DerSignature`
+#[derive(Debug, Clone, Copy)]
+pub enum SignatureMutation {
+    Truncate,
+    AppendTrailingBytes,
+    AppendInteger,
+    WrongSequenceTag,
+    WrongIntegerTag,
+    IndefiniteLength,
+    LongFormSequenceLength,
+    LongFormIntegerLength,
+    PadInteger,
+    NegateInteger,
+    EmptyInteger,
+    ZeroInteger,
+    WidenInteger,
+}
+
+impl SignatureMutation {
+    pub const ALL: [SignatureMutation; 13] = [
+        SignatureMutation::Truncate,
+        SignatureMutation::AppendTrailingBytes,
+        SignatureMutation::AppendInteger,
+        SignatureMutation::WrongSequenceTag,
+        SignatureMutation::WrongIntegerTag,
+        SignatureMutation::IndefiniteLength,
+        SignatureMutation::LongFormSequenceLength,
+        SignatureMutation::LongFormIntegerLength,
+        SignatureMutation::PadInteger,
+        SignatureMutation::NegateInteger,
+        SignatureMutation::EmptyInteger,
+        SignatureMutation::ZeroInteger,
+        SignatureMutation::WidenInteger,
+    ];
+
+    /// The beginning of the error message the extension should raise
+    pub fn expected_error(self) -> &'static str {
+        match self {
+            SignatureMutation::Truncate => "DER signature truncated",
+            SignatureMutation::AppendTrailingBytes
+            | SignatureMutation::AppendInteger => "Trailing bytes after DER signature",
+            SignatureMutation::WrongSequenceTag
+            | SignatureMutation::WrongIntegerTag => "DER signature has unexpected tag",
+            SignatureMutation::IndefiniteLength => "DER signature has indefinite length",
+            SignatureMutation::LongFormSequenceLength
+            | SignatureMutation::LongFormIntegerLength => "DER signature length not minimally encoded",
+            SignatureMutation::PadInteger => "DER signature INTEGER not minimally encoded",
+            SignatureMutation::NegateInteger => "DER signature INTEGER is negative",
+            SignatureMutation::EmptyInteger => "DER signature INTEGER is empty",
+            SignatureMutation::ZeroInteger
+            | SignatureMutation::WidenInteger => "DER signature INTEGER out of range",
+        }
+    }
+
+    /// Apply the mutation to a DER signature whose r and s are `width` bytes wide
+    pub fn apply(self, der: &[u8], width: usize) -> Result<Vec<u8>> {
+        let parts = DerSignatureParts::parse(der)?;
+        anyhow::ensure!(parts.encode() == der, "DER signature is not minimally encoded");
+
+        let mut mutated = parts.clone();
+        let mutated = match self {
+            SignatureMutation::Truncate => der[..der.len() - 1].to_vec(),
+            SignatureMutation::AppendTrailingBytes => [der, &[0x00]].concat(),
+            SignatureMutation::AppendInteger => {
+                let mut integers = encode_tlv(0x02, &parts.r);
+                integers.extend(encode_tlv(0x02, &parts.s));
+                integers.extend(encode_tlv(0x02, &[0x01]));
+                encode_tlv(0x30, &integers)
+            }
+            SignatureMutation::WrongSequenceTag => [&[0x31], &der[1..]].concat(),
+            SignatureMutation::WrongIntegerTag => {
+                let mut integers = encode_tlv(0x02, &parts.r);
+                integers.extend(encode_tlv(0x03, &parts.s));
+                encode_tlv(0x30, &integers)
+            }
+            SignatureMutation::IndefiniteLength => {
+                let (sequence, _) = read_tlv(der, 0x30)?;
+                [&[0x30, 0x80], sequence, &[0x00, 0x00]].concat()
+            }
+            SignatureMutation::LongFormSequenceLength => {
+                // A two byte length whose first byte is zero is never minimal
+                let (sequence, _) = read_tlv(der, 0x30)?;
+                let length = (sequence.len() as u16).to_be_bytes();
+                [&[0x30, 0x82], &length[..], sequence].concat()
+            }
+            SignatureMutation::LongFormIntegerLength => {
+                let mut integers = vec![0x02, 0x81, parts.r.len() as u8];
+                integers.extend_from_slice(&parts.r);
+                integers.extend(encode_tlv(0x02, &parts.s));
+                encode_tlv(0x30, &integers)
+            }
+            SignatureMutation::PadInteger => {
+                mutated.r.insert(0, 0x00);
+                mutated.encode()
+            }
+            SignatureMutation::NegateInteger => {
+                mutated.r = unsigned_integer(&parts.r).to_vec();
+                mutated.r[0] |= 0x80;
+                mutated.encode()
+            }
+            SignatureMutation::EmptyInteger => {
+                mutated.r.clear();
+                mutated.encode()
+            }
+            SignatureMutation::ZeroInteger => {
+                mutated.s = vec![0x00];
+                mutated.encode()
+            }
+            SignatureMutation::WidenInteger => {
+                mutated.r = [&[0x01], unsigned_integer(&parts.r)].concat();
+                mutated.r.resize(width + 1, 0x01);
+                mutated.encode()
+            }
+        };
+        Ok(mutated)
+    }
+}
@@ -1,8 +1,10 @@
-use crate::authenticator::SoftwareAuthenticator;
+use crate::authenticator::{CoseKeyVariant, SoftwareAuthenticator};
 use crate::database::TestDatabase;
-use crate::utils::{flip_bit_in_base64, format_string_diff, format_test_result, generate_challenge, generate_user_id};
+use crate::mutator::{AuthDataMutation, SignatureMutation};
+use crate::utils::{assert_db_error_message, flip_bit_in_base64, format_string_diff, format_test_result, generate_challenge, generate_user_id};
 use crate::verifier;
 use anyhow::{Context, Result};
+use ecdsa::signature::Signer;
 
 pub fn run_negative_tests(db: &mut TestDatabase, _debug: bool) -> Result<()> {
     println!("\n=== Running Negative Tests ===\n");
@@ -88,6 +90,71 @@ pub fn run_negative_tests(db: &mut TestDatabase, _debug: bool) -> Result<()> {
         }
     }
 
+    // Test 7: Lockout after repeated failures
+    match test_lockout_after_repeated_failures(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("Lockout after repeated failures", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("Lockout after repeated failures", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
+    // Test 8: Structure-aware authData mutations
+    match test_auth_data_mutations(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("Structure-aware authData mutations", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("Structure-aware authData mutations", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
+    // Test 9: Malformed DER signatures
+    match test_malformed_der_signatures(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("Malformed DER signatures", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("Malformed DER signatures", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
+    // Test 10: Invalid COSE keys
+    match test_invalid_cose_keys(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("Invalid COSE keys", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("Invalid COSE keys", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
+    // Test 11: Cross-origin policy
+    match test_cross_origin_policy(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("Cross-origin policy", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("Cross-origin policy", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
     println!("\nNegative Tests: {} passed, {} failed", passed, failed);
 
     if failed > 0 {
@@ -832,4 +899,550 @@ fn test_expired_challenge(db: &mut TestDatabase) -> Result<()> {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn test_lockout_after_repeated_failures(db: &mut TestDatabase) -> Result<()> {
+    db.client().execute(
+        "INSERT INTO webauthn.lockout_policies (scope, max_failures, failure_window, cooldown)
+         VALUES ('credential', 3, '5 minutes', '1 minute'),
+                ('user_name', 3, '5 minutes', '1 minute')",
+        &[],
+    )?;
+
+    let result = hammer_until_locked_out(db);
+
+    // Lockout policies apply to the whole database, so always remove them
+    // to not affect the other tests.
+    db.client().execute("DELETE FROM webauthn.lockout_policies", &[])?;
+
+    result
+}
+
+fn hammer_until_locked_out(db: &mut TestDatabase) -> Result<()> {
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    // Setup: Create a valid credential, with a unique user_name since lockouts persist
+    let challenge = generate_challenge();
+    let user_id = generate_user_id();
+    let user_name = format!("lockout.{}@example.com", rand::random::<u32>());
+    let rp_id = "localhost";
+
+    db.client().execute(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => $4,
+            relying_party_name => $5,
+            relying_party_id => $6,
+            require_resident_key => false,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            attestation => 'none'::webauthn.attestation_conveyance_preference,
+            timeout => '5 minutes'::interval
+        )",
+        &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Lockout User", &"Test Corp", &rp_id],
+    )?;
+
+    let (credential_id, _credential_type, attestation_object, client_data_json) =
+        authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+    db.client().execute(
+        "SELECT webauthn.store_credential(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            attestation_object => $2,
+            client_data_json => $3
+        )",
+        &[&credential_id, &attestation_object, &client_data_json],
+    )?;
+
+    let auth_challenge = generate_challenge();
+    db.client().execute(
+        "SELECT webauthn.get_credentials(
+            challenge => $1,
+            user_name => $2,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            timeout => '5 minutes'::interval,
+            relying_party_id => $3
+        )",
+        &[&auth_challenge.as_slice(), &user_name, &rp_id],
+    )?;
+
+    let (cred_id, _cred_type, auth_data, client_json, signature, user_handle) =
+        authenticator.create_assertion(&credential_id, &auth_challenge, rp_id, false)?;
+
+    // Test: Each corrupted signature is recorded as a failure and returns NULL,
+    // including garbage that isn't even a DER signature
+    for attempt in 1..=3 {
+        let corrupted_signature = match attempt {
+            1 => base64_url::encode(&rand::random::<[u8; 16]>()),
+            _ => flip_bit_in_base64(&signature)?,
+        };
+
+        let row = db.client().query_one(
+            "SELECT webauthn.verify_assertion(
+                credential_id => $1,
+                credential_type => 'public-key'::webauthn.credential_type,
+                authenticator_data => $2,
+                client_data_json => $3,
+                signature => $4,
+                user_handle => $5
+            )",
+            &[&cred_id, &auth_data, &client_json, &corrupted_signature, &user_handle],
+        ).with_context(|| format!("Failed attempt {} should return NULL, not raise", attempt))?;
+
+        let user_id_result: Option<Vec<u8>> = row.get(0);
+        if user_id_result.is_some() {
+            anyhow::bail!("Verification should have failed with corrupted signature\n\n{}",
+                format_string_diff("signature", &signature, &corrupted_signature));
+        }
+    }
+
+    println!("  Recorded 3 failed assertions");
+
+    // Test: The valid assertion is now refused with the lockout error
+    let result = db.client().query_one(
+        "SELECT webauthn.verify_assertion(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            authenticator_data => $2,
+            client_data_json => $3,
+            signature => $4,
+            user_handle => $5
+        )",
+        &[&cred_id, &auth_data, &client_json, &signature, &user_handle],
+    );
+    assert_db_error_message(result, "Locked out", "verify_assertion with valid signature during lockout")?;
+
+    // Test: get_credentials is refused for the locked out user_name
+    let result = db.client().query_one(
+        "SELECT webauthn.get_credentials(
+            challenge => $1,
+            user_name => $2,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            timeout => '5 minutes'::interval,
+            relying_party_id => $3
+        )",
+        &[&generate_challenge().as_slice(), &user_name, &rp_id],
+    );
+    assert_db_error_message(result, "Locked out", "get_credentials during lockout")?;
+
+    // Test: The valid assertion succeeds once the cooldown has passed
+    let row = db.client().query_one(
+        "SELECT webauthn.verify_assertion(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            authenticator_data => $2,
+            client_data_json => $3,
+            signature => $4,
+            user_handle => $5,
+            verified_at => now() + '2 minutes'::interval
+        )",
+        &[&cred_id, &auth_data, &client_json, &signature, &user_handle],
+    ).context("Valid assertion should succeed after cooldown")?;
+
+    let verified_user_id: Option<Vec<u8>> = row.get(0);
+    if verified_user_id.as_deref() != Some(user_id.as_slice()) {
+        anyhow::bail!("Valid assertion after cooldown should return the user_id");
+    }
+
+    Ok(())
+}
+
+fn test_auth_data_mutations(db: &mut TestDatabase) -> Result<()> {
+    let mut authenticator = SoftwareAuthenticator::new();
+    let user_id = generate_user_id();
+    let user_name = "mutation.authdata@example.com";
+    let rp_id = "localhost";
+
+    // Test: Each mutation of the attestation object's authData is refused by store_credential
+    for mutation in AuthDataMutation::ALL {
+        let challenge = generate_challenge();
+        db.client().execute(
+            "SELECT webauthn.init_credential(
+                challenge => $1,
+                user_name => $2,
+                user_id => $3,
+                user_display_name => $4,
+                relying_party_name => $5,
+                relying_party_id => $6,
+                require_resident_key => false,
+                user_verification => 'discouraged'::webauthn.user_verification_requirement,
+                attestation => 'none'::webauthn.attestation_conveyance_preference,
+                timeout => '5 minutes'::interval
+            )",
+            &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Mutated AuthData", &"Test Corp", &rp_id],
+        )?;
+
+        let (credential_id, _credential_type, attestation_object, client_data_json) =
+            authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+        let mutated_attestation_object = mutation.apply_to_attestation_object(&attestation_object)?;
+
+        let result = db.client().query_one(
+            "SELECT webauthn.store_credential(
+                credential_id => $1,
+                credential_type => 'public-key'::webauthn.credential_type,
+                attestation_object => $2,
+                client_data_json => $3
+            )",
+            &[&credential_id, &mutated_attestation_object, &client_data_json],
+        );
+        assert_db_error_message(result, mutation.expected_error(), &format!("store_credential with {:?}", mutation))?;
+    }
+
+    println!("  Refused {} mutated attestation objects", AuthDataMutation::ALL.len());
+
+    // Setup: Store a valid credential to assert with
+    let challenge = generate_challenge();
+    db.client().execute(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => $4,
+            relying_party_name => $5,
+            relying_party_id => $6,
+            require_resident_key => false,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            attestation => 'none'::webauthn.attestation_conveyance_preference,
+            timeout => '5 minutes'::interval
+        )",
+        &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Mutated AuthData", &"Test Corp", &rp_id],
+    )?;
+
+    let (credential_id, _credential_type, attestation_object, client_data_json) =
+        authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+    db.client().execute(
+        "SELECT webauthn.store_credential(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            attestation_object => $2,
+            client_data_json => $3
+        )",
+        &[&credential_id, &attestation_object, &client_data_json],
+    )?;
+
+    // Test: Each mutation of an assertion's authenticator data is refused by verify_assertion
+    let assertion_mutations: Vec<AuthDataMutation> = AuthDataMutation::ALL
+        .into_iter()
+        .filter(|mutation| mutation.applies_to_assertion())
+        .collect();
+
+    for mutation in &assertion_mutations {
+        let auth_challenge = generate_challenge();
+        db.client().execute(
+            "SELECT webauthn.get_credentials(
+                challenge => $1,
+                user_name => $2,
+                user_verification => 'discouraged'::webauthn.user_verification_requirement,
+                timeout => '5 minutes'::interval,
+                relying_party_id => $3
+            )",
+            &[&auth_challenge.as_slice(), &user_name, &rp_id],
+        )?;
+
+        let (cred_id, _cred_type, auth_data, client_json, signature, user_handle) =
+            authenticator.create_assertion(&credential_id, &auth_challenge, rp_id, false)?;
+
+        let mutated_auth_data = mutation.apply_base64(&auth_data)?;
+
+        let result = db.client().query_one(
+            "SELECT webauthn.verify_assertion(
+                credential_id => $1,
+                credential_type => 'public-key'::webauthn.credential_type,
+                authenticator_data => $2,
+                client_data_json => $3,
+                signature => $4,
+                user_handle => $5
+            )",
+            &[&cred_id, &mutated_auth_data, &client_json, &signature, &user_handle],
+        );
+        assert_db_error_message(result, mutation.expected_error(), &format!("verify_assertion with {:?}", mutation))?;
+    }
+
+    println!("  Refused {} mutated assertions", assertion_mutations.len());
+
+    Ok(())
+}
+
+fn test_malformed_der_signatures(db: &mut TestDatabase) -> Result<()> {
+    let message = generate_challenge();
+
+    // Setup: Sign with each curve, keeping both the DER encoding and the fixed-width r || s
+    let p256_signature: p256::ecdsa::Signature =
+        p256::ecdsa::SigningKey::random(&mut rand::thread_rng()).sign(&message);
+    let p384_signature: p384::ecdsa::Signature =
+        p384::ecdsa::SigningKey::random(&mut rand::thread_rng()).sign(&message);
+    let p521_signature: p521::ecdsa::Signature =
+        p521::ecdsa::SigningKey::random(&mut rand::thread_rng()).sign(&message);
+
+    let signatures = [
+        ("secp256r1", 32, p256_signature.to_der().as_bytes().to_vec(), p256_signature.to_bytes().to_vec()),
+        ("secp384r1", 48, p384_signature.to_der().as_bytes().to_vec(), p384_signature.to_bytes().to_vec()),
+        ("secp521r1", 66, p521_signature.to_der().as_bytes().to_vec(), p521_signature.to_bytes().to_vec()),
+    ];
+
+    for (curve_name, width, der, raw) in &signatures {
+        // Test: The signature as encoded by DerSignature decodes to r || s
+        let decoded: Vec<u8> = db.client().query_one(
+            "SELECT webauthn.decode_asn1_der_signature($1, $2)",
+            &[der, curve_name],
+        ).with_context(|| format!("Valid {} DER signature should decode", curve_name))?.get(0);
+
+        if &decoded != raw {
+            anyhow::bail!("Decoded {} signature differs from r || s\n\n{}",
+                curve_name, format_string_diff("signature", &hex::encode(raw), &hex::encode(&decoded)));
+        }
+
+        // Test: Each mutation is refused with its error
+        for mutation in SignatureMutation::ALL {
+            let mutated = mutation.apply(der, *width)?;
+            let result = db.client().query_one(
+                "SELECT webauthn.decode_asn1_der_signature($1, $2)",
+                &[&mutated, curve_name],
+            );
+            assert_db_error_message(result, mutation.expected_error(),
+                &format!("{} signature with {:?} ({})", curve_name, mutation, hex::encode(&mutated)))?;
+        }
+    }
+
+    println!("  Refused {} malformed DER signatures for each of {} curves", SignatureMutation::ALL.len(), signatures.len());
+
+    Ok(())
+}
+
+fn test_invalid_cose_keys(db: &mut TestDatabase) -> Result<()> {
+    let user_id = generate_user_id();
+    let user_name = "invalid.cose.key@example.com";
+    let rp_id = "localhost";
+
+    // Test: Each invalid COSE_Key variant is refused by store_credential
+    for variant in CoseKeyVariant::INVALID {
+        let mut authenticator = SoftwareAuthenticator::new().with_cose_key_variant(variant);
+
+        let challenge = generate_challenge();
+        db.client().execute(
+            "SELECT webauthn.init_credential(
+                challenge => $1,
+                user_name => $2,
+                user_id => $3,
+                user_display_name => $4,
+                relying_party_name => $5,
+                relying_party_id => $6,
+                require_resident_key => false,
+                user_verification => 'discouraged'::webauthn.user_verification_requirement,
+                attestation => 'none'::webauthn.attestation_conveyance_preference,
+                timeout => '5 minutes'::interval
+            )",
+            &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Invalid COSE Key", &"Test Corp", &rp_id],
+        )?;
+
+        let (credential_id, _credential_type, attestation_object, client_data_json) =
+            authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+        let result = db.client().query_one(
+            "SELECT webauthn.store_credential(
+                credential_id => $1,
+                credential_type => 'public-key'::webauthn.credential_type,
+                attestation_object => $2,
+                client_data_json => $3
+            )",
+            &[&credential_id, &attestation_object, &client_data_json],
+        );
+        let expected_error = variant.expected_error()
+            .context("Invalid COSE key variant without expected error")?;
+        assert_db_error_message(result, expected_error, &format!("store_credential with {:?} COSE key", variant))?;
+    }
+
+    println!("  Refused {} invalid COSE keys", CoseKeyVariant::INVALID.len());
+
+    // Test: The valid COSE_Key is stored with its key type, algorithm and curve
+    let mut authenticator = SoftwareAuthenticator::new();
+    let challenge = generate_challenge();
+    db.client().execute(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => $4,
+            relying_party_name => $5,
+            relying_party_id => $6,
+            require_resident_key => false,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            attestation => 'none'::webauthn.attestation_conveyance_preference,
+            timeout => '5 minutes'::interval
+        )",
+        &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Invalid COSE Key", &"Test Corp", &rp_id],
+    )?;
+
+    let (credential_id, _credential_type, attestation_object, client_data_json) =
+        authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+    db.client().execute(
+        "SELECT webauthn.store_credential(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            attestation_object => $2,
+            client_data_json => $3
+        )",
+        &[&credential_id, &attestation_object, &client_data_json],
+    )?;
+
+    let row = db.client().query_one(
+        "SELECT kty, alg, crv FROM webauthn.credentials WHERE credential_id = webauthn.base64url_decode($1)",
+        &[&credential_id],
+    )?;
+    let (kty, alg, crv): (i32, i32, i32) = (row.get(0), row.get(1), row.get(2));
+    if (kty, alg, crv) != (2, -7, 1) {
+        anyhow::bail!("Stored credential should have kty 2, alg -7, crv 1, but has kty {}, alg {}, crv {}", kty, alg, crv);
+    }
+
+    Ok(())
+}
+
+fn test_cross_origin_policy(db: &mut TestDatabase) -> Result<()> {
+    let mut authenticator = SoftwareAuthenticator::new().with_top_origin("https://shop.example");
+
+    let user_id = generate_user_id();
+    let user_name = "cross.origin@example.com";
+
+    // Test: A relying party doesn't allow cross-origin iframes by default
+    let challenge = generate_challenge();
+    db.client().execute(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => $4,
+            relying_party_name => $5,
+            relying_party_id => $6,
+            require_resident_key => false,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            attestation => 'none'::webauthn.attestation_conveyance_preference,
+            timeout => '5 minutes'::interval
+        )",
+        &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Cross Origin", &"Test Corp", &"localhost"],
+    )?;
+
+    let (credential_id, _credential_type, attestation_object, client_data_json) =
+        authenticator.create_credential(&challenge, &user_id, "localhost", false)?;
+
+    let result = db.client().query_one(
+        "SELECT webauthn.store_credential(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            attestation_object => $2,
+            client_data_json => $3
+        )",
+        &[&credential_id, &attestation_object, &client_data_json],
+    );
+    assert_db_error_message(result,
+        "new row for relation \"credentials\" violates check constraint \"cross_origin_allowed\"",
+        "store_credential in a cross-origin iframe")?;
+
+    // Setup: A relying party allowing cross-origin iframes of one top-level origin
+    let rp_id = "cross-origin.localhost";
+    db.client().execute(
+        "INSERT INTO webauthn.relying_parties (relying_party_id, relying_party_name, allow_cross_origin, top_origins)
+         VALUES ($1, 'Test Corp', TRUE, ARRAY['https://shop.example'])
+         ON CONFLICT (relying_party_id) DO UPDATE SET allow_cross_origin = EXCLUDED.allow_cross_origin, top_origins = EXCLUDED.top_origins",
+        &[&rp_id],
+    )?;
+
+    // Test: The credential is accepted from the allowed top-level origin
+    let challenge = generate_challenge();
+    db.client().execute(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => $4,
+            relying_party_name => $5,
+            relying_party_id => $6,
+            require_resident_key => false,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            attestation => 'none'::webauthn.attestation_conveyance_preference,
+            timeout => '5 minutes'::interval
+        )",
+        &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Cross Origin", &"Test Corp", &rp_id],
+    )?;
+
+    let (credential_id, _credential_type, attestation_object, client_data_json) =
+        authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+    db.client().execute(
+        "SELECT webauthn.store_credential(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            attestation_object => $2,
+            client_data_json => $3
+        )",
+        &[&credential_id, &attestation_object, &client_data_json],
+    ).context("store_credential from an allowed top-level origin should succeed")?;
+
+    let row = db.client().query_one(
+        "SELECT cross_origin, top_origin FROM webauthn.credentials WHERE credential_id = webauthn.base64url_decode($1)",
+        &[&credential_id],
+    )?;
+    let (cross_origin, top_origin): (Option<bool>, Option<String>) = (row.get(0), row.get(1));
+    if cross_origin != Some(true) || top_origin.as_deref() != Some("https://shop.example") {
+        anyhow::bail!("Stored credential should be cross-origin from https://shop.example, but has cross_origin {:?}, top_origin {:?}", cross_origin, top_origin);
+    }
+
+    // Test: Assertions are accepted from the allowed top-level origin only,
+    // and only if the challenge doesn't override the relying party's policy
+    let cases = [
+        (authenticator.clone(), None, None),
+        (authenticator.clone().with_top_origin("https://evil.example"), None, Some("new row for relation \"assertions\" violates check constraint \"top_origin_allowed\"")),
+        (authenticator, Some(false), Some("new row for relation \"assertions\" violates check constraint \"cross_origin_allowed\"")),
+    ];
+
+    for (mut assertion_authenticator, allow_cross_origin, expected_error) in cases {
+        let auth_challenge = generate_challenge();
+        db.client().execute(
+            "SELECT webauthn.get_credentials(
+                challenge => $1,
+                user_name => $2,
+                user_verification => 'discouraged'::webauthn.user_verification_requirement,
+                timeout => '5 minutes'::interval,
+                relying_party_id => $3,
+                allow_cross_origin => $4
+            )",
+            &[&auth_challenge.as_slice(), &user_name, &rp_id, &allow_cross_origin],
+        )?;
+
+        let (cred_id, _cred_type, auth_data, client_json, signature, user_handle) =
+            assertion_authenticator.create_assertion(&credential_id, &auth_challenge, rp_id, false)?;
+
+        let result = db.client().query_one(
+            "SELECT webauthn.verify_assertion(
+                credential_id => $1,
+                credential_type => 'public-key'::webauthn.credential_type,
+                authenticator_data => $2,
+                client_data_json => $3,
+                signature => $4,
+                user_handle => $5
+            )",
+            &[&cred_id, &auth_data, &client_json, &signature, &user_handle],
+        );
+        let description = format!("verify_assertion from {:?} with allow_cross_origin {:?}",
+            assertion_authenticator.top_origin(), allow_cross_origin);
+        match expected_error {
+            Some(expected_error) => assert_db_error_message(result, expected_error, &description)?,
+            None => {
+                let verified_user_id: Option<Vec<u8>> = result
+                    .with_context(|| format!("{} should succeed", description))?
+                    .get(0);
+                if verified_user_id.as_deref() != Some(user_id.as_slice()) {
+                    anyhow::bail!("{} should return the user_id", description);
+                }
+            }
+        }
+    }
+
+    println!("  Enforced the cross-origin policy for credentials and assertions");
+
+    Ok(())
+}
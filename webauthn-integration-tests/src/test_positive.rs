@@ -3,6 +3,7 @@ use crate::database::TestDatabase;
 use crate::utils::{format_test_result, generate_challenge, generate_user_id};
 use crate::verifier;
 use anyhow::{Context, Result};
+use base64_url as base64url;
 use serde_json::Value as JsonValue;
 
 pub fn run_positive_tests(db: &mut TestDatabase, _debug: bool) -> Result<()> {
@@ -63,6 +64,58 @@ pub fn run_positive_tests(db: &mut TestDatabase, _debug: bool) -> Result<()> {
         }
     }
 
+    // Test 5: PublicKeyCredential JSON entry points
+    match test_public_key_credential_json(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("PublicKeyCredential JSON entry points", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("PublicKeyCredential JSON entry points", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
+    // Test 6: PRF extension
+    match test_prf_extension(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("PRF extension (hmac-secret)", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("PRF extension (hmac-secret)", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
+    // Test 7: AppID extension for imported U2F registrations
+    match test_appid_extension(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("AppID extension (imported U2F credential)", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("AppID extension (imported U2F credential)", false));
+            eprintln!("  Error: {}", e);
+            failed += 1;
+        }
+    }
+
+    // Test 8: Public key export
+    match test_public_key_export(db) {
+        Ok(_) => {
+            println!("{}", format_test_result("Public key export", true));
+            passed += 1;
+        }
+        Err(e) => {
+            println!("{}", format_test_result("Public key export", false));
+            eprintln!("  Error: {:?}", e);
+            failed += 1;
+        }
+    }
+
     println!("\nPositive Tests: {} passed, {} failed", passed, failed);
 
     if failed > 0 {
@@ -902,4 +955,399 @@ fn test_timeout_validation(db: &mut TestDatabase) -> Result<()> {
     assert!(result.is_err(), "11 minute timeout should fail");
 
     Ok(())
-}
\ No newline at end of file
+}
+
+fn test_public_key_credential_json(db: &mut TestDatabase) -> Result<()> {
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    let challenge = generate_challenge();
+    let user_id = generate_user_id();
+    let user_name = format!("json.user.{}@example.com", rand::random::<u32>());
+    let rp_id = "localhost";
+
+    db.client().execute(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => 'JSON User',
+            relying_party_name => 'Test Corp',
+            relying_party_id => $4,
+            require_resident_key => false,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            attestation => 'none'::webauthn.attestation_conveyance_preference,
+            timeout => '5 minutes'::interval
+        )",
+        &[&challenge.as_slice(), &user_name.as_str(), &user_id.as_slice(), &rp_id],
+    )?;
+
+    let (cred_id, _cred_type, attest_obj, client_json) =
+        authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+    let registration_json = SoftwareAuthenticator::registration_response_json(&cred_id, &attest_obj, &client_json);
+
+    // Store via the text entry point inside a rolled back transaction,
+    // so that the JSON entry point can consume the same challenge afterwards
+    let stored_credential_sql = "SELECT to_jsonb(credentials) - 'credential_at'
+        FROM webauthn.credentials
+        WHERE credential_id = webauthn.base64url_decode($1)";
+
+    let mut transaction = db.client().transaction()?;
+    let text_user_id: Vec<u8> = transaction.query_one(
+        "SELECT webauthn.store_credential(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            attestation_object => $2,
+            client_data_json => $3,
+            client_extension_results => $4,
+            authenticator_attachment => $5::text::webauthn.authenticator_attachment
+        )",
+        &[&cred_id, &attest_obj, &client_json, &registration_json["clientExtensionResults"],
+          &registration_json["authenticatorAttachment"].as_str()],
+    ).context("store_credential with text arguments failed")?.get(0);
+    let text_credential: JsonValue = transaction.query_one(stored_credential_sql, &[&cred_id])?.get(0);
+    transaction.rollback()?;
+
+    let json_user_id: Vec<u8> = db.client().query_one(
+        "SELECT webauthn.store_credential(credential => $1)",
+        &[&registration_json],
+    ).context("store_credential with PublicKeyCredential JSON failed")?.get(0);
+    let json_credential: JsonValue = db.client().query_one(stored_credential_sql, &[&cred_id])?.get(0);
+
+    assert_eq!(text_user_id, user_id, "Text entry point should return the user ID");
+    assert_eq!(json_user_id, user_id, "JSON entry point should return the user ID");
+    if text_credential != json_credential {
+        anyhow::bail!("Stored credentials differ between entry points:\n  text: {}\n  json: {}", text_credential, json_credential);
+    }
+
+    let auth_challenge = generate_challenge();
+    db.client().execute(
+        "SELECT webauthn.get_credentials(
+            challenge => $1,
+            user_name => $2,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            timeout => '5 minutes'::interval,
+            relying_party_id => $3
+        )",
+        &[&auth_challenge.as_slice(), &user_name.as_str(), &rp_id],
+    )?;
+
+    let (cred_id, _cred_type, auth_data, client_json, signature, user_handle) =
+        authenticator.create_assertion(&cred_id, &auth_challenge, rp_id, false)?;
+    let authentication_json = SoftwareAuthenticator::authentication_response_json(
+        &cred_id, &auth_data, &client_json, &signature, user_handle.as_deref(),
+    );
+
+    let mut transaction = db.client().transaction()?;
+    let text_user_id: Vec<u8> = transaction.query_one(
+        "SELECT webauthn.verify_assertion(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            authenticator_data => $2,
+            client_data_json => $3,
+            signature => $4,
+            user_handle => $5
+        )",
+        &[&cred_id, &auth_data, &client_json, &signature, &user_handle],
+    ).context("verify_assertion with text arguments failed")?.get(0);
+    transaction.rollback()?;
+
+    let json_user_id: Vec<u8> = db.client().query_one(
+        "SELECT webauthn.verify_assertion(credential => $1)",
+        &[&authentication_json],
+    ).context("verify_assertion with PublicKeyCredential JSON failed")?.get(0);
+
+    assert_eq!(text_user_id, user_id, "Text entry point should verify the assertion");
+    assert_eq!(json_user_id, user_id, "JSON entry point should verify the assertion");
+
+    Ok(())
+}
+
+fn test_prf_extension(db: &mut TestDatabase) -> Result<()> {
+    let mut authenticator = SoftwareAuthenticator::new().with_hmac_secret();
+
+    let challenge = generate_challenge();
+    let user_id = generate_user_id();
+    let user_name = format!("prf.user.{}@example.com", rand::random::<u32>());
+    let rp_id = "localhost";
+
+    let row = db.client().query_one(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => 'PRF User',
+            relying_party_name => 'Test Corp',
+            relying_party_id => $4,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            extensions => '{\"prf\": {}}'::jsonb
+        )",
+        &[&challenge.as_slice(), &user_name.as_str(), &user_id.as_slice(), &rp_id],
+    )?;
+    let creation_options: JsonValue = row.get(0);
+    if creation_options["publicKey"]["extensions"]["prf"] != serde_json::json!({}) {
+        anyhow::bail!("Creation options should request the prf extension: {}", creation_options);
+    }
+
+    let (cred_id, _cred_type, attest_obj, client_json) =
+        authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+    let mut registration_json = SoftwareAuthenticator::registration_response_json(&cred_id, &attest_obj, &client_json);
+    registration_json["clientExtensionResults"] = serde_json::json!({"prf": {"enabled": true}});
+
+    db.client().query_one(
+        "SELECT webauthn.store_credential(credential => $1)",
+        &[&registration_json],
+    ).context("store_credential with prf.enabled failed")?;
+
+    let row = db.client().query_one(
+        "SELECT prf_enabled, authenticator_extension_outputs
+        FROM webauthn.credentials
+        WHERE credential_id = webauthn.base64url_decode($1)",
+        &[&cred_id],
+    )?;
+    let prf_enabled: Option<bool> = row.get(0);
+    let authenticator_extension_outputs: Option<JsonValue> = row.get(1);
+    assert_eq!(prf_enabled, Some(true), "prf_enabled should be stored");
+    assert_eq!(
+        authenticator_extension_outputs,
+        Some(serde_json::json!({"hmac-secret": true})),
+        "hmac-secret should be reported in the authenticator data"
+    );
+
+    // Request a per-credential salt, with a different default salt for other credentials
+    let credential_salt = generate_challenge();
+    let default_salt = generate_challenge();
+    let extensions = serde_json::json!({
+        "prf": {
+            "eval": {"first": base64url::encode(&default_salt)},
+            "evalByCredential": {
+                cred_id.as_str(): {"first": base64url::encode(&credential_salt)}
+            }
+        }
+    });
+
+    let auth_challenge = generate_challenge();
+    let row = db.client().query_one(
+        "SELECT webauthn.get_credentials(
+            challenge => $1,
+            user_name => $2,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            relying_party_id => $3,
+            extensions => $4
+        )",
+        &[&auth_challenge.as_slice(), &user_name.as_str(), &rp_id, &extensions],
+    )?;
+    let request_options: JsonValue = row.get(0);
+    if request_options["publicKey"]["extensions"] != extensions {
+        anyhow::bail!("Request options should contain the prf salts: {}", request_options);
+    }
+
+    // The salts are persisted on the assertion challenge
+    let challenge_extensions: Option<JsonValue> = db.client().query_one(
+        "SELECT extensions FROM webauthn.assertion_challenges WHERE challenge = $1",
+        &[&auth_challenge.as_slice()],
+    )?.get(0);
+    assert_eq!(challenge_extensions, Some(extensions), "The prf salts should be stored on the assertion challenge");
+
+    // The client passes the salt of evalByCredential for the credential to the authenticator
+    let authenticator_salt = base64url::decode(
+        request_options["publicKey"]["extensions"]["prf"]["evalByCredential"][cred_id.as_str()]["first"]
+            .as_str()
+            .context("evalByCredential salt for the credential missing")?,
+    )?;
+    assert_eq!(authenticator_salt, credential_salt, "The per-credential salt should be sent for the credential");
+
+    let (cred_id, _cred_type, auth_data, client_json, signature, user_handle) =
+        authenticator.create_assertion(&cred_id, &auth_challenge, rp_id, false)?;
+    let authentication_json = SoftwareAuthenticator::authentication_response_json(
+        &cred_id, &auth_data, &client_json, &signature, user_handle.as_deref(),
+    );
+
+    db.client().query_one(
+        "SELECT webauthn.verify_assertion(credential => $1)",
+        &[&authentication_json],
+    ).context("verify_assertion with prf salts failed")?;
+
+    // The assertion records the salt the authenticator evaluated, not the default one
+    let prf_salts: Option<JsonValue> = db.client().query_one(
+        "SELECT prf_salts FROM webauthn.assertions WHERE challenge = $1",
+        &[&auth_challenge.as_slice()],
+    )?.get(0);
+    assert_eq!(
+        prf_salts,
+        Some(serde_json::json!({"first": base64url::encode(&authenticator_salt)})),
+        "The salt sent to the authenticator should be stored with the assertion"
+    );
+
+    // hmac-secret outputs are deterministic for the credential and the stored salt
+    let output = authenticator.evaluate_prf(&cred_id, &authenticator_salt)?;
+    let stored_salt = base64url::decode(
+        prf_salts.as_ref().and_then(|salts| salts["first"].as_str()).context("prf_salts.first missing")?,
+    )?;
+    assert_eq!(output, authenticator.evaluate_prf(&cred_id, &stored_salt)?, "PRF output should be deterministic");
+    assert_ne!(output, authenticator.evaluate_prf(&cred_id, &default_salt)?, "Different salts should give different outputs");
+
+    // Each credential has its own secret, so another credential gives a different output for the same salt
+    let (other_cred_id, _, _, _) = authenticator.create_credential(&generate_challenge(), &user_id, rp_id, false)?;
+    assert_ne!(output, authenticator.evaluate_prf(&other_cred_id, &stored_salt)?, "Different credentials should give different outputs");
+
+    Ok(())
+}
+
+fn test_appid_extension(db: &mut TestDatabase) -> Result<()> {
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    let user_id = generate_user_id();
+    let user_name = format!("u2f.user.{}@example.com", rand::random::<u32>());
+    let rp_id = "localhost";
+    let appid = "https://localhost/u2f/app-id.json";
+
+    // A registration made with the legacy U2F API, scoped to the AppID
+    let (key_handle, public_key) = authenticator.register_u2f(&user_id)?;
+
+    db.client().execute(
+        "SELECT webauthn.import_u2f_credential(
+            key_handle => $1,
+            public_key => $2,
+            appid => $3,
+            user_name => $4,
+            user_id => $5,
+            user_display_name => 'U2F User',
+            relying_party_name => 'Test Corp',
+            relying_party_id => $6
+        )",
+        &[&key_handle, &public_key, &appid, &user_name.as_str(), &user_id.as_slice(), &rp_id],
+    ).context("import_u2f_credential failed")?;
+
+    let auth_challenge = generate_challenge();
+    let row = db.client().query_one(
+        "SELECT webauthn.get_credentials(
+            challenge => $1,
+            user_name => $2,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            relying_party_id => $3
+        )",
+        &[&auth_challenge.as_slice(), &user_name.as_str(), &rp_id],
+    )?;
+    let request_options: JsonValue = row.get(0);
+    if request_options["publicKey"]["extensions"]["appid"] != appid {
+        anyhow::bail!("Request options should contain the AppID: {}", request_options);
+    }
+
+    let (cred_id, _cred_type, auth_data, client_json, signature, user_handle) =
+        authenticator.create_assertion_with_appid(&key_handle, &auth_challenge, appid)?;
+    let mut authentication_json = SoftwareAuthenticator::authentication_response_json(
+        &cred_id, &auth_data, &client_json, &signature, user_handle.as_deref(),
+    );
+
+    // The rpIdHash is the hash of the AppID, which is only accepted if the client reports using it
+    let mut transaction = db.client().transaction()?;
+    let result = transaction.query_one(
+        "SELECT webauthn.verify_assertion(credential => $1)",
+        &[&authentication_json],
+    );
+    transaction.rollback()?;
+    if result.is_ok() {
+        anyhow::bail!("Assertion with an AppID rpIdHash should fail without clientExtensionResults.appid");
+    }
+
+    authentication_json["clientExtensionResults"] = serde_json::json!({"appid": true});
+    let verified_user_id: Vec<u8> = db.client().query_one(
+        "SELECT webauthn.verify_assertion(credential => $1)",
+        &[&authentication_json],
+    ).context("verify_assertion with clientExtensionResults.appid failed")?.get(0);
+    assert_eq!(verified_user_id, user_id, "Assertion should be verified for the imported credential");
+
+    Ok(())
+}
+
+fn test_public_key_export(db: &mut TestDatabase) -> Result<()> {
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    let challenge = generate_challenge();
+    let user_id = generate_user_id();
+    let user_name = "public.key.export@example.com";
+    let rp_id = "localhost";
+
+    db.client().execute(
+        "SELECT webauthn.init_credential(
+            challenge => $1,
+            user_name => $2,
+            user_id => $3,
+            user_display_name => $4,
+            relying_party_name => $5,
+            relying_party_id => $6,
+            require_resident_key => false,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            attestation => 'none'::webauthn.attestation_conveyance_preference,
+            timeout => '5 minutes'::interval
+        )",
+        &[&challenge.as_slice(), &user_name, &user_id.as_slice(), &"Public Key Export", &"Test Corp", &rp_id],
+    )?;
+
+    let (credential_id, _credential_type, attestation_object, client_data_json) =
+        authenticator.create_credential(&challenge, &user_id, rp_id, false)?;
+
+    db.client().execute(
+        "SELECT webauthn.store_credential(
+            credential_id => $1,
+            credential_type => 'public-key'::webauthn.credential_type,
+            attestation_object => $2,
+            client_data_json => $3
+        )",
+        &[&credential_id, &attestation_object, &client_data_json],
+    )?;
+
+    // Load the public key from each export format
+    let row = db.client().query_one(
+        "SELECT
+            webauthn.credential_public_key_spki($1),
+            webauthn.credential_public_key_pem($1),
+            webauthn.credential_public_key_jwk($1)",
+        &[&credential_id],
+    )?;
+    let spki: Vec<u8> = row.get(0);
+    let pem: String = row.get(1);
+    let jwk: JsonValue = row.get(2);
+
+    if jwk["kid"] != credential_id.as_str() {
+        anyhow::bail!("JWK kid should be the credential ID: {}", jwk);
+    }
+
+    let exported_keys = [
+        ("SPKI", verifier::verifying_key_from_spki(&spki)?),
+        ("PEM", verifier::verifying_key_from_pem(&pem)?),
+        ("JWK", verifier::verifying_key_from_jwk(&jwk)?),
+    ];
+
+    let verifying_key = *authenticator.verifying_keys.get(&credential_id)
+        .context("VerifyingKey not found for credential")?;
+
+    let auth_challenge = generate_challenge();
+    db.client().execute(
+        "SELECT webauthn.get_credentials(
+            challenge => $1,
+            user_name => $2,
+            user_verification => 'discouraged'::webauthn.user_verification_requirement,
+            timeout => '5 minutes'::interval,
+            relying_party_id => $3
+        )",
+        &[&auth_challenge.as_slice(), &user_name, &rp_id],
+    )?;
+
+    let (_cred_id, _cred_type, auth_data, client_json, signature, _user_handle) =
+        authenticator.create_assertion(&credential_id, &auth_challenge, rp_id, false)?;
+
+    // Each exported key is the authenticator's key, and verifies the assertion on its own
+    for (format, exported_key) in &exported_keys {
+        if *exported_key != verifying_key {
+            anyhow::bail!("{} export differs from the authenticator's public key", format);
+        }
+        let verified = verifier::verify_assertion_signature(exported_key, &auth_data, &client_json, &signature)?;
+        if !verified {
+            anyhow::bail!("Assertion should verify with the {} export", format);
+        }
+        println!("  Verified assertion with {} export", format);
+    }
+
+    Ok(())
+}
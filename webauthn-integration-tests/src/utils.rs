@@ -75,6 +75,17 @@ pub fn assert_db_error(result: std::result::Result<postgres::Row, postgres::Erro
     }
 }
 
+/// Assert that a database operation fails with an error message starting with the given prefix
+pub fn assert_db_error_message(result: std::result::Result<postgres::Row, postgres::Error>, message_prefix: &str, test_description: &str) -> Result<()> {
+    match result {
+        Ok(_) => anyhow::bail!("{} should have failed but succeeded", test_description),
+        Err(e) => match e.as_db_error() {
+            Some(db_error) if db_error.message().starts_with(message_prefix) => Ok(()),
+            _ => anyhow::bail!("{} should have failed with \"{}\" but failed with: {:#}", test_description, message_prefix, e),
+        },
+    }
+}
+
 /// Assert that a database operation returns None/NULL
 #[allow(dead_code)]
 pub fn assert_returns_null(result: std::result::Result<postgres::Row, postgres::Error>, test_description: &str) -> Result<()> {
@@ -2,6 +2,9 @@ use anyhow::{Context, Result};
 use base64_url as base64url;
 use ecdsa::signature::Verifier;
 use p256::ecdsa::{Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+use p256::PublicKey;
+use serde_json::Value as JsonValue;
 use sha2::{Digest, Sha256};
 
 /// Verify a WebAuthn assertion signature using Rust crypto
@@ -34,4 +37,34 @@ pub fn verify_assertion_signature(
         Ok(()) => Ok(true),
         Err(_) => Ok(false),
     }
-}
\ No newline at end of file
+}
+
+/// Load a verifying key from the DER SubjectPublicKeyInfo
+/// returned by webauthn.credential_public_key_spki()
+pub fn verifying_key_from_spki(spki: &[u8]) -> Result<VerifyingKey> {
+    VerifyingKey::from_public_key_der(spki)
+        .context("Failed to parse SubjectPublicKeyInfo")
+}
+
+/// Load a verifying key from the PEM returned by webauthn.credential_public_key_pem()
+pub fn verifying_key_from_pem(pem: &str) -> Result<VerifyingKey> {
+    VerifyingKey::from_public_key_pem(pem)
+        .context("Failed to parse PEM public key")
+}
+
+/// Load a verifying key from the JWK returned by webauthn.credential_public_key_jwk()
+pub fn verifying_key_from_jwk(jwk: &JsonValue) -> Result<VerifyingKey> {
+    if jwk["alg"] != "ES256" {
+        anyhow::bail!("Unsupported JWK algorithm: {}", jwk["alg"]);
+    }
+    // The elliptic-curve crate refuses unknown members, such as kid, alg and use
+    let key_parameters = serde_json::json!({
+        "kty": jwk["kty"],
+        "crv": jwk["crv"],
+        "x": jwk["x"],
+        "y": jwk["y"],
+    });
+    let public_key = PublicKey::from_jwk_str(&key_parameters.to_string())
+        .context("Failed to parse JWK public key")?;
+    Ok(VerifyingKey::from(public_key))
+}